@@ -206,7 +206,7 @@ impl<'bump> PumpAmmFuzzContext<'bump> {
 mod fuzz_tests {
     use super::*;
     use anchor_lang::prelude::*;
-    use pump_v2_amm::instructions::set_parameters;
+    use pump_v2_amm::instructions::{execute_parameters, queue_parameters};
     use std::collections::BTreeMap;
 }
 
@@ -0,0 +1,389 @@
+//! Concentrated liquidity curve used by `ClPool`.
+//!
+//! Prices are stored as the square root of the quote per base price in Q64.64 fixed point, and
+//! liquidity is only active between the ticks of the positions providing it. Tick `i` is the
+//! price `1.0001^i`.
+use super::{mul_div_u256, to_u64, Rounding, BPS_DENOMINATOR};
+use crate::error::ErrorCode;
+use anchor_lang::prelude::*;
+
+/// Lowest tick a position or the pool price can reach.
+pub const MIN_TICK: i32 = -443_636;
+/// Highest tick a position or the pool price can reach.
+pub const MAX_TICK: i32 = 443_636;
+/// Square root price of `MIN_TICK`.
+pub const MIN_SQRT_PRICE_X64: u128 = 4_295_048_016;
+/// Square root price of `MAX_TICK`.
+pub const MAX_SQRT_PRICE_X64: u128 = 79_226_673_521_066_979_257_578_248_091;
+
+/// `1.0` in Q64.64.
+const Q64: u128 = 1 << 64;
+
+/// `1 / sqrt(1.0001)^(2^i)` in Q64.64, one factor per bit of the absolute tick.
+const TICK_FACTORS_X64: [u128; 19] = [
+    0xfffcb933bd6fad37,
+    0xfff97272373d4132,
+    0xfff2e50f5f656932,
+    0xffe5caca7e10e4e6,
+    0xffcb9843d60f6159,
+    0xff973b41fa98c081,
+    0xff2ea16466c96a38,
+    0xfe5dee046a99a2a8,
+    0xfcbe86c7900a88ae,
+    0xf987a7253ac41317,
+    0xf3392b0822b70005,
+    0xe7159475a2c29b74,
+    0xd097f3bdfd2022b8,
+    0xa9f746462d870fdf,
+    0x70d869a156d2a1b8,
+    0x31be135f97d08fd9,
+    0x9aa508b5b7a84e1,
+    0x5d6af8dedb8119,
+    0x2216e584f5fa,
+];
+
+/// Returns the square root price of a tick in Q64.64.
+pub fn sqrt_price_at_tick(tick: i32) -> Result<u128> {
+    require!(
+        (MIN_TICK..=MAX_TICK).contains(&tick),
+        ErrorCode::InvalidTickRange
+    );
+
+    // Both factors are below 2^64, so the product of two of them fits in a u128
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio = Q64;
+    for (bit, factor) in TICK_FACTORS_X64.iter().enumerate() {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * factor) >> 64;
+        }
+    }
+
+    // The factors price negative ticks, positive ones are their inverse
+    if tick > 0 {
+        Ok(u128::MAX / ratio)
+    } else {
+        Ok(ratio)
+    }
+}
+
+/// Returns the greatest tick whose square root price is at or below `sqrt_price_x64`.
+pub fn tick_at_sqrt_price(sqrt_price_x64: u128) -> Result<i32> {
+    require!(
+        (MIN_SQRT_PRICE_X64..=MAX_SQRT_PRICE_X64).contains(&sqrt_price_x64),
+        ErrorCode::InvalidSqrtPrice
+    );
+
+    // Binary search, `sqrt_price_at_tick` is strictly increasing
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        let middle = low + (high - low + 1) / 2;
+        if sqrt_price_at_tick(middle)? <= sqrt_price_x64 {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+    Ok(low)
+}
+
+/// Base amount held by `liquidity` between two square root prices.
+pub fn base_amount_delta(
+    sqrt_price_a_x64: u128,
+    sqrt_price_b_x64: u128,
+    liquidity: u128,
+    rounding: Rounding,
+) -> Result<u128> {
+    let (lower, upper) = ordered(sqrt_price_a_x64, sqrt_price_b_x64);
+    require_gt!(lower, 0, ErrorCode::InvalidSqrtPrice);
+    // liquidity * (upper - lower) / (upper * lower), in two steps to stay within 256 bits
+    let numerator = mul_div_u256(liquidity, upper - lower, upper, rounding)?;
+    mul_div_u256(numerator, Q64, lower, rounding)
+}
+
+/// Quote amount held by `liquidity` between two square root prices.
+pub fn quote_amount_delta(
+    sqrt_price_a_x64: u128,
+    sqrt_price_b_x64: u128,
+    liquidity: u128,
+    rounding: Rounding,
+) -> Result<u128> {
+    let (lower, upper) = ordered(sqrt_price_a_x64, sqrt_price_b_x64);
+    mul_div_u256(liquidity, upper - lower, Q64, rounding)
+}
+
+/// Base and quote amounts backing `liquidity` between two ticks at the current pool price.
+pub fn position_amounts(
+    sqrt_price_x64: u128,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+    rounding: Rounding,
+) -> Result<(u64, u64)> {
+    let sqrt_price_lower = sqrt_price_at_tick(tick_lower)?;
+    let sqrt_price_upper = sqrt_price_at_tick(tick_upper)?;
+
+    let (base_amount, quote_amount) = if sqrt_price_x64 <= sqrt_price_lower {
+        // Price below the range: the position is all base
+        (
+            base_amount_delta(sqrt_price_lower, sqrt_price_upper, liquidity, rounding)?,
+            0,
+        )
+    } else if sqrt_price_x64 >= sqrt_price_upper {
+        // Price above the range: the position is all quote
+        (
+            0,
+            quote_amount_delta(sqrt_price_lower, sqrt_price_upper, liquidity, rounding)?,
+        )
+    } else {
+        (
+            base_amount_delta(sqrt_price_x64, sqrt_price_upper, liquidity, rounding)?,
+            quote_amount_delta(sqrt_price_lower, sqrt_price_x64, liquidity, rounding)?,
+        )
+    };
+    Ok((to_u64(base_amount)?, to_u64(quote_amount)?))
+}
+
+/// Square root price reached after `amount` enters the pool, rounded against the trader.
+///
+/// Base in moves the price down, quote in moves it up.
+pub fn next_sqrt_price_from_input(
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount: u128,
+    base_in: bool,
+) -> Result<u128> {
+    require_gt!(liquidity, 0, ErrorCode::InsufficientLiquidity);
+    if amount == 0 {
+        return Ok(sqrt_price_x64);
+    }
+    if base_in {
+        // liquidity / (liquidity / sqrt_price + amount), rounded up
+        let denominator = mul_div_u256(liquidity, Q64, sqrt_price_x64, Rounding::Down)?
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        mul_div_u256(liquidity, Q64, denominator, Rounding::Up)
+    } else {
+        // sqrt_price + amount / liquidity, rounded down
+        Ok(sqrt_price_x64
+            .checked_add(mul_div_u256(amount, Q64, liquidity, Rounding::Down)?)
+            .ok_or(ErrorCode::MathOverflow)?)
+    }
+}
+
+/// Result of swapping within a single price range of constant liquidity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapStep {
+    /// Square root price at the end of the step.
+    pub sqrt_price_x64: u128,
+    /// Input consumed by the curve, excluding the fee.
+    pub amount_in: u128,
+    /// Output paid out by the curve.
+    pub amount_out: u128,
+    /// Input kept as the LP fee.
+    pub fee_amount: u128,
+}
+
+/// Swaps up to `amount_remaining` input tokens, fee included, from `sqrt_price_x64` towards
+/// `sqrt_price_target_x64`, using `liquidity` for the whole step.
+///
+/// The direction follows from the prices: a lower target means base in, a higher one quote in.
+pub fn compute_swap_step(
+    sqrt_price_x64: u128,
+    sqrt_price_target_x64: u128,
+    liquidity: u128,
+    amount_remaining: u128,
+    fee_bps: u64,
+) -> Result<SwapStep> {
+    // Nothing to trade against, the price moves freely to the target
+    if liquidity == 0 {
+        return Ok(SwapStep {
+            sqrt_price_x64: sqrt_price_target_x64,
+            amount_in: 0,
+            amount_out: 0,
+            fee_amount: 0,
+        });
+    }
+
+    let base_in = sqrt_price_target_x64 < sqrt_price_x64;
+    let fee_bps = fee_bps as u128;
+    let remainder_bps = BPS_DENOMINATOR
+        .checked_sub(fee_bps)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let amount_remaining_less_fee = mul_div_u256(
+        amount_remaining,
+        remainder_bps,
+        BPS_DENOMINATOR,
+        Rounding::Down,
+    )?;
+
+    let amount_in_to_target =
+        input_delta(sqrt_price_x64, sqrt_price_target_x64, liquidity, base_in)?;
+    let next_sqrt_price_x64 = if amount_remaining_less_fee >= amount_in_to_target {
+        sqrt_price_target_x64
+    } else {
+        next_sqrt_price_from_input(
+            sqrt_price_x64,
+            liquidity,
+            amount_remaining_less_fee,
+            base_in,
+        )?
+    };
+
+    let reached_target = next_sqrt_price_x64 == sqrt_price_target_x64;
+    let amount_in = if reached_target {
+        amount_in_to_target
+    } else {
+        input_delta(sqrt_price_x64, next_sqrt_price_x64, liquidity, base_in)?
+    };
+    let amount_out = if base_in {
+        quote_amount_delta(
+            next_sqrt_price_x64,
+            sqrt_price_x64,
+            liquidity,
+            Rounding::Down,
+        )?
+    } else {
+        base_amount_delta(
+            sqrt_price_x64,
+            next_sqrt_price_x64,
+            liquidity,
+            Rounding::Down,
+        )?
+    };
+
+    // A step that stops short of its target consumes everything, the rest is the fee
+    let fee_amount = if reached_target {
+        mul_div_u256(amount_in, fee_bps, remainder_bps, Rounding::Up)?
+    } else {
+        amount_remaining
+            .checked_sub(amount_in)
+            .ok_or(ErrorCode::MathOverflow)?
+    };
+
+    Ok(SwapStep {
+        sqrt_price_x64: next_sqrt_price_x64,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+/// Fee growth per unit of liquidity earned by `fee_amount`, in Q64.64.
+pub fn fee_growth_delta(fee_amount: u128, liquidity: u128) -> Result<u128> {
+    if liquidity == 0 {
+        return Ok(0);
+    }
+    mul_div_u256(fee_amount, Q64, liquidity, Rounding::Down)
+}
+
+/// Fees earned by `liquidity` over a Q64.64 fee growth delta.
+pub fn fees_earned(liquidity: u128, fee_growth_delta_x64: u128) -> Result<u64> {
+    to_u64(mul_div_u256(
+        liquidity,
+        fee_growth_delta_x64,
+        Q64,
+        Rounding::Down,
+    )?)
+}
+
+/// Applies a signed liquidity delta.
+pub fn add_liquidity_delta(liquidity: u128, delta: i128) -> Result<u128> {
+    let result = if delta < 0 {
+        liquidity.checked_sub(delta.unsigned_abs())
+    } else {
+        liquidity.checked_add(delta as u128)
+    };
+    Ok(result.ok_or(ErrorCode::MathOverflow)?)
+}
+
+/// Input needed to move the price between two square root prices, rounded up.
+fn input_delta(from_x64: u128, to_x64: u128, liquidity: u128, base_in: bool) -> Result<u128> {
+    if base_in {
+        base_amount_delta(to_x64, from_x64, liquidity, Rounding::Up)
+    } else {
+        quote_amount_delta(from_x64, to_x64, liquidity, Rounding::Up)
+    }
+}
+
+fn ordered(a: u128, b: u128) -> (u128, u128) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqrt_price_at_tick_bounds() {
+        assert_eq!(sqrt_price_at_tick(0).unwrap(), Q64);
+        assert_eq!(sqrt_price_at_tick(MIN_TICK).unwrap(), MIN_SQRT_PRICE_X64);
+        assert_eq!(sqrt_price_at_tick(MAX_TICK).unwrap(), MAX_SQRT_PRICE_X64);
+        assert!(sqrt_price_at_tick(MIN_TICK - 1).is_err());
+        assert!(sqrt_price_at_tick(MAX_TICK + 1).is_err());
+    }
+
+    #[test]
+    fn test_tick_at_sqrt_price_round_trip() {
+        for tick in [MIN_TICK, -100_000, -1, 0, 1, 64, 100_000, MAX_TICK] {
+            let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(tick_at_sqrt_price(sqrt_price).unwrap(), tick);
+            if tick < MAX_TICK {
+                assert_eq!(tick_at_sqrt_price(sqrt_price + 1).unwrap(), tick);
+            }
+        }
+    }
+
+    #[test]
+    fn test_position_amounts_by_range() {
+        let liquidity = 1_000_000_000u128;
+        let sqrt_price = sqrt_price_at_tick(0).unwrap();
+        // In range: both tokens, about equal at price 1
+        let (base, quote) =
+            position_amounts(sqrt_price, -100, 100, liquidity, Rounding::Up).unwrap();
+        assert!(base > 0 && quote > 0);
+        assert!(base.abs_diff(quote) <= 1);
+        // Below and above the range: a single token
+        let (base, quote) =
+            position_amounts(sqrt_price, 100, 200, liquidity, Rounding::Up).unwrap();
+        assert!(base > 0 && quote == 0);
+        let (base, quote) =
+            position_amounts(sqrt_price, -200, -100, liquidity, Rounding::Up).unwrap();
+        assert!(base == 0 && quote > 0);
+    }
+
+    #[test]
+    fn test_swap_step_stops_at_target() {
+        let sqrt_price = sqrt_price_at_tick(0).unwrap();
+        let target = sqrt_price_at_tick(-10).unwrap();
+        let step =
+            compute_swap_step(sqrt_price, target, 1_000_000_000, u64::MAX as u128, 30).unwrap();
+        assert_eq!(step.sqrt_price_x64, target);
+        assert!(step.amount_out < step.amount_in);
+        assert!(step.fee_amount > 0);
+    }
+
+    #[test]
+    fn test_swap_step_consumes_input() {
+        let sqrt_price = sqrt_price_at_tick(0).unwrap();
+        let target = sqrt_price_at_tick(10_000).unwrap();
+        let amount = 1_000_000u128;
+        let step = compute_swap_step(sqrt_price, target, 1_000_000_000_000, amount, 30).unwrap();
+        assert!(step.sqrt_price_x64 > sqrt_price && step.sqrt_price_x64 < target);
+        assert_eq!(step.amount_in + step.fee_amount, amount);
+        // Close to a 1:1 price, the output is the input minus the fee and a little slippage
+        assert!(step.amount_out < amount && step.amount_out > amount * 99 / 100);
+    }
+
+    #[test]
+    fn test_swap_step_without_liquidity() {
+        let sqrt_price = sqrt_price_at_tick(0).unwrap();
+        let target = sqrt_price_at_tick(50).unwrap();
+        let step = compute_swap_step(sqrt_price, target, 0, 1_000, 30).unwrap();
+        assert_eq!(step.sqrt_price_x64, target);
+        assert_eq!(step.amount_in + step.amount_out + step.fee_amount, 0);
+    }
+}
@@ -0,0 +1,297 @@
+//! Constant product (x * y = k) curve used by every xyk pool.
+use super::{mul_div, to_u64, Rounding};
+use crate::error::ErrorCode;
+use anchor_lang::prelude::*;
+use num_integer::Roots;
+
+/// Calculates the required quote amount to obtain a specified amount of base tokens.
+///
+/// # Parameters:
+/// - `base_amount`: Desired amount of base tokens.
+/// - `base_reserve`: Current reserve of the base token in the AMM.
+/// - `quote_reserve`: Current reserve of the quote token in the AMM.
+///
+/// # Returns:
+/// - Result containing the quote amount, rounded up, or an error.
+pub fn buy_quote(base_amount: u64, base_reserve: u64, quote_reserve: u64) -> Result<u64> {
+    require_gt!(base_reserve, base_amount, ErrorCode::InsufficientLiquidity);
+    to_u64(mul_div(
+        base_amount as u128,
+        quote_reserve as u128,
+        (base_reserve - base_amount) as u128,
+        Rounding::Up,
+    )?)
+}
+
+/// Calculates the amount of base tokens obtained for spending a specified amount of quote tokens.
+///
+/// # Parameters:
+/// - `quote_amount`: Amount of quote tokens the user spends.
+/// - `base_reserve`: Current reserve of the base token in the AMM.
+/// - `quote_reserve`: Current reserve of the quote token in the AMM.
+///
+/// # Returns:
+/// - Result containing the base amount, rounded down, or an error.
+pub fn buy_exact_in_quote(quote_amount: u64, base_reserve: u64, quote_reserve: u64) -> Result<u64> {
+    to_u64(mul_div(
+        quote_amount as u128,
+        base_reserve as u128,
+        (quote_reserve as u128)
+            .checked_add(quote_amount as u128)
+            .ok_or(ErrorCode::MathOverflow)?,
+        Rounding::Down,
+    )?)
+}
+
+/// Calculates the amount of quote tokens received for selling a given amount of base tokens.
+///
+/// # Parameters:
+/// - `base_amount`: The amount of base tokens being sold by the user.
+/// - `base_reserve`: Current reserve of the base token in the AMM.
+/// - `quote_reserve`: Current reserve of the quote token in the AMM.
+///
+/// # Returns:
+/// - Result containing the quote amount, rounded down, or an error.
+pub fn sell_quote(base_amount: u64, base_reserve: u64, quote_reserve: u64) -> Result<u64> {
+    to_u64(mul_div(
+        base_amount as u128,
+        quote_reserve as u128,
+        (base_reserve as u128)
+            .checked_add(base_amount as u128)
+            .ok_or(ErrorCode::MathOverflow)?,
+        Rounding::Down,
+    )?)
+}
+
+/// Calculates the amount of base tokens that must be sold to receive a given amount of quote tokens.
+///
+/// # Parameters:
+/// - `quote_amount`: The amount of quote tokens the user wants to receive.
+/// - `base_reserve`: Current reserve of the base token in the AMM.
+/// - `quote_reserve`: Current reserve of the quote token in the AMM.
+///
+/// # Returns:
+/// - Result containing the base amount, rounded up, or an error.
+pub fn sell_exact_out_quote(
+    quote_amount: u64,
+    base_reserve: u64,
+    quote_reserve: u64,
+) -> Result<u64> {
+    require_gt!(
+        quote_reserve,
+        quote_amount,
+        ErrorCode::InsufficientLiquidity
+    );
+    to_u64(mul_div(
+        quote_amount as u128,
+        base_reserve as u128,
+        (quote_reserve - quote_amount) as u128,
+        Rounding::Up,
+    )?)
+}
+
+/// Calculates the number of shares a user will receive for their liquidity.
+///
+/// # Parameters:
+/// - `base_token_amount`: Amount of the base token the user wants to deposit.
+/// - `quote_token_amount`: Amount of the quote token the user wants to deposit.
+/// - `base_reserve`: Current reserve of the base token in the AMM.
+/// - `quote_reserve`: Current reserve of the quote token in the AMM.
+/// - `total_shares`: Current total number of shares in the AMM.
+///
+/// # Returns:
+/// - Result containing the number of shares, rounded down, or an error.
+pub fn calculate_shares(
+    base_token_amount: u64,
+    quote_token_amount: u64,
+    base_reserve: u64,
+    quote_reserve: u64,
+    total_shares: u64,
+) -> Result<u64> {
+    if total_shares != 0 {
+        // Calculate amount of LP tokens as a fraction of existing reserves
+        let base_token_share = mul_div(
+            base_token_amount as u128,
+            total_shares as u128,
+            base_reserve as u128,
+            Rounding::Down,
+        )?;
+        let quote_token_share = mul_div(
+            quote_token_amount as u128,
+            total_shares as u128,
+            quote_reserve as u128,
+            Rounding::Down,
+        )?;
+        to_u64(base_token_share.min(quote_token_share))
+    } else {
+        // Initialize shares when there's no existing liquidity
+        let initial_shares = (base_token_amount as u128)
+            .checked_mul(quote_token_amount as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            .sqrt();
+        to_u64(initial_shares)
+    }
+}
+
+/// Calculates the largest deposit within the given bounds that is in the ratio of the reserves,
+/// so no side is deposited beyond what `calculate_shares` credits. An empty pool takes both
+/// amounts, they set its price.
+///
+/// # Parameters:
+/// - `max_base_amount`: Most base tokens the user deposits.
+/// - `max_quote_amount`: Most quote tokens the user deposits.
+/// - `base_reserve`: Current reserve of the base token in the AMM.
+/// - `quote_reserve`: Current reserve of the quote token in the AMM.
+///
+/// # Returns:
+/// - Result containing the tuple of base and quote tokens to deposit, the side derived from the
+///   other rounded up, or an error.
+pub fn deposit_quote(
+    max_base_amount: u64,
+    max_quote_amount: u64,
+    base_reserve: u64,
+    quote_reserve: u64,
+) -> Result<(u64, u64)> {
+    if base_reserve == 0 || quote_reserve == 0 {
+        return Ok((max_base_amount, max_quote_amount));
+    }
+    let quote_amount = mul_div(
+        max_base_amount as u128,
+        quote_reserve as u128,
+        base_reserve as u128,
+        Rounding::Up,
+    )?;
+    if quote_amount <= max_quote_amount as u128 {
+        return Ok((max_base_amount, quote_amount as u64));
+    }
+    let base_amount = mul_div(
+        max_quote_amount as u128,
+        base_reserve as u128,
+        quote_reserve as u128,
+        Rounding::Up,
+    )?;
+    Ok((to_u64(base_amount)?, max_quote_amount))
+}
+
+/// Calculates the amount of base and quote tokens proportional to the shares being removed.
+/// LP fees are kept in the reserves, so the returned amounts include the shares' part of the accrued fees.
+///
+/// # Parameters:
+/// - `shares`: Number of liquidity shares being removed.
+/// - `base_reserve`: Current reserve of the base token in the AMM.
+/// - `quote_reserve`: Current reserve of the quote token in the AMM.
+/// - `total_shares`: Current total number of shares in the AMM.
+///
+/// # Returns:
+/// - Result containing the tuple of base and quote tokens, each rounded down, or an error.
+pub fn remove_quote(
+    shares: u64,
+    base_reserve: u64,
+    quote_reserve: u64,
+    total_shares: u64,
+) -> Result<(u64, u64)> {
+    require_gte!(
+        total_shares,
+        shares,
+        ErrorCode::LiquidityRemovalExceedsShares
+    );
+    let base_amount = mul_div(
+        shares as u128,
+        base_reserve as u128,
+        total_shares as u128,
+        Rounding::Down,
+    )?;
+    let quote_amount = mul_div(
+        shares as u128,
+        quote_reserve as u128,
+        total_shares as u128,
+        Rounding::Down,
+    )?;
+    Ok((to_u64(base_amount)?, to_u64(quote_amount)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One billion tokens with 9 decimals on each side
+    const LARGE_RESERVE: u64 = 1_000_000_000_000_000_000;
+
+    #[test]
+    fn test_large_reserves_do_not_overflow() {
+        let quote = buy_quote(1_000_000_000_000, LARGE_RESERVE, LARGE_RESERVE).unwrap();
+        assert!(quote > 1_000_000_000_000);
+        let quote = sell_quote(1_000_000_000_000, LARGE_RESERVE, LARGE_RESERVE).unwrap();
+        assert!(quote < 1_000_000_000_000);
+        let shares = calculate_shares(
+            LARGE_RESERVE,
+            LARGE_RESERVE,
+            LARGE_RESERVE,
+            LARGE_RESERVE,
+            LARGE_RESERVE,
+        )
+        .unwrap();
+        assert_eq!(shares, LARGE_RESERVE);
+        let (base, quote) = remove_quote(
+            LARGE_RESERVE / 2,
+            LARGE_RESERVE,
+            LARGE_RESERVE,
+            LARGE_RESERVE,
+        )
+        .unwrap();
+        assert_eq!((base, quote), (LARGE_RESERVE / 2, LARGE_RESERVE / 2));
+    }
+
+    #[test]
+    fn test_initial_shares_use_u128() {
+        assert_eq!(
+            calculate_shares(LARGE_RESERVE, LARGE_RESERVE, 0, 0, 0).unwrap(),
+            LARGE_RESERVE
+        );
+    }
+
+    #[test]
+    fn test_rounding_favors_pool() {
+        // 10 * 1000 / (1000 - 10) = 10.10..
+        assert_eq!(buy_quote(10, 1_000, 1_000).unwrap(), 11);
+        // 10 * 1000 / (1000 + 10) = 9.90..
+        assert_eq!(sell_quote(10, 1_000, 1_000).unwrap(), 9);
+        assert_eq!(buy_exact_in_quote(10, 1_000, 1_000).unwrap(), 9);
+        assert_eq!(sell_exact_out_quote(10, 1_000, 1_000).unwrap(), 11);
+        assert_eq!(remove_quote(1, 10, 10, 3).unwrap(), (3, 3));
+    }
+
+    #[test]
+    fn test_deposit_quote_keeps_reserve_ratio() {
+        // The quote bound is the binding one
+        assert_eq!(
+            deposit_quote(100_000, 100_000, 100_000_000, 1_000_000_000).unwrap(),
+            (10_000, 100_000)
+        );
+        // The base bound is the binding one
+        assert_eq!(
+            deposit_quote(1_000, 100_000, 100_000_000, 1_000_000_000).unwrap(),
+            (1_000, 10_000)
+        );
+        // The derived side is rounded up
+        assert_eq!(deposit_quote(10, 10, 3, 1).unwrap(), (10, 4));
+        // An empty pool takes both amounts
+        assert_eq!(deposit_quote(10, 20, 0, 0).unwrap(), (10, 20));
+    }
+
+    #[test]
+    fn test_exact_in_and_exact_out_agree() {
+        let (base_reserve, quote_reserve) = (100_000_000, 1_000_000_000);
+        let quote_in = buy_quote(100_000, base_reserve, quote_reserve).unwrap();
+        assert!(buy_exact_in_quote(quote_in, base_reserve, quote_reserve).unwrap() >= 100_000);
+        let base_in = sell_exact_out_quote(100_000, base_reserve, quote_reserve).unwrap();
+        assert!(sell_quote(base_in, base_reserve, quote_reserve).unwrap() >= 100_000);
+    }
+
+    #[test]
+    fn test_output_exceeding_reserves_fails() {
+        assert!(buy_quote(1_000, 1_000, 1_000).is_err());
+        assert!(sell_exact_out_quote(1_000, 1_000, 1_000).is_err());
+        assert!(remove_quote(11, 10, 10, 10).is_err());
+    }
+}
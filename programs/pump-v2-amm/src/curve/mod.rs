@@ -0,0 +1,456 @@
+//! Pool math shared by the swap and liquidity instructions.
+//!
+//! Every calculation is done in `u128` with checked operations and fails with
+//! `ErrorCode::MathOverflow` instead of wrapping or panicking. Results are always rounded in the
+//! pool's favor: amounts paid out by the pool round down, amounts paid in round up.
+use crate::error::ErrorCode;
+use anchor_lang::prelude::*;
+use num_integer::Roots;
+
+pub mod concentrated;
+pub mod constant_product;
+pub mod stable;
+
+pub use constant_product::*;
+
+/// Denominator of every basis point value.
+pub const BPS_DENOMINATOR: u128 = 10_000;
+
+/// Invariant a pool trades on, with the amplification of stable pools resolved for the current
+/// time. Every swap and liquidity calculation of an `Amm` goes through it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Invariant {
+    ConstantProduct,
+    Stable { amp: u64 },
+}
+
+impl Invariant {
+    /// See `constant_product::buy_quote`.
+    pub fn buy_quote(
+        &self,
+        base_amount: u64,
+        base_reserve: u64,
+        quote_reserve: u64,
+    ) -> Result<u64> {
+        match *self {
+            Invariant::ConstantProduct => buy_quote(base_amount, base_reserve, quote_reserve),
+            Invariant::Stable { amp } => {
+                stable::buy_quote(base_amount, base_reserve, quote_reserve, amp)
+            }
+        }
+    }
+
+    /// See `constant_product::buy_exact_in_quote`.
+    pub fn buy_exact_in_quote(
+        &self,
+        quote_amount: u64,
+        base_reserve: u64,
+        quote_reserve: u64,
+    ) -> Result<u64> {
+        match *self {
+            Invariant::ConstantProduct => {
+                buy_exact_in_quote(quote_amount, base_reserve, quote_reserve)
+            }
+            Invariant::Stable { amp } => {
+                stable::buy_exact_in_quote(quote_amount, base_reserve, quote_reserve, amp)
+            }
+        }
+    }
+
+    /// See `constant_product::sell_quote`.
+    pub fn sell_quote(
+        &self,
+        base_amount: u64,
+        base_reserve: u64,
+        quote_reserve: u64,
+    ) -> Result<u64> {
+        match *self {
+            Invariant::ConstantProduct => sell_quote(base_amount, base_reserve, quote_reserve),
+            Invariant::Stable { amp } => {
+                stable::sell_quote(base_amount, base_reserve, quote_reserve, amp)
+            }
+        }
+    }
+
+    /// See `constant_product::sell_exact_out_quote`.
+    pub fn sell_exact_out_quote(
+        &self,
+        quote_amount: u64,
+        base_reserve: u64,
+        quote_reserve: u64,
+    ) -> Result<u64> {
+        match *self {
+            Invariant::ConstantProduct => {
+                sell_exact_out_quote(quote_amount, base_reserve, quote_reserve)
+            }
+            Invariant::Stable { amp } => {
+                stable::sell_exact_out_quote(quote_amount, base_reserve, quote_reserve, amp)
+            }
+        }
+    }
+
+    /// See `constant_product::calculate_shares`.
+    pub fn calculate_shares(
+        &self,
+        base_token_amount: u64,
+        quote_token_amount: u64,
+        base_reserve: u64,
+        quote_reserve: u64,
+        total_shares: u64,
+    ) -> Result<u64> {
+        match *self {
+            Invariant::ConstantProduct => calculate_shares(
+                base_token_amount,
+                quote_token_amount,
+                base_reserve,
+                quote_reserve,
+                total_shares,
+            ),
+            Invariant::Stable { amp } => stable::calculate_shares(
+                base_token_amount,
+                quote_token_amount,
+                base_reserve,
+                quote_reserve,
+                total_shares,
+                amp,
+            ),
+        }
+    }
+
+    /// Liquidity of the reserves: `sqrt(x * y)` on the constant product curve, `D` on the
+    /// stable one. It only grows through fees between two liquidity changes.
+    pub fn liquidity(&self, base_reserve: u64, quote_reserve: u64) -> Result<u128> {
+        match *self {
+            Invariant::ConstantProduct => Ok((base_reserve as u128)
+                .checked_mul(quote_reserve as u128)
+                .ok_or(ErrorCode::MathOverflow)?
+                .sqrt()),
+            Invariant::Stable { amp } => {
+                stable::compute_d(base_reserve as u128, quote_reserve as u128, amp)
+            }
+        }
+    }
+
+    /// See `constant_product::deposit_quote`. Deposits are proportional on every curve.
+    pub fn deposit_quote(
+        &self,
+        max_base_amount: u64,
+        max_quote_amount: u64,
+        base_reserve: u64,
+        quote_reserve: u64,
+    ) -> Result<(u64, u64)> {
+        deposit_quote(
+            max_base_amount,
+            max_quote_amount,
+            base_reserve,
+            quote_reserve,
+        )
+    }
+
+    /// See `constant_product::remove_quote`. Withdrawals are proportional on every curve.
+    pub fn remove_quote(
+        &self,
+        shares: u64,
+        base_reserve: u64,
+        quote_reserve: u64,
+        total_shares: u64,
+    ) -> Result<(u64, u64)> {
+        remove_quote(shares, base_reserve, quote_reserve, total_shares)
+    }
+}
+
+/// Direction in which a division is rounded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// Computes `a * b / denominator` in `u128`, rounded in the given direction.
+pub fn mul_div(a: u128, b: u128, denominator: u128, rounding: Rounding) -> Result<u128> {
+    require_neq!(denominator, 0, ErrorCode::MathOverflow);
+    let product = a.checked_mul(b).ok_or(ErrorCode::MathOverflow)?;
+    let quotient = product / denominator;
+    if rounding == Rounding::Up && product % denominator != 0 {
+        Ok(quotient.checked_add(1).ok_or(ErrorCode::MathOverflow)?)
+    } else {
+        Ok(quotient)
+    }
+}
+
+/// Computes `a * b / denominator` with a 256 bit intermediate product, rounded in the given
+/// direction. Fails if the result does not fit in a `u128`.
+pub fn mul_div_u256(a: u128, b: u128, denominator: u128, rounding: Rounding) -> Result<u128> {
+    require_neq!(denominator, 0, ErrorCode::MathOverflow);
+
+    // Full 256 bit product as (high, low) halves
+    let mask = u64::MAX as u128;
+    let (a1, a0) = (a >> 64, a & mask);
+    let (b1, b0) = (b >> 64, b & mask);
+    let low_low = a0 * b0;
+    let low_high = a0 * b1;
+    let high_low = a1 * b0;
+    let middle = (low_low >> 64) + (low_high & mask) + (high_low & mask);
+    let low = (low_low & mask) | (middle << 64);
+    let high = a1 * b1 + (low_high >> 64) + (high_low >> 64) + (middle >> 64);
+
+    // The quotient only fits in 128 bits if the high half is below the denominator
+    require_gt!(denominator, high, ErrorCode::MathOverflow);
+
+    // Binary long division of the 256 bit product
+    let mut quotient: u128 = 0;
+    let mut remainder = high;
+    for i in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> i) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1;
+        }
+    }
+
+    if rounding == Rounding::Up && remainder != 0 {
+        Ok(quotient.checked_add(1).ok_or(ErrorCode::MathOverflow)?)
+    } else {
+        Ok(quotient)
+    }
+}
+
+/// Narrows a `u128` result back to a token amount.
+pub fn to_u64(value: u128) -> Result<u64> {
+    Ok(u64::try_from(value).map_err(|_| ErrorCode::MathOverflow)?)
+}
+
+/// Takes `bps` basis points of `amount`, rounded in the given direction.
+pub fn apply_bps(amount: u64, bps: u64, rounding: Rounding) -> Result<u64> {
+    to_u64(mul_div(
+        amount as u128,
+        bps as u128,
+        BPS_DENOMINATOR,
+        rounding,
+    )?)
+}
+
+/// Fee kept by the pool on `amount`, rounded up so the pool never undercharges.
+pub fn fee_amount(amount: u64, fee_bps: u64) -> Result<u64> {
+    apply_bps(amount, fee_bps, Rounding::Up)
+}
+
+/// Gross amount whose fee-deducted remainder covers `amount`, rounded up.
+pub fn amount_with_fee(amount: u64, fee_bps: u64) -> Result<u64> {
+    let remainder_bps = BPS_DENOMINATOR
+        .checked_sub(fee_bps as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+    to_u64(mul_div(
+        amount as u128,
+        BPS_DENOMINATOR,
+        remainder_bps,
+        Rounding::Up,
+    )?)
+}
+
+/// LP shares minted to the protocol for its `protocol_share_bps` part of the fees accrued since
+/// the pool's liquidity was `liquidity_last`, Uniswap v2 `kLast` style.
+///
+/// The fees grew the liquidity from `liquidity_last` to `liquidity`. Minting `s` new shares on
+/// top of `total_shares` gives the protocol `s / (total_shares + s)` of the pool, which is worth
+/// `protocol_share_bps` of the growth when
+/// `s = total_shares * (liquidity - liquidity_last) * share / ((1 - share) * liquidity + share * liquidity_last)`,
+/// rounded down.
+pub fn protocol_fee_shares(
+    liquidity: u128,
+    liquidity_last: u128,
+    total_shares: u64,
+    protocol_share_bps: u64,
+) -> Result<u64> {
+    if liquidity <= liquidity_last || protocol_share_bps == 0 {
+        return Ok(0);
+    }
+    let share = protocol_share_bps as u128;
+    let numerator = (total_shares as u128)
+        .checked_mul(liquidity - liquidity_last)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let denominator = BPS_DENOMINATOR
+        .checked_sub(share)
+        .and_then(|value| value.checked_mul(liquidity))
+        .and_then(|value| value.checked_add(share.checked_mul(liquidity_last)?))
+        .ok_or(ErrorCode::MathOverflow)?;
+    to_u64(mul_div_u256(numerator, share, denominator, Rounding::Down)?)
+}
+
+/// LP shares of a lock of `amount` shares that only represent the fees accrued since the pool's
+/// liquidity per share was `liquidity_per_share_last`, now `liquidity_per_share`.
+///
+/// The rest, rounded up, is still worth the liquidity that was locked.
+pub fn locked_fee_shares(
+    amount: u64,
+    liquidity_per_share_last: u128,
+    liquidity_per_share: u128,
+) -> Result<u64> {
+    if liquidity_per_share <= liquidity_per_share_last {
+        return Ok(0);
+    }
+    let principal = to_u64(mul_div_u256(
+        amount as u128,
+        liquidity_per_share_last,
+        liquidity_per_share,
+        Rounding::Up,
+    )?)?;
+    Ok(amount - principal)
+}
+
+/// Part of a single sided deposit of `amount` to swap for the other side first, so the rest and
+/// the swap output are in the ratio of the reserves after the swap, which maximizes the shares
+/// minted for the deposit.
+///
+/// `swap` returns, for an amount swapped, the deposit and then the reserve of both sides after
+/// the swap as `(deposit_in, deposit_out, reserve_in, reserve_out)`. The more is swapped the less
+/// the deposited side is worth of its reserve and the more the other side is, so the balanced
+/// amount is found by bisection whatever the curve and fees.
+pub fn zap_swap_amount(
+    amount: u64,
+    swap: impl Fn(u64) -> Result<(u64, u64, u64, u64)>,
+) -> Result<u64> {
+    let (mut low, mut high) = (0, amount);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let (deposit_in, deposit_out, reserve_in, reserve_out) = swap(mid)?;
+        if deposit_in as u128 * reserve_out as u128 > deposit_out as u128 * reserve_in as u128 {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(low)
+}
+
+/// Part of `amount` vested at `now` by a schedule releasing it linearly from `start` to `end`,
+/// of which nothing is released before `cliff`.
+pub fn vested_amount(amount: u64, start: i64, cliff: i64, end: i64, now: i64) -> Result<u64> {
+    if now < cliff {
+        return Ok(0);
+    }
+    if now >= end {
+        return Ok(amount);
+    }
+    to_u64(mul_div(
+        amount as u128,
+        (now - start) as u128,
+        (end - start) as u128,
+        Rounding::Down,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mul_div_rounding() {
+        assert_eq!(mul_div(10, 10, 3, Rounding::Down).unwrap(), 33);
+        assert_eq!(mul_div(10, 10, 3, Rounding::Up).unwrap(), 34);
+        assert_eq!(mul_div(10, 9, 3, Rounding::Up).unwrap(), 30);
+    }
+
+    #[test]
+    fn test_mul_div_overflow() {
+        assert!(mul_div(u128::MAX, 2, 1, Rounding::Down).is_err());
+        assert!(mul_div(1, 1, 0, Rounding::Down).is_err());
+    }
+
+    #[test]
+    fn test_mul_div_u256() {
+        assert_eq!(
+            mul_div_u256(u128::MAX, u128::MAX, u128::MAX, Rounding::Down).unwrap(),
+            u128::MAX
+        );
+        assert_eq!(
+            mul_div_u256(1 << 100, 1 << 100, 1 << 80, Rounding::Down).unwrap(),
+            1 << 120
+        );
+        assert_eq!(mul_div_u256(10, 10, 3, Rounding::Up).unwrap(), 34);
+        assert!(mul_div_u256(u128::MAX, 2, 1, Rounding::Down).is_err());
+        assert!(mul_div_u256(1, 1, 0, Rounding::Down).is_err());
+    }
+
+    #[test]
+    fn test_fee_amount_rounds_up() {
+        assert_eq!(fee_amount(10_000, 30).unwrap(), 30);
+        assert_eq!(fee_amount(1, 30).unwrap(), 1);
+        assert_eq!(fee_amount(1_000, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_amount_with_fee_covers_amount() {
+        for amount in [1u64, 997, 1_000_000, u32::MAX as u64] {
+            let gross = amount_with_fee(amount, 30).unwrap();
+            assert!(gross - fee_amount(gross, 30).unwrap() >= amount);
+        }
+        assert!(amount_with_fee(1, 10_001).is_err());
+    }
+
+    #[test]
+    fn test_protocol_fee_shares() {
+        // Half of a 10% growth: 47 new shares out of 1047 are worth 49.4 of the 100 grown
+        assert_eq!(protocol_fee_shares(1_100, 1_000, 1_000, 5_000).unwrap(), 47);
+        // The whole growth
+        assert_eq!(
+            protocol_fee_shares(1_100, 1_000, 1_000, 10_000).unwrap(),
+            100
+        );
+        // Nothing accrued, or nothing owed to the protocol
+        assert_eq!(protocol_fee_shares(1_000, 1_000, 1_000, 5_000).unwrap(), 0);
+        assert_eq!(protocol_fee_shares(900, 1_000, 1_000, 5_000).unwrap(), 0);
+        assert_eq!(protocol_fee_shares(1_100, 1_000, 1_000, 0).unwrap(), 0);
+        assert!(protocol_fee_shares(1_100, 1_000, 1_000, 10_001).is_err());
+    }
+
+    #[test]
+    fn test_locked_fee_shares() {
+        // The liquidity per share grew by 25%, a fifth of the shares are fees
+        assert_eq!(locked_fee_shares(1_000, 100, 125).unwrap(), 200);
+        // The principal is rounded up
+        assert_eq!(locked_fee_shares(1_000, 100, 300).unwrap(), 666);
+        assert_eq!(locked_fee_shares(1_000, 100, 100).unwrap(), 0);
+        assert_eq!(locked_fee_shares(1_000, 100, 90).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_zap_swap_amount() {
+        let zap = |amount: u64, fee_bps: u64| {
+            zap_swap_amount(amount, |swap_amount| {
+                let swapped = swap_amount - fee_amount(swap_amount, fee_bps)?;
+                let base_amount = buy_exact_in_quote(swapped, 1_000_000, 1_000_000)?;
+                Ok((
+                    amount - swap_amount,
+                    base_amount,
+                    1_000_000 + swap_amount,
+                    1_000_000 - base_amount,
+                ))
+            })
+            .unwrap()
+        };
+        // Without fees, sqrt(R * (R + A)) - R of a quote deposit A, give or take the rounding
+        let amount = 100_000;
+        let expected = (1_000_000u128 * (1_000_000 + amount as u128)).sqrt() as u64 - 1_000_000;
+        assert!(zap(amount, 0).abs_diff(expected) <= 2);
+        // Fees make the output smaller, so a bit more has to be swapped
+        assert!(zap(amount, 100) > zap(amount, 0));
+        assert_eq!(zap(0, 0), 0);
+    }
+
+    #[test]
+    fn test_vested_amount() {
+        // Nothing before the cliff, then what vested linearly since the start
+        assert_eq!(vested_amount(1_000, 0, 250, 1_000, 100).unwrap(), 0);
+        assert_eq!(vested_amount(1_000, 0, 250, 1_000, 250).unwrap(), 250);
+        assert_eq!(vested_amount(1_000, 0, 250, 1_000, 333).unwrap(), 333);
+        assert_eq!(vested_amount(1_000, 0, 250, 1_000, 1_000).unwrap(), 1_000);
+        assert_eq!(vested_amount(1_000, 0, 250, 1_000, 5_000).unwrap(), 1_000);
+        // Rounded down
+        assert_eq!(vested_amount(10, 0, 0, 3, 1).unwrap(), 3);
+        // Without a cliff the release starts right away
+        assert_eq!(vested_amount(1_000, 100, 100, 200, 150).unwrap(), 500);
+    }
+}
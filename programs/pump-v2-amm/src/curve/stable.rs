@@ -0,0 +1,252 @@
+//! StableSwap curve used by pools of pegged pairs.
+//!
+//! The invariant `4A(x + y) + D = 4AD + D^3 / (4xy)` trades like a constant sum near the peg and
+//! like a constant product as the pool gets imbalanced, `A` being the amplification coefficient.
+//! Reserves are compared in raw units, so both mints of a stable pool have the same decimals.
+use super::{constant_product, mul_div_u256, to_u64, Rounding};
+use crate::error::ErrorCode;
+use anchor_lang::prelude::*;
+
+/// Lowest amplification coefficient of a stable pool.
+pub const MIN_AMP: u64 = 1;
+/// Highest amplification coefficient of a stable pool.
+pub const MAX_AMP: u64 = 1_000_000;
+
+/// Newton iterations after which `compute_d` and `compute_y` give up.
+const MAX_ITERATIONS: usize = 255;
+
+/// Computes the invariant `D` of two reserves.
+pub fn compute_d(x: u128, y: u128, amp: u64) -> Result<u128> {
+    let sum = x.checked_add(y).ok_or(ErrorCode::MathOverflow)?;
+    if sum == 0 {
+        return Ok(0);
+    }
+    require!(x > 0 && y > 0, ErrorCode::InsufficientLiquidity);
+
+    // A * n^n with two coins
+    let ann = amp as u128 * 4;
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        // D^3 / (4xy)
+        let d_p = mul_div_u256(
+            mul_div_u256(d, d, x * 2, Rounding::Down)?,
+            d,
+            y * 2,
+            Rounding::Down,
+        )?;
+        let d_prev = d;
+        // D = (Ann * S + 2 * D_P) * D / ((Ann - 1) * D + 3 * D_P)
+        let numerator = (ann * sum)
+            .checked_add(d_p.checked_mul(2).ok_or(ErrorCode::MathOverflow)?)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let denominator = (ann - 1)
+            .checked_mul(d)
+            .and_then(|value| value.checked_add(d_p.checked_mul(3)?))
+            .ok_or(ErrorCode::MathOverflow)?;
+        d = mul_div_u256(numerator, d, denominator, Rounding::Down)?;
+        if d.abs_diff(d_prev) <= 1 {
+            return Ok(d);
+        }
+    }
+    err!(ErrorCode::CurveDidNotConverge)
+}
+
+/// Computes the reserve of one side keeping the invariant `d` once the other side is `x`,
+/// rounded up so the pool never pays out too much.
+pub fn compute_y(x: u128, d: u128, amp: u64) -> Result<u128> {
+    require_gt!(x, 0, ErrorCode::InsufficientLiquidity);
+
+    let ann = amp as u128 * 4;
+    // c = D^3 / (4 * x * Ann), b = x + D / Ann
+    let c = mul_div_u256(
+        mul_div_u256(d, d, x * 2, Rounding::Up)?,
+        d,
+        ann * 2,
+        Rounding::Up,
+    )?;
+    let b = x.checked_add(d / ann).ok_or(ErrorCode::MathOverflow)?;
+
+    // Newton's method on y^2 + (b - D) * y = c
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let y_prev = y;
+        let numerator = y
+            .checked_mul(y)
+            .and_then(|value| value.checked_add(c))
+            .ok_or(ErrorCode::MathOverflow)?;
+        let denominator = y
+            .checked_mul(2)
+            .and_then(|value| value.checked_add(b))
+            .and_then(|value| value.checked_sub(d))
+            .ok_or(ErrorCode::MathOverflow)?;
+        y = numerator.div_ceil(denominator);
+        if y.abs_diff(y_prev) <= 1 {
+            return Ok(y);
+        }
+    }
+    err!(ErrorCode::CurveDidNotConverge)
+}
+
+/// Output paid for `amount_in`, rounded down with one extra unit kept to absorb the Newton
+/// approximation.
+fn amount_out(amount_in: u64, reserve_in: u64, reserve_out: u64, amp: u64) -> Result<u64> {
+    let d = compute_d(reserve_in as u128, reserve_out as u128, amp)?;
+    let new_reserve_in = (reserve_in as u128)
+        .checked_add(amount_in as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+    let new_reserve_out = compute_y(new_reserve_in, d, amp)?;
+    to_u64(
+        (reserve_out as u128)
+            .saturating_sub(new_reserve_out)
+            .saturating_sub(1),
+    )
+}
+
+/// Input needed for `amount_out`, rounded up with one extra unit to absorb the Newton
+/// approximation.
+fn amount_in(amount_out: u64, reserve_in: u64, reserve_out: u64, amp: u64) -> Result<u64> {
+    require_gt!(reserve_out, amount_out, ErrorCode::InsufficientLiquidity);
+    let d = compute_d(reserve_in as u128, reserve_out as u128, amp)?;
+    let new_reserve_in = compute_y((reserve_out - amount_out) as u128, d, amp)?;
+    to_u64(
+        new_reserve_in
+            .saturating_sub(reserve_in as u128)
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?,
+    )
+}
+
+/// Calculates the required quote amount to obtain a specified amount of base tokens.
+pub fn buy_quote(base_amount: u64, base_reserve: u64, quote_reserve: u64, amp: u64) -> Result<u64> {
+    amount_in(base_amount, quote_reserve, base_reserve, amp)
+}
+
+/// Calculates the amount of base tokens obtained for spending a specified amount of quote tokens.
+pub fn buy_exact_in_quote(
+    quote_amount: u64,
+    base_reserve: u64,
+    quote_reserve: u64,
+    amp: u64,
+) -> Result<u64> {
+    amount_out(quote_amount, quote_reserve, base_reserve, amp)
+}
+
+/// Calculates the amount of quote tokens received for selling a given amount of base tokens.
+pub fn sell_quote(
+    base_amount: u64,
+    base_reserve: u64,
+    quote_reserve: u64,
+    amp: u64,
+) -> Result<u64> {
+    amount_out(base_amount, base_reserve, quote_reserve, amp)
+}
+
+/// Calculates the amount of base tokens that must be sold to receive a given amount of quote tokens.
+pub fn sell_exact_out_quote(
+    quote_amount: u64,
+    base_reserve: u64,
+    quote_reserve: u64,
+    amp: u64,
+) -> Result<u64> {
+    amount_in(quote_amount, base_reserve, quote_reserve, amp)
+}
+
+/// Calculates the number of shares a user will receive for their liquidity.
+///
+/// The first deposit mints `D` shares. Later deposits are proportional to the reserves like on
+/// constant product pools, so an imbalanced deposit cannot be used as a fee-free swap.
+pub fn calculate_shares(
+    base_token_amount: u64,
+    quote_token_amount: u64,
+    base_reserve: u64,
+    quote_reserve: u64,
+    total_shares: u64,
+    amp: u64,
+) -> Result<u64> {
+    if total_shares != 0 {
+        constant_product::calculate_shares(
+            base_token_amount,
+            quote_token_amount,
+            base_reserve,
+            quote_reserve,
+            total_shares,
+        )
+    } else {
+        to_u64(compute_d(
+            base_token_amount as u128,
+            quote_token_amount as u128,
+            amp,
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One billion tokens with 9 decimals on each side
+    const LARGE_RESERVE: u64 = 1_000_000_000_000_000_000;
+
+    #[test]
+    fn test_balanced_invariant_is_the_sum() {
+        assert_eq!(compute_d(1_000_000, 1_000_000, 100).unwrap(), 2_000_000);
+        assert_eq!(compute_d(0, 0, 100).unwrap(), 0);
+        assert!(compute_d(0, 1_000_000, 100).is_err());
+    }
+
+    #[test]
+    fn test_stable_beats_constant_product_near_peg() {
+        let (reserve, amount) = (1_000_000_000, 10_000_000);
+        let stable = sell_quote(amount, reserve, reserve, 100).unwrap();
+        let xyk = constant_product::sell_quote(amount, reserve, reserve).unwrap();
+        assert!(stable > xyk);
+        // Never more than a constant sum would pay
+        assert!(stable < amount);
+    }
+
+    #[test]
+    fn test_higher_amp_flattens_the_curve() {
+        let (reserve, amount) = (1_000_000_000, 100_000_000);
+        let low = sell_quote(amount, reserve, reserve, 10).unwrap();
+        let high = sell_quote(amount, reserve, reserve, 1_000).unwrap();
+        assert!(high > low);
+    }
+
+    #[test]
+    fn test_exact_in_and_exact_out_agree() {
+        let (base_reserve, quote_reserve, amp) = (800_000_000, 1_200_000_000, 200);
+        let quote_in = buy_quote(100_000, base_reserve, quote_reserve, amp).unwrap();
+        assert!(buy_exact_in_quote(quote_in, base_reserve, quote_reserve, amp).unwrap() >= 100_000);
+        let base_in = sell_exact_out_quote(100_000, base_reserve, quote_reserve, amp).unwrap();
+        assert!(sell_quote(base_in, base_reserve, quote_reserve, amp).unwrap() >= 100_000);
+    }
+
+    #[test]
+    fn test_round_trip_does_not_profit() {
+        let (reserve, amp) = (1_000_000_000, 500);
+        let quote_out = sell_quote(1_000_000, reserve, reserve, amp).unwrap();
+        let base_out =
+            buy_exact_in_quote(quote_out, reserve + 1_000_000, reserve - quote_out, amp).unwrap();
+        assert!(base_out < 1_000_000);
+    }
+
+    #[test]
+    fn test_large_reserves_do_not_overflow() {
+        let quote = sell_quote(1_000_000_000_000, LARGE_RESERVE, LARGE_RESERVE, MAX_AMP).unwrap();
+        assert!(quote < 1_000_000_000_000);
+        let quote = buy_quote(1_000_000_000_000, LARGE_RESERVE, LARGE_RESERVE, MIN_AMP).unwrap();
+        assert!(quote > 1_000_000_000_000);
+    }
+
+    #[test]
+    fn test_initial_shares_are_the_invariant() {
+        assert_eq!(
+            calculate_shares(1_000_000, 1_000_000, 0, 0, 0, 100).unwrap(),
+            2_000_000
+        );
+        assert_eq!(
+            calculate_shares(1_000, 2_000, 10_000, 10_000, 10_000, 100).unwrap(),
+            1_000
+        );
+    }
+}
@@ -40,4 +40,74 @@ pub enum ErrorCode {
     ReferrerFeeDiscountExceedsFee,
     #[msg("Invalid fee configuration.")]
     InvalidFeeConfiguration,
+    #[msg("Base amount too high.")]
+    BaseAmountTooHigh,
+    #[msg("Invalid route.")]
+    InvalidRoute,
+    #[msg("Invalid route accounts.")]
+    InvalidRouteAccounts,
+    #[msg("Route output too low.")]
+    RouteOutputTooLow,
+    #[msg("Invalid tick range.")]
+    InvalidTickRange,
+    #[msg("Invalid tick spacing.")]
+    InvalidTickSpacing,
+    #[msg("Invalid sqrt price.")]
+    InvalidSqrtPrice,
+    #[msg("Invalid tick array.")]
+    InvalidTickArray,
+    #[msg("Not enough tick arrays for the swap.")]
+    TickArraysExhausted,
+    #[msg("Invalid amplification coefficient.")]
+    InvalidAmplification,
+    #[msg("Invalid amplification ramp.")]
+    InvalidAmpRamp,
+    #[msg("Stable pools need mints with the same decimals.")]
+    StableDecimalsMismatch,
+    #[msg("Pool does not use the stable curve.")]
+    NotStablePool,
+    #[msg("Curve calculation did not converge.")]
+    CurveDidNotConverge,
+    #[msg("Invalid observation window.")]
+    InvalidObservationWindow,
+    #[msg("Timestamp is older than the recorded observations.")]
+    ObservationTooOld,
+    #[msg("Invalid observations capacity.")]
+    InvalidObservationsCapacity,
+    #[msg("A flash loan is in progress on this pool.")]
+    FlashLoanInProgress,
+    #[msg("No flash loan to repay on this pool.")]
+    NoFlashLoan,
+    #[msg("Flash loan is not repaid later in the transaction.")]
+    MissingFlashRepay,
+    #[msg("Operation is paused.")]
+    Paused,
+    #[msg("Invalid pause flags.")]
+    InvalidPauseFlags,
+    #[msg("Signer does not hold the required role.")]
+    MissingRole,
+    #[msg("Invalid roles.")]
+    InvalidRoles,
+    #[msg("Invalid timelock delay.")]
+    InvalidTimelockDelay,
+    #[msg("Queued parameters cannot be executed yet.")]
+    TimelockNotElapsed,
+    #[msg("No admin proposal to cancel.")]
+    NoAdminProposal,
+    #[msg("Admin proposal has expired.")]
+    AdminProposalExpired,
+    #[msg("Treasury LP token account is required to mint the protocol fee.")]
+    MissingTreasuryLpAccount,
+    #[msg("Liquidity is still locked.")]
+    LiquidityLocked,
+    #[msg("Unlock timestamp must be in the future.")]
+    InvalidUnlockTimestamp,
+    #[msg("Locker accounts are required to lock the LP shares.")]
+    MissingLockerAccounts,
+    #[msg("Invalid vesting schedule.")]
+    InvalidVestingSchedule,
+    #[msg("No vested shares to claim.")]
+    NothingVested,
+    #[msg("Account layout cannot be upgraded.")]
+    UnknownAccountLayout,
 }
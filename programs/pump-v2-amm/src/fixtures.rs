@@ -1,5 +1,6 @@
 #[cfg(test)]
 pub mod tests {
+    use anchor_lang::AnchorSerialize;
     use anchor_spl::token::{self, Mint};
     use solana_client::nonblocking::rpc_client::RpcClient;
     use solana_sdk::pubkey::Pubkey;
@@ -74,18 +75,67 @@ pub mod tests {
         Ok((base_mint.pubkey(), quote_mint.pubkey()))
     }
 
-    fn set_parameters_instruction(
+    pub fn queue_parameters_instruction(
         program_id: &solana_sdk::pubkey::Pubkey,
         global_parameters: &solana_sdk::pubkey::Pubkey,
         payer_pubkey: &solana_sdk::pubkey::Pubkey,
     ) -> solana_sdk::instruction::Instruction {
-        let mut data = switchboard_solana::get_ixn_discriminator("set_parameters").to_vec();
-        data.extend_from_slice(&100u64.to_le_bytes());
-        data.extend_from_slice(&10u64.to_le_bytes());
-        data.extend_from_slice(&20u64.to_le_bytes());
+        let mut data = switchboard_solana::get_ixn_discriminator("queue_parameters").to_vec();
+        crate::QueuedParameters {
+            fee_config: crate::FeeConfig {
+                protocol_fee_bps: 100,
+                referrer_fee_bps: 10,
+                referrer_fee_discount_bps: 20,
+                lp_fee_bps: 30,
+            },
+            fee_mode: crate::FeeMode::OnTop,
+            flash_loan_fee_bps: 9,
+            // No timelock, so that the parameters can be executed right away
+            timelock_delay: 0,
+        }
+        .serialize(&mut data)
+        .unwrap();
+        let accounts = vec![
+            solana_sdk::instruction::AccountMeta::new_readonly(*global_parameters, false),
+            solana_sdk::instruction::AccountMeta::new(*payer_pubkey, true),
+            solana_sdk::instruction::AccountMeta::new(
+                Pubkey::find_program_address(&[b"parameters_proposal"], program_id).0,
+                false,
+            ),
+            // No role account, the payer is the admin
+            solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(
+                solana_program::system_program::ID,
+                false,
+            ),
+            solana_sdk::instruction::AccountMeta::new_readonly(
+                Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+                false,
+            ),
+            solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
+        ];
+        solana_sdk::instruction::Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    }
+
+    fn execute_parameters_instruction(
+        program_id: &solana_sdk::pubkey::Pubkey,
+        global_parameters: &solana_sdk::pubkey::Pubkey,
+        payer_pubkey: &solana_sdk::pubkey::Pubkey,
+    ) -> solana_sdk::instruction::Instruction {
+        let data = switchboard_solana::get_ixn_discriminator("execute_parameters").to_vec();
         let accounts = vec![
             solana_sdk::instruction::AccountMeta::new(*global_parameters, false),
-            solana_sdk::instruction::AccountMeta::new_readonly(*payer_pubkey, true),
+            solana_sdk::instruction::AccountMeta::new(*payer_pubkey, true),
+            solana_sdk::instruction::AccountMeta::new(
+                Pubkey::find_program_address(&[b"parameters_proposal"], program_id).0,
+                false,
+            ),
+            // No role account, the payer is the admin
+            solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
             solana_sdk::instruction::AccountMeta::new_readonly(
                 Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
                 false,
@@ -121,7 +171,7 @@ pub mod tests {
         }
     }
 
-    fn buy_instruction(
+    pub fn buy_instruction(
         program_id: &solana_sdk::pubkey::Pubkey,
         global_parameters: &solana_sdk::pubkey::Pubkey,
         amm_pubkey: &solana_sdk::pubkey::Pubkey,
@@ -173,7 +223,7 @@ pub mod tests {
             ),
             solana_sdk::instruction::AccountMeta::new(
                 spl_associated_token_account::get_associated_token_address_with_program_id(
-                    &payer_pubkey,
+                    &Pubkey::find_program_address(&[b"fee_authority"], program_id).0,
                     &quote_pubkey,
                     &spl_token::ID,
                 ),
@@ -183,6 +233,8 @@ pub mod tests {
             solana_sdk::instruction::AccountMeta::new(*quote_pubkey, false),
             solana_sdk::instruction::AccountMeta::new_readonly(spl_token_2022::ID, false),
             solana_sdk::instruction::AccountMeta::new_readonly(spl_token::ID, false),
+            // No observations account
+            solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
             solana_sdk::instruction::AccountMeta::new(
                 spl_associated_token_account::get_associated_token_address_with_program_id(
                     &payer_pubkey,
@@ -212,17 +264,21 @@ pub mod tests {
         quote_pubkey: &solana_sdk::pubkey::Pubkey,
         payer_pubkey: &solana_sdk::pubkey::Pubkey,
         lp_mint_pubkey: &solana_sdk::pubkey::Pubkey,
-        base_amount: u64,
-        quote_amount: u64,
+        max_base: u64,
+        max_quote: u64,
         min_liquidity: u64,
     ) -> solana_sdk::instruction::Instruction {
         let mut data = switchboard_solana::get_ixn_discriminator("add_liquidity").to_vec();
-        data.extend_from_slice(&base_amount.to_le_bytes());
-        data.extend_from_slice(&quote_amount.to_le_bytes());
+        data.extend_from_slice(&max_base.to_le_bytes());
+        data.extend_from_slice(&max_quote.to_le_bytes());
         data.extend_from_slice(&min_liquidity.to_le_bytes());
         // Construct the accounts required for the add_liquidity_instruction
         let accounts = vec![
             solana_sdk::instruction::AccountMeta::new(*amm_pubkey, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(
+                Pubkey::find_program_address(&[b"global_parameters"], program_id).0,
+                false,
+            ),
             solana_sdk::instruction::AccountMeta::new_readonly(*payer_pubkey, true),
             solana_sdk::instruction::AccountMeta::new(
                 spl_associated_token_account::get_associated_token_address_with_program_id(
@@ -269,10 +325,13 @@ pub mod tests {
             solana_sdk::instruction::AccountMeta::new(*quote_pubkey, false),
             solana_sdk::instruction::AccountMeta::new_readonly(spl_token_2022::ID, false),
             solana_sdk::instruction::AccountMeta::new_readonly(spl_token::ID, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(spl_token::ID, false),
             solana_sdk::instruction::AccountMeta::new_readonly(
                 solana_program::system_program::ID,
                 false,
             ),
+            // No treasury LP account, fees are charged on top
+            solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
             solana_sdk::instruction::AccountMeta::new_readonly(
                 Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
                 false,
@@ -306,6 +365,10 @@ pub mod tests {
         // Construct the accounts required for the add_liquidity_instruction
         let accounts = vec![
             solana_sdk::instruction::AccountMeta::new(*amm_pubkey, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(
+                Pubkey::find_program_address(&[b"global_parameters"], program_id).0,
+                false,
+            ),
             solana_sdk::instruction::AccountMeta::new_readonly(*payer_pubkey, true),
             solana_sdk::instruction::AccountMeta::new(
                 spl_associated_token_account::get_associated_token_address_with_program_id(
@@ -352,10 +415,13 @@ pub mod tests {
             solana_sdk::instruction::AccountMeta::new(*quote_pubkey, false),
             solana_sdk::instruction::AccountMeta::new_readonly(spl_token_2022::ID, false),
             solana_sdk::instruction::AccountMeta::new_readonly(spl_token::ID, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(spl_token::ID, false),
             solana_sdk::instruction::AccountMeta::new_readonly(
                 solana_program::system_program::ID,
                 false,
             ),
+            // No treasury LP account, fees are charged on top
+            solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
             solana_sdk::instruction::AccountMeta::new_readonly(
                 Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
                 false,
@@ -422,7 +488,7 @@ pub mod tests {
             ),
             solana_sdk::instruction::AccountMeta::new(
                 spl_associated_token_account::get_associated_token_address_with_program_id(
-                    &payer_pubkey,
+                    &Pubkey::find_program_address(&[b"fee_authority"], program_id).0,
                     &quote_pubkey,
                     &spl_token::ID,
                 ),
@@ -432,6 +498,8 @@ pub mod tests {
             solana_sdk::instruction::AccountMeta::new(*quote_pubkey, false),
             solana_sdk::instruction::AccountMeta::new_readonly(spl_token_2022::ID, false),
             solana_sdk::instruction::AccountMeta::new_readonly(spl_token::ID, false),
+            // No observations account
+            solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
             solana_sdk::instruction::AccountMeta::new(
                 spl_associated_token_account::get_associated_token_address_with_program_id(
                     &payer_pubkey,
@@ -461,10 +529,12 @@ pub mod tests {
         quote_pubkey: &solana_sdk::pubkey::Pubkey,
         payer_pubkey: &solana_sdk::pubkey::Pubkey,
         lp_mint_pubkey: &solana_sdk::pubkey::Pubkey,
-        fee_recipient_pubkey: &solana_sdk::pubkey::Pubkey,
     ) -> solana_sdk::instruction::Instruction {
+        let fee_authority = Pubkey::find_program_address(&[b"fee_authority"], program_id).0;
         // Construct the data payload according to the program's expected schema
-        let data = switchboard_solana::get_ixn_discriminator("create").to_vec();
+        let mut data = switchboard_solana::get_ixn_discriminator("create").to_vec();
+        // Constant product pool
+        data.push(0);
         solana_sdk::instruction::Instruction {
             program_id: *program_id,
             accounts: vec![
@@ -493,12 +563,13 @@ pub mod tests {
                 ),
                 solana_sdk::instruction::AccountMeta::new(
                     spl_associated_token_account::get_associated_token_address_with_program_id(
-                        &fee_recipient_pubkey,
+                        &fee_authority,
                         &quote_pubkey,
                         &spl_token::ID,
                     ),
                     false,
                 ),
+                solana_sdk::instruction::AccountMeta::new_readonly(fee_authority, false),
                 solana_sdk::instruction::AccountMeta::new_readonly(*base_pubkey, false),
                 solana_sdk::instruction::AccountMeta::new_readonly(*quote_pubkey, false),
                 solana_sdk::instruction::AccountMeta::new_readonly(spl_token_2022::ID, false),
@@ -759,10 +830,13 @@ pub mod tests {
             let initialize_instruction =
                 initialize_instruction(&program_id, &payer.pubkey(), &global_parameters);
 
-            let set_parameters_instruction =
-                set_parameters_instruction(&program_id, &global_parameters, &payer.pubkey());
+            let queue_parameters_instruction =
+                queue_parameters_instruction(&program_id, &global_parameters, &payer.pubkey());
+            let execute_parameters_instruction =
+                execute_parameters_instruction(&program_id, &global_parameters, &payer.pubkey());
             ixs.push(initialize_instruction);
-            ixs.push(set_parameters_instruction);
+            ixs.push(queue_parameters_instruction);
+            ixs.push(execute_parameters_instruction);
         }
 
         // Create associated token accounts for the vaults
@@ -795,7 +869,6 @@ pub mod tests {
             &quote_mint,
             &payer.pubkey(),
             &lp_mint.pubkey(),
-            &payer.pubkey(),
         );
         ixs.push(create_amm_instruction);
         let mut transaction = Transaction::new_with_payer(&ixs, Some(&payer.pubkey()));
@@ -805,4 +878,57 @@ pub mod tests {
         println!("AMM created");
         Ok((amm_account, global_parameters, lp_mint.pubkey()))
     }
+
+    /// Global parameters minting half of the LP fee growth to the protocol as LP shares.
+    pub fn lp_share_global_parameters() -> crate::GlobalParameters {
+        crate::GlobalParameters {
+            protocol_fee_bps: 0,
+            referrer_fee_bps: 0,
+            referrer_fee_discount_bps: 0,
+            admin: Pubkey::default(),
+            proposed_admin: Pubkey::default(),
+            lp_fee_bps: 30,
+            flash_loan_fee_bps: 0,
+            paused: 0,
+            timelock_delay: 0,
+            proposed_admin_expiry: 0,
+            treasury: Pubkey::default(),
+            fee_mode: crate::FeeMode::LpShare {
+                protocol_share_bps: 5_000,
+            },
+        }
+    }
+
+    /// A stable pool holding `base_reserve` and `quote_reserve` for `total_shares`, its
+    /// amplification coefficient following `ramp`.
+    pub fn stable_amm(
+        base_reserve: u64,
+        quote_reserve: u64,
+        total_shares: u64,
+        ramp: crate::AmpRamp,
+    ) -> crate::Amm {
+        crate::Amm {
+            base_mint: Pubkey::default(),
+            quote_mint: Pubkey::default(),
+            base_reserve,
+            quote_reserve,
+            total_shares,
+            lp_mint: Pubkey::default(),
+            creator: Pubkey::default(),
+            base_reserve_ata: Pubkey::default(),
+            quote_reserve_ata: Pubkey::default(),
+            fee_receiver_ata: Pubkey::default(),
+            fee_config: None,
+            curve: crate::Curve::Stable(ramp),
+            base_price_cumulative_x64: 0,
+            quote_price_cumulative_x64: 0,
+            last_price_update_ts: 0,
+            liquidity_cumulative: 0,
+            flash_loan: None,
+            paused: 0,
+            liquidity_last: 0,
+            liquidity_last_amp: 0,
+            fee_mode: crate::FeeMode::OnTop,
+        }
+    }
 }
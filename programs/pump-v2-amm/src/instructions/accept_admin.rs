@@ -3,17 +3,19 @@
 /// the new admin is correctly recorded and integrated into the AMM's administrative controls.
 pub mod accept_admin {
 
-    // BuyEvent event
+    // AcceptAdminEvent event
     #[event]
     pub struct AcceptAdminEvent {
+        pub previous_admin: Pubkey,
         pub proposed_admin: Pubkey,
         pub timestamp: u64,
     }
 
+    use crate::error::ErrorCode;
     use crate::AcceptAdmin;
     use anchor_lang::prelude::*;
 
-    /// Accepts a proposed change to admin
+    /// Accepts a proposed change to admin, unless the proposal has expired
     ///
     /// # Parameters:
     /// - `ctx`: Context containing all required accounts for the transaction.
@@ -22,14 +24,23 @@ pub mod accept_admin {
 
     pub fn handler(ctx: Context<AcceptAdmin>) -> Result<()> {
         let global_parameters = &mut ctx.accounts.global_parameters;
+        require_gte!(
+            global_parameters.proposed_admin_expiry,
+            Clock::get()?.unix_timestamp,
+            ErrorCode::AdminProposalExpired
+        );
+        let previous_admin = global_parameters.admin;
         global_parameters.admin = ctx.accounts.admin.key();
         global_parameters.proposed_admin = Pubkey::default();
+        global_parameters.proposed_admin_expiry = 0;
         // Emit the event
         emit_cpi!(AcceptAdminEvent {
+            previous_admin,
             proposed_admin: *ctx.accounts.admin.to_account_info().key,
             timestamp: Clock::get()?.unix_timestamp as u64,
         });
         emit!(AcceptAdminEvent {
+            previous_admin,
             proposed_admin: *ctx.accounts.admin.to_account_info().key,
             timestamp: Clock::get()?.unix_timestamp as u64,
         });
@@ -123,4 +134,66 @@ mod tests {
         println!("{:?}", result);
         assert!(result.is_ok(), "Accept admin transaction should succeed");
     }
+
+    fn cancel_admin_proposal_instruction(
+        admin_pubkey: Pubkey,
+        global_parameters_pubkey: Pubkey,
+    ) -> Instruction {
+        let data = switchboard_solana::get_ixn_discriminator("cancel_admin_proposal").to_vec();
+        let accounts = vec![
+            solana_sdk::instruction::AccountMeta::new(global_parameters_pubkey, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(admin_pubkey, true),
+            solana_sdk::instruction::AccountMeta::new_readonly(
+                Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+                false,
+            ),
+            solana_sdk::instruction::AccountMeta::new_readonly(crate::ID, false),
+        ];
+        solana_sdk::instruction::Instruction {
+            program_id: crate::ID,
+            accounts,
+            data,
+        }
+    }
+
+    #[tokio::test]
+    async fn cancelled_admin_proposal_cannot_be_accepted() {
+        let setup = setup_test_environment(true).await;
+        let global_parameters_pubkey = setup.global_parameters;
+        let proposed_admin = Keypair::new();
+        let admin_pubkey = setup.keypair.pubkey();
+
+        let mut tx = Transaction::new_with_payer(
+            &[
+                propose_admin_instruction(
+                    proposed_admin.pubkey(),
+                    admin_pubkey,
+                    global_parameters_pubkey,
+                )
+                .await,
+                cancel_admin_proposal_instruction(admin_pubkey, global_parameters_pubkey),
+            ],
+            Some(&setup.keypair.pubkey()),
+        );
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.send_and_confirm_transaction(&tx).await;
+        assert!(result.is_ok(), "Propose and cancel should succeed");
+
+        let mut tx = Transaction::new_with_payer(
+            &[accept_admin_instruction(proposed_admin.pubkey(), global_parameters_pubkey).await],
+            Some(&setup.keypair.pubkey()),
+        );
+        tx.sign(
+            &[&setup.keypair, &proposed_admin],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.send_and_confirm_transaction(&tx).await;
+        assert!(
+            result.is_err(),
+            "A cancelled proposal should not be accepted"
+        );
+    }
 }
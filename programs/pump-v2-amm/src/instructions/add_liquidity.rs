@@ -1,96 +1,114 @@
 use crate::error::ErrorCode;
-use crate::utils::{spl_token_transfer, TokenTransferParams};
+use crate::utils::{
+    amount_after_transfer_fee, amount_with_transfer_fee, check_reserve_balances,
+    spl_token_transfer, TokenTransferParams,
+};
 use anchor_lang::prelude::*;
-use num_integer::Roots;
 
 // Define a constant for the minimum liquidity threshold
-const MINIMUM_LIQUIDITY: u64 = 100_000;
+pub(crate) const MINIMUM_LIQUIDITY: u64 = 100_000;
 
-// AddLiquidityEvent event
+// AddLiquidityEvent event, amounts are the ones actually deposited, as credited to the reserves
+// after transfer fees
 #[event]
 pub struct AddLiquidityEvent {
     pub base_amount: u64,
     pub quote_amount: u64,
     pub shares: u64,
+    pub protocol_fee_shares: u64,
     pub timestamp: u64,
     pub user: Pubkey,
 }
 
-/// Calculates the number of shares a user will receive for their liquidity based on the
-/// current state of the Automated Market Maker (AMM).
-///
-/// # Parameters:
-/// - `base_token_amount`: Amount of the base token the user wants to deposit.
-/// - `quote_token_amount`: Amount of the quote token the user wants to deposit.
-/// - `base_reserve`: Current reserve of the base token in the AMM.
-/// - `quote_reserve`: Current reserve of the quote token in the AMM.
-/// - `total_shares`: Current total number of shares in the AMM.
-///
-/// # Returns:
-/// - Result containing the number of shares or an error.
-fn calculate_shares(
-    base_token_amount: u64,
-    quote_token_amount: u64,
-    base_reserve: u64,
-    quote_reserve: u64,
-    total_shares: u64,
-) -> Result<u64> {
-    if total_shares != 0 {
-        // Calculate amount of LP tokens as a fraction of existing reserves
-        let base_token_share = (base_token_amount * total_shares) / base_reserve;
-        let fractional_token_share = (quote_token_amount * total_shares) / quote_reserve;
-        Ok(base_token_share.min(fractional_token_share))
-    } else {
-        // Initialize shares when there's no existing liquidity
-        let initial_shares = (base_token_amount * quote_token_amount).sqrt();
-        Ok(initial_shares)
-    }
-}
-
 /// This submodule is dedicated to handling the addition of liquidity to the Automated Market Maker (AMM).
 /// It includes functions and structures necessary for processing liquidity transactions, ensuring that
 /// the liquidity added is correctly accounted for and integrated into the AMM's existing pool.
 pub mod add_liquidity {
     use super::*;
-    use crate::AddLiquidity;
+    use crate::{AddLiquidity, PAUSE_DEPOSITS};
 
     /// Handles liquidity addition to the AMM, ensuring that liquidity constraints are met and updating AMM reserves.
     ///
+    /// Only the largest pair within the bounds that is in the ratio of the reserves is transferred,
+    /// the rest stays with the user instead of being donated to the pool.
+    ///
     /// # Parameters:
     /// - `ctx`: Context containing all the accounts required to execute the operation.
-    /// - `base_amount`: Amount of the base currency being added.
-    /// - `quote_amount`: Amount of the quote currency being added.
+    /// - `max_base`: Most of the base currency the user adds.
+    /// - `max_quote`: Most of the quote currency the user adds.
     /// - `min_lp_shares`: Minimum number of liquidity provider (LP) shares the user expects to receive.
     ///
     /// # Returns:
     /// - Result indicating success or error state.
     pub fn handler(
         ctx: Context<AddLiquidity>,
-        base_amount: u64,
-        quote_amount: u64,
+        max_base: u64,
+        max_quote: u64,
         min_lp_shares: u64,
     ) -> Result<()> {
+        ctx.accounts
+            .amm
+            .require_not_paused(&ctx.accounts.global_parameters, PAUSE_DEPOSITS)?;
+
         // Retrieve account information for mints and token accounts
         let base_mint = &ctx.accounts.base_mint;
         let quote_mint = &ctx.accounts.quote_mint;
         let base_reserve_ata = &ctx.accounts.base_reserve_ata;
         let quote_reserve_ata = &ctx.accounts.quote_reserve_ata;
-        let lp_mint = &ctx.accounts.lp_mint;
+
+        // Deposit the pair in the ratio of the reserves, measured on what reaches the vaults
+        // after Token-2022 transfer fees
+        let now = Clock::get()?.unix_timestamp;
+        let (base_deposit_amount, quote_deposit_amount) =
+            ctx.accounts.amm.invariant(now).deposit_quote(
+                amount_after_transfer_fee(&base_mint.to_account_info(), max_base)?,
+                amount_after_transfer_fee(&quote_mint.to_account_info(), max_quote)?,
+                ctx.accounts.amm.base_reserve,
+                ctx.accounts.amm.quote_reserve,
+            )?;
+        // The user pays what it takes for that pair to arrive, within their bounds, and only
+        // what actually arrives is credited to the pool
+        let base_amount =
+            amount_with_transfer_fee(&base_mint.to_account_info(), base_deposit_amount)?
+                .min(max_base);
+        let quote_amount =
+            amount_with_transfer_fee(&quote_mint.to_account_info(), quote_deposit_amount)?
+                .min(max_quote);
+        let base_deposit_amount =
+            amount_after_transfer_fee(&base_mint.to_account_info(), base_amount)?;
+        let quote_deposit_amount =
+            amount_after_transfer_fee(&quote_mint.to_account_info(), quote_amount)?;
+
+        // Pay the protocol its share of the fees accrued since the last liquidity change
+        let protocol_fee_shares = ctx.accounts.amm.accrue_protocol_fee()?;
 
         // Calculate and mint liquidity tokens (shares)
-        let shares = calculate_shares(
-            base_amount,
-            quote_amount,
+        let shares = ctx.accounts.amm.invariant(now).calculate_shares(
+            base_deposit_amount,
+            quote_deposit_amount,
             ctx.accounts.amm.base_reserve,
             ctx.accounts.amm.quote_reserve,
             ctx.accounts.amm.total_shares,
         )?;
         require_gte!(shares, min_lp_shares, ErrorCode::InsufficientLiquidity);
+        // Read before the deposit is applied: a burned migration leaves shares but no LP supply
+        let first_deposit = ctx.accounts.amm.total_shares == 0;
         // Update AMM state with new reserves and total shares
         let amm = &mut ctx.accounts.amm;
-        amm.base_reserve += base_amount;
-        amm.quote_reserve += quote_amount;
-        amm.total_shares += shares;
+        amm.update_price_accumulators(now);
+        amm.base_reserve = amm
+            .base_reserve
+            .checked_add(base_deposit_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        amm.quote_reserve = amm
+            .quote_reserve
+            .checked_add(quote_deposit_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        amm.total_shares = amm
+            .total_shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+        amm.update_liquidity_last(&ctx.accounts.global_parameters, now)?;
         // Define signer seeds for transactions requiring signatures
         let signer_seeds = [
             b"amm",
@@ -100,11 +118,32 @@ pub mod add_liquidity {
             &[ctx.bumps.amm],
         ];
 
+        // Mint the protocol's share of the accrued fees to the treasury
+        if protocol_fee_shares > 0 {
+            let treasury_lp_ata = ctx
+                .accounts
+                .treasury_lp_ata
+                .as_ref()
+                .ok_or(ErrorCode::MissingTreasuryLpAccount)?;
+            anchor_spl::token_interface::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.lp_token_program.to_account_info(),
+                    anchor_spl::token_interface::MintTo {
+                        mint: ctx.accounts.lp_mint.to_account_info(),
+                        to: treasury_lp_ata.to_account_info(),
+                        authority: ctx.accounts.amm.to_account_info(),
+                    },
+                    &[&signer_seeds],
+                ),
+                protocol_fee_shares,
+            )?;
+        }
+
         // Mint the minimum liquidity to the user
-        anchor_spl::token::mint_to(
+        anchor_spl::token_interface::mint_to(
             CpiContext::new_with_signer(
-                ctx.accounts.base_token_program.to_account_info(),
-                anchor_spl::token::MintTo {
+                ctx.accounts.lp_token_program.to_account_info(),
+                anchor_spl::token_interface::MintTo {
                     mint: ctx.accounts.lp_mint.to_account_info(),
                     to: ctx.accounts.user_lp_ata.to_account_info(),
                     authority: ctx.accounts.amm.to_account_info(),
@@ -114,13 +153,13 @@ pub mod add_liquidity {
             shares,
         )?;
 
-        // Handle liquidity initialization case when LP mint amount is zero
-        if lp_mint.supply == 0 {
+        // Handle liquidity initialization case when the pool has no shares yet
+        if first_deposit {
             // Burn the minimum liquidity from the user's account
-            anchor_spl::token::burn(
+            anchor_spl::token_interface::burn(
                 CpiContext::new(
-                    ctx.accounts.base_token_program.to_account_info(),
-                    anchor_spl::token::Burn {
+                    ctx.accounts.lp_token_program.to_account_info(),
+                    anchor_spl::token_interface::Burn {
                         mint: ctx.accounts.lp_mint.to_account_info(),
                         from: ctx.accounts.user_lp_ata.to_account_info(),
                         authority: ctx.accounts.user.to_account_info(),
@@ -154,19 +193,28 @@ pub mod add_liquidity {
             token_program: ctx.accounts.quote_token_program.to_account_info(),
         })?;
 
+        // Make sure the vaults still back the recorded reserves
+        check_reserve_balances(
+            &ctx.accounts.amm,
+            &mut ctx.accounts.base_reserve_ata,
+            &mut ctx.accounts.quote_reserve_ata,
+        )?;
+
         // Emit the event
         emit_cpi!(AddLiquidityEvent {
-            base_amount,
-            quote_amount,
+            base_amount: base_deposit_amount,
+            quote_amount: quote_deposit_amount,
             shares,
+            protocol_fee_shares,
             timestamp: Clock::get()?.unix_timestamp as u64,
             user: *ctx.accounts.user.key,
         });
 
         emit!(AddLiquidityEvent {
-            base_amount,
-            quote_amount,
+            base_amount: base_deposit_amount,
+            quote_amount: quote_deposit_amount,
             shares,
+            protocol_fee_shares,
             timestamp: Clock::get()?.unix_timestamp as u64,
             user: *ctx.accounts.user.key,
         });
@@ -179,21 +227,13 @@ pub mod add_liquidity {
 mod tests {
     use crate::fixtures::tests::fetch_reserves;
     use crate::fixtures::tests::setup_test_environment;
-    use crate::instructions::add_liquidity::MINIMUM_LIQUIDITY;
 
-    use crate::fixtures::tests::setup_amm;
-    use crate::fixtures::tests::setup_mints_and_accounts;
-    use crate::fixtures::tests::setup_user_accounts;
-    use crate::instructions::add_liquidity::calculate_shares;
-    use solana_client::nonblocking::rpc_client::RpcClient;
+    use crate::curve::deposit_quote;
+    use crate::fixtures::tests::TestEnvironment;
     use solana_sdk::pubkey::Pubkey;
-    use solana_sdk::{
-        signature::{Keypair, Signer},
-        transaction::Transaction,
-    };
+    use solana_sdk::{signature::Signer, transaction::Transaction};
     use solana_transaction_status::option_serializer::OptionSerializer;
     use std::str::FromStr;
-    use std::sync::Arc;
 
     pub fn add_liquidity_instruction(
         program_id: &solana_sdk::pubkey::Pubkey,
@@ -202,17 +242,21 @@ mod tests {
         quote_pubkey: &solana_sdk::pubkey::Pubkey,
         payer_pubkey: &solana_sdk::pubkey::Pubkey,
         lp_mint_pubkey: &solana_sdk::pubkey::Pubkey,
-        base_amount: u64,
-        quote_amount: u64,
+        max_base: u64,
+        max_quote: u64,
         min_liquidity: u64,
     ) -> solana_sdk::instruction::Instruction {
         let mut data = switchboard_solana::get_ixn_discriminator("add_liquidity").to_vec();
-        data.extend_from_slice(&base_amount.to_le_bytes());
-        data.extend_from_slice(&quote_amount.to_le_bytes());
+        data.extend_from_slice(&max_base.to_le_bytes());
+        data.extend_from_slice(&max_quote.to_le_bytes());
         data.extend_from_slice(&min_liquidity.to_le_bytes());
         // Construct the accounts required for the add_liquidity_instruction
         let accounts = vec![
             solana_sdk::instruction::AccountMeta::new(*amm_pubkey, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(
+                Pubkey::find_program_address(&[b"global_parameters"], program_id).0,
+                false,
+            ),
             solana_sdk::instruction::AccountMeta::new_readonly(*payer_pubkey, true),
             solana_sdk::instruction::AccountMeta::new(
                 spl_associated_token_account::get_associated_token_address_with_program_id(
@@ -259,10 +303,13 @@ mod tests {
             solana_sdk::instruction::AccountMeta::new(*quote_pubkey, false),
             solana_sdk::instruction::AccountMeta::new_readonly(spl_token_2022::ID, false),
             solana_sdk::instruction::AccountMeta::new_readonly(spl_token::ID, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(spl_token::ID, false),
             solana_sdk::instruction::AccountMeta::new_readonly(
                 solana_program::system_program::ID,
                 false,
             ),
+            // No treasury LP account, fees are charged on top
+            solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
             solana_sdk::instruction::AccountMeta::new_readonly(
                 Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
                 false,
@@ -281,9 +328,13 @@ mod tests {
     #[tokio::test]
     async fn test_events_on_add_liquidity_success() {
         let setup = setup_test_environment(true).await;
-        let base_amount = 100_000;
-        let quote_amount = 100_000;
+        let max_base = 100_000;
+        let max_quote = 100_000;
         let min_lp_shares = 1;
+        // Only the pair in the ratio of the reserves is deposited
+        let (base_reserve, quote_reserve) = fetch_reserves(&setup).await;
+        let (base_amount, quote_amount) =
+            deposit_quote(max_base, max_quote, base_reserve, quote_reserve).unwrap();
 
         let ix = add_liquidity_instruction(
             &setup.program_id,
@@ -292,8 +343,8 @@ mod tests {
             &setup.quote_mint,
             &setup.keypair.pubkey(),
             &setup.lp_mint,
-            base_amount,
-            quote_amount,
+            max_base,
+            max_quote,
             min_lp_shares,
         );
         let mut tx = Transaction::new_with_payer(&[ix], Some(&setup.keypair.pubkey()));
@@ -503,4 +554,60 @@ mod tests {
             "Quote reserve should be updated after add liquidity transaction"
         );
     }
+
+    async fn token_balance(setup: &TestEnvironment, token_account: &Pubkey) -> u64 {
+        setup
+            .client
+            .get_token_account_balance(token_account)
+            .await
+            .unwrap()
+            .amount
+            .parse::<u64>()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_add_liquidity_refunds_excess() {
+        let setup = setup_test_environment(true).await;
+        let (base_reserve, quote_reserve) = fetch_reserves(&setup).await;
+        let quote_before = token_balance(&setup, &setup.user_quote).await;
+
+        // Far more quote than the base bound allows at the current price
+        let max_base = 10_000;
+        let max_quote = quote_reserve;
+        let (base_amount, quote_amount) =
+            deposit_quote(max_base, max_quote, base_reserve, quote_reserve).unwrap();
+        let ix = add_liquidity_instruction(
+            &setup.program_id,
+            &setup.amm_account,
+            &setup.base_mint,
+            &setup.quote_mint,
+            &setup.keypair.pubkey(),
+            &setup.lp_mint,
+            max_base,
+            max_quote,
+            1,
+        );
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        setup
+            .client
+            .send_and_confirm_transaction(&tx)
+            .await
+            .unwrap();
+
+        assert_eq!(base_amount, max_base);
+        assert_eq!(
+            fetch_reserves(&setup).await,
+            (base_reserve + base_amount, quote_reserve + quote_amount)
+        );
+        assert_eq!(
+            quote_before - token_balance(&setup, &setup.user_quote).await,
+            quote_amount,
+            "Only the quote matching the base deposit should be transferred"
+        );
+    }
 }
@@ -1,6 +1,10 @@
+use crate::curve::{self, Rounding};
 use crate::error::ErrorCode;
-use crate::utils::{spl_token_transfer, TokenTransferParams};
-use crate::{Amm, Buy};
+use crate::utils::{
+    amount_after_transfer_fee, amount_with_transfer_fee, check_reserve_balances, load_referrer,
+    spl_token_transfer, SwapAmounts, TokenTransferParams,
+};
+use crate::{Amm, Buy, FeeConfig, PAUSE_SWAPS};
 use anchor_lang::prelude::*;
 
 // BuyEvent event
@@ -13,19 +17,7 @@ pub struct BuyEvent {
     pub protocol_fee_amount: u64,
     pub referrer: Option<Pubkey>,
     pub referrer_fee_amount: Option<u64>,
-}
-
-/// Calculates the required quote amount to obtain a specified amount of base tokens.
-///
-/// # Parameters:
-/// - `output_amount`: Desired amount of base tokens.
-/// - `amm`: Reference to the current state of the AMM.
-///
-/// # Returns:
-/// - Result containing the calculated quote amount or an error.
-fn buy_quote(output_amount: u64, amm: &Amm) -> Result<u64> {
-    let input_amount = (output_amount * amm.quote_reserve) / (amm.base_reserve - output_amount);
-    Ok(input_amount)
+    pub lp_fee_amount: u64,
 }
 
 // This module is responsible for processing buy transactions where users exchange quote tokens for base tokens.
@@ -46,142 +38,228 @@ pub mod buy {
         base_amount: u64,
         max_quote_amount: u64,
     ) -> Result<()> {
-        let quote_token_program = ctx.accounts.quote_token_program.to_account_info();
-        let base_token_program = ctx.accounts.base_token_program.to_account_info();
-
-        // Calculate the required quote amount for the desired base amount
-        let quote_amount = buy_quote(base_amount, &*ctx.accounts.amm)?;
+        let fees = ctx.accounts.amm.fee_config(&ctx.accounts.global_parameters);
+        let amounts = quote_buy(
+            &ctx.accounts.amm,
+            &fees,
+            &ctx.accounts.base_mint.to_account_info(),
+            &ctx.accounts.quote_mint.to_account_info(),
+            base_amount,
+        )?;
 
         // Ensure the quote amount does not exceed what the user is willing to pay
         require_gte!(
             max_quote_amount,
-            quote_amount,
+            amounts.quote_amount,
             ErrorCode::InsufficientQuoteAmount
         );
 
-        // Decrease base reserve and increase quote reserve by the transaction amounts
-        let amm = &mut ctx.accounts.amm;
-        amm.base_reserve -= base_amount;
-        amm.quote_reserve += quote_amount;
-
-        // Define seeds for signing transactions involving the AMM
-        let signer_seeds = [
-            b"amm",
-            ctx.accounts.amm.creator.as_ref(),
-            ctx.accounts.base_mint.to_account_info().key.as_ref(),
-            ctx.accounts.quote_mint.to_account_info().key.as_ref(),
-            &[ctx.bumps.amm],
-        ];
+        process_buy(ctx, amounts)
+    }
+}
 
-        let mut protocol_fee_bps = ctx.accounts.global_parameters.protocol_fee_bps;
-        let mut referrer_fee_amount = None;
-        // Transfer the fee to refferer if set and apply protocol fee discount.
-        if ctx.remaining_accounts.len() > 0 {
-            protocol_fee_bps -= ctx.accounts.global_parameters.referrer_fee_discount_bps;
-            let referrer_fee_bps = ctx.accounts.global_parameters.referrer_fee_bps;
-            referrer_fee_amount = Some((quote_amount * referrer_fee_bps) / 10000);
-
-            spl_token_transfer(TokenTransferParams {
-                source: ctx.accounts.user_quote_ata.to_account_info(),
-                // This account is checked in one of the token program instructions
-                destination: ctx.remaining_accounts.get(0).unwrap().to_account_info(),
-                amount: referrer_fee_amount.unwrap(),
-                authority: ctx.accounts.user.to_account_info(),
-                authority_signer_seeds: &[],
-                decimals: ctx.accounts.quote_mint.decimals,
-                mint: ctx.accounts.quote_mint.to_account_info(),
-                token_program: quote_token_program.clone(),
-            })?;
-        }
+/// Quotes a buy of exactly `base_amount` base tokens, as received by the user.
+///
+/// The base reserve pays out `base_amount` plus the base mint's transfer fee, and the user pays
+/// the curve price grossed up by the LP fee and then by the quote mint's transfer fee. The quote
+/// reserve is credited with what actually lands in the vault, any rounding of the gross up
+/// counting towards the LP fee.
+pub(crate) fn quote_buy(
+    amm: &Amm,
+    fees: &FeeConfig,
+    base_mint: &AccountInfo,
+    quote_mint: &AccountInfo,
+    base_amount: u64,
+) -> Result<SwapAmounts> {
+    let base_reserve_amount = amount_with_transfer_fee(base_mint, base_amount)?;
+    let swap_quote_amount = amm.invariant(Clock::get()?.unix_timestamp).buy_quote(
+        base_reserve_amount,
+        amm.base_reserve,
+        amm.quote_reserve,
+    )?;
+    let quote_amount = amount_with_transfer_fee(
+        quote_mint,
+        curve::amount_with_fee(swap_quote_amount, fees.lp_fee_bps)?,
+    )?;
+    let quote_reserve_amount = amount_after_transfer_fee(quote_mint, quote_amount)?;
+
+    Ok(SwapAmounts {
+        base_amount,
+        quote_amount,
+        base_reserve_amount,
+        quote_reserve_amount,
+        lp_fee_amount: quote_reserve_amount - swap_quote_amount,
+    })
+}
 
-        // Transfer the protocol fee to the fee receiver
-        let protocol_fee_amount = (quote_amount * protocol_fee_bps) / 10000;
-        spl_token_transfer(TokenTransferParams {
-            source: ctx.accounts.user_quote_ata.to_account_info(),
-            destination: ctx.accounts.fee_receiver_ata.to_account_info(),
-            amount: protocol_fee_amount,
-            authority: ctx.accounts.user.to_account_info(),
-            authority_signer_seeds: &[],
-            decimals: ctx.accounts.quote_mint.decimals,
-            mint: ctx.accounts.quote_mint.to_account_info(),
-            token_program: quote_token_program.clone(),
-        })?;
+/// Settles a quoted buy: updates the reserves, pays the referrer and protocol fees,
+/// moves the tokens and emits the `BuyEvent`.
+///
+/// Shared by `buy` and `buy_exact_in`, which only differ in which side of the swap is fixed.
+///
+/// # Parameters:
+/// - `ctx`: Context containing all required accounts for the transaction.
+/// - `amounts`: The quoted amounts, see `SwapAmounts`.
+///
+/// # Returns:
+/// - Result indicating success or an error.
+pub(crate) fn process_buy<'a>(
+    ctx: anchor_lang::context::Context<'_, '_, '_, 'a, Buy<'a>>,
+    amounts: SwapAmounts,
+) -> Result<()> {
+    let SwapAmounts {
+        base_amount,
+        quote_amount,
+        base_reserve_amount,
+        quote_reserve_amount,
+        lp_fee_amount,
+    } = amounts;
+    ctx.accounts
+        .amm
+        .require_not_paused(&ctx.accounts.global_parameters, PAUSE_SWAPS)?;
+    let quote_token_program = ctx.accounts.quote_token_program.to_account_info();
+    let base_token_program = ctx.accounts.base_token_program.to_account_info();
+
+    // Decrease base reserve and increase quote reserve by what actually moves through the vaults
+    let amm = &mut ctx.accounts.amm;
+    let now = Clock::get()?.unix_timestamp;
+    amm.update_price_accumulators(now);
+    if let Some(observations) = ctx.accounts.observations.as_mut() {
+        observations.write(amm.observe(now));
+    }
+    amm.base_reserve = amm
+        .base_reserve
+        .checked_sub(base_reserve_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    amm.quote_reserve = amm
+        .quote_reserve
+        .checked_add(quote_reserve_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    // Define seeds for signing transactions involving the AMM
+    let signer_seeds = [
+        b"amm",
+        ctx.accounts.amm.creator.as_ref(),
+        ctx.accounts.base_mint.to_account_info().key.as_ref(),
+        ctx.accounts.quote_mint.to_account_info().key.as_ref(),
+        &[ctx.bumps.amm],
+    ];
+
+    // Use the pool's own fees when set, the global ones otherwise
+    let fees = ctx.accounts.amm.fee_config(&ctx.accounts.global_parameters);
+    let mut protocol_fee_bps = fees.protocol_fee_bps;
+    let mut referrer_fee_amount = None;
+    // Transfer the fee to the referrer if a registered one is set and apply its protocol fee discount
+    let referrer = load_referrer(ctx.remaining_accounts, &ctx.accounts.quote_mint.key())?;
+    if let Some(mut referrer) = referrer {
+        let fee_tier = referrer.referrer.fee_tier(&fees, ctx.accounts.amm.fee_mode);
+        protocol_fee_bps = protocol_fee_bps.saturating_sub(fee_tier.referrer_fee_discount_bps);
+        let referrer_fee =
+            curve::apply_bps(quote_amount, fee_tier.referrer_fee_bps, Rounding::Down)?;
+        referrer_fee_amount = Some(referrer_fee);
 
-        // Transfer the quote amount to the AMM reserves
         spl_token_transfer(TokenTransferParams {
             source: ctx.accounts.user_quote_ata.to_account_info(),
-            destination: ctx.accounts.quote_reserve_ata.to_account_info(),
-            amount: quote_amount,
+            destination: referrer.token_account.clone(),
+            amount: referrer_fee,
             authority: ctx.accounts.user.to_account_info(),
-            authority_signer_seeds: &signer_seeds,
+            authority_signer_seeds: &[],
             decimals: ctx.accounts.quote_mint.decimals,
             mint: ctx.accounts.quote_mint.to_account_info(),
             token_program: quote_token_program.clone(),
         })?;
-
-        // Transfer the purchased base amount to the user
-        spl_token_transfer(TokenTransferParams {
-            source: ctx.accounts.base_reserve_ata.to_account_info(),
-            destination: ctx.accounts.user_base_ata.to_account_info(),
-            amount: base_amount,
-            authority: ctx.accounts.amm.to_account_info(),
-            authority_signer_seeds: &signer_seeds,
-            decimals: ctx.accounts.base_mint.decimals,
-            mint: ctx.accounts.base_mint.to_account_info(),
-            token_program: base_token_program.clone(),
-        })?;
-
-        // Emit the event
-        emit_cpi!(BuyEvent {
-            base_amount,
-            quote_amount,
-            user: *ctx.accounts.user.to_account_info().key,
-            timestamp: Clock::get()?.unix_timestamp as u64,
-            referrer: ctx
-                .remaining_accounts
-                .get(0)
-                .map(|r| *r.to_account_info().key),
-            referrer_fee_amount,
-            protocol_fee_amount
-        });
-
-        emit!(BuyEvent {
-            base_amount,
-            quote_amount,
-            user: *ctx.accounts.user.to_account_info().key,
-            timestamp: Clock::get()?.unix_timestamp as u64,
-            referrer: ctx
-                .remaining_accounts
-                .get(0)
-                .map(|r| *r.to_account_info().key),
-            referrer_fee_amount,
-            protocol_fee_amount
-        });
-
-        Ok(())
+        referrer.record(quote_amount, referrer_fee)?;
     }
+
+    // Transfer the protocol fee to the fee receiver
+    let protocol_fee_amount = curve::apply_bps(quote_amount, protocol_fee_bps, Rounding::Down)?;
+    spl_token_transfer(TokenTransferParams {
+        source: ctx.accounts.user_quote_ata.to_account_info(),
+        destination: ctx.accounts.fee_receiver_ata.to_account_info(),
+        amount: protocol_fee_amount,
+        authority: ctx.accounts.user.to_account_info(),
+        authority_signer_seeds: &[],
+        decimals: ctx.accounts.quote_mint.decimals,
+        mint: ctx.accounts.quote_mint.to_account_info(),
+        token_program: quote_token_program.clone(),
+    })?;
+
+    // Transfer the quote amount to the AMM reserves
+    spl_token_transfer(TokenTransferParams {
+        source: ctx.accounts.user_quote_ata.to_account_info(),
+        destination: ctx.accounts.quote_reserve_ata.to_account_info(),
+        amount: quote_amount,
+        authority: ctx.accounts.user.to_account_info(),
+        authority_signer_seeds: &signer_seeds,
+        decimals: ctx.accounts.quote_mint.decimals,
+        mint: ctx.accounts.quote_mint.to_account_info(),
+        token_program: quote_token_program.clone(),
+    })?;
+
+    // Transfer the purchased base amount to the user, the transfer fee is withheld on the way
+    spl_token_transfer(TokenTransferParams {
+        source: ctx.accounts.base_reserve_ata.to_account_info(),
+        destination: ctx.accounts.user_base_ata.to_account_info(),
+        amount: base_reserve_amount,
+        authority: ctx.accounts.amm.to_account_info(),
+        authority_signer_seeds: &signer_seeds,
+        decimals: ctx.accounts.base_mint.decimals,
+        mint: ctx.accounts.base_mint.to_account_info(),
+        token_program: base_token_program.clone(),
+    })?;
+
+    // Make sure the vaults still back the recorded reserves
+    check_reserve_balances(
+        &ctx.accounts.amm,
+        &mut ctx.accounts.base_reserve_ata,
+        &mut ctx.accounts.quote_reserve_ata,
+    )?;
+
+    // Emit the event
+    emit_cpi!(BuyEvent {
+        base_amount,
+        quote_amount,
+        user: *ctx.accounts.user.to_account_info().key,
+        timestamp: Clock::get()?.unix_timestamp as u64,
+        referrer: ctx
+            .remaining_accounts
+            .get(0)
+            .map(|r| *r.to_account_info().key),
+        referrer_fee_amount,
+        protocol_fee_amount,
+        lp_fee_amount,
+    });
+
+    emit!(BuyEvent {
+        base_amount,
+        quote_amount,
+        user: *ctx.accounts.user.to_account_info().key,
+        timestamp: Clock::get()?.unix_timestamp as u64,
+        referrer: ctx
+            .remaining_accounts
+            .get(0)
+            .map(|r| *r.to_account_info().key),
+        referrer_fee_amount,
+        protocol_fee_amount,
+        lp_fee_amount,
+    });
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use crate::fixtures::tests::fetch_reserves;
-    use crate::fixtures::tests::setup_amm;
-    use crate::fixtures::tests::setup_mints_and_accounts;
     use crate::fixtures::tests::setup_test_environment;
-    use crate::fixtures::tests::setup_user_accounts;
-    use crate::fixtures::tests::TestEnvironment;
     use crate::Pubkey;
     use std::str::FromStr;
 
     use anchor_lang::AccountDeserialize;
-    use solana_client::nonblocking::rpc_client::RpcClient;
-    use solana_sdk::program_pack::Pack;
     use solana_sdk::{
         signature::{Keypair, Signer},
         transaction::Transaction,
     };
     use solana_transaction_status::option_serializer::OptionSerializer;
-    use std::sync::Arc;
 
     fn buy_instruction(
         program_id: &solana_sdk::pubkey::Pubkey,
@@ -190,7 +268,7 @@ mod tests {
         base_pubkey: &solana_sdk::pubkey::Pubkey,
         quote_pubkey: &solana_sdk::pubkey::Pubkey,
         payer_pubkey: &solana_sdk::pubkey::Pubkey,
-        referrer_pubkey: &solana_sdk::pubkey::Pubkey,
+        referrer_pubkey: Option<&solana_sdk::pubkey::Pubkey>,
         base_amount: u64,
         max_quote_amount: u64,
     ) -> solana_sdk::instruction::Instruction {
@@ -198,7 +276,7 @@ mod tests {
         data.extend_from_slice(&base_amount.to_le_bytes());
         data.extend_from_slice(&max_quote_amount.to_le_bytes());
         // Construct the accounts required for the buy_instruction
-        let accounts = vec![
+        let mut accounts = vec![
             solana_sdk::instruction::AccountMeta::new(*amm_pubkey, false),
             solana_sdk::instruction::AccountMeta::new(*global_parameters, false),
             solana_sdk::instruction::AccountMeta::new_readonly(*payer_pubkey, true),
@@ -236,7 +314,7 @@ mod tests {
             ),
             solana_sdk::instruction::AccountMeta::new(
                 spl_associated_token_account::get_associated_token_address_with_program_id(
-                    &payer_pubkey,
+                    &Pubkey::find_program_address(&[b"fee_authority"], program_id).0,
                     &quote_pubkey,
                     &spl_token::ID,
                 ),
@@ -246,20 +324,30 @@ mod tests {
             solana_sdk::instruction::AccountMeta::new(*quote_pubkey, false),
             solana_sdk::instruction::AccountMeta::new_readonly(spl_token_2022::ID, false),
             solana_sdk::instruction::AccountMeta::new_readonly(spl_token::ID, false),
+            // No observations account
+            solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
             solana_sdk::instruction::AccountMeta::new_readonly(
                 Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
                 false,
             ),
             solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
-            solana_sdk::instruction::AccountMeta::new(
+        ];
+        // The referrer's quote token account followed by its registry account
+        if let Some(referrer_pubkey) = referrer_pubkey {
+            accounts.push(solana_sdk::instruction::AccountMeta::new(
                 spl_associated_token_account::get_associated_token_address_with_program_id(
-                    &referrer_pubkey,
-                    &quote_pubkey,
+                    referrer_pubkey,
+                    quote_pubkey,
                     &spl_token::ID,
                 ),
                 false,
-            ),
-        ];
+            ));
+            accounts.push(solana_sdk::instruction::AccountMeta::new(
+                Pubkey::find_program_address(&[b"referrer", referrer_pubkey.as_ref()], program_id)
+                    .0,
+                false,
+            ));
+        }
         // Create the instruction using the program_id, accounts, and data
         solana_sdk::instruction::Instruction {
             program_id: *program_id,
@@ -280,7 +368,7 @@ mod tests {
             &setup.base_mint,
             &setup.quote_mint,
             &setup.keypair.pubkey(),
-            &setup.keypair.pubkey(),
+            None,
             base_amount,
             max_quote_amount,
         );
@@ -306,7 +394,7 @@ mod tests {
             &setup.base_mint,
             &setup.quote_mint,
             &setup.keypair.pubkey(),
-            &setup.keypair.pubkey(),
+            None,
             base_amount,
             max_quote_amount,
         );
@@ -358,7 +446,7 @@ mod tests {
             &setup.base_mint,
             &setup.quote_mint,
             &setup.keypair.pubkey(),
-            &setup.keypair.pubkey(),
+            None,
             base_amount,
             max_quote_amount,
         );
@@ -390,7 +478,7 @@ mod tests {
             &setup.base_mint,
             &setup.quote_mint,
             &setup.keypair.pubkey(),
-            &setup.keypair.pubkey(),
+            None,
             base_amount,
             max_quote_amount,
         );
@@ -431,7 +519,7 @@ mod tests {
             &setup.base_mint,
             &setup.quote_mint,
             &setup.keypair.pubkey(),
-            &setup.keypair.pubkey(),
+            None,
             base_amount,
             max_quote_amount,
         );
@@ -467,7 +555,7 @@ mod tests {
             .client
             .get_token_account_balance(
                 &spl_associated_token_account::get_associated_token_address_with_program_id(
-                    &setup.keypair.pubkey(),
+                    &Pubkey::find_program_address(&[b"fee_authority"], &setup.program_id).0,
                     &setup.quote_mint,
                     &spl_token::ID,
                 ),
@@ -485,7 +573,7 @@ mod tests {
             &setup.base_mint,
             &setup.quote_mint,
             &setup.keypair.pubkey(),
-            &setup.keypair.pubkey(),
+            None,
             base_amount,
             max_quote_amount,
         );
@@ -506,7 +594,7 @@ mod tests {
             .client
             .get_token_account_balance(
                 &spl_associated_token_account::get_associated_token_address_with_program_id(
-                    &setup.keypair.pubkey(),
+                    &Pubkey::find_program_address(&[b"fee_authority"], &setup.program_id).0,
                     &setup.quote_mint,
                     &spl_token::ID,
                 ),
@@ -523,14 +611,48 @@ mod tests {
         );
     }
 
+    fn register_referrer_instruction(
+        program_id: &solana_sdk::pubkey::Pubkey,
+        owner_pubkey: &solana_sdk::pubkey::Pubkey,
+    ) -> solana_sdk::instruction::Instruction {
+        solana_sdk::instruction::Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                solana_sdk::instruction::AccountMeta::new(
+                    Pubkey::find_program_address(&[b"referrer", owner_pubkey.as_ref()], program_id)
+                        .0,
+                    false,
+                ),
+                solana_sdk::instruction::AccountMeta::new(*owner_pubkey, true),
+                solana_sdk::instruction::AccountMeta::new_readonly(
+                    solana_program::system_program::ID,
+                    false,
+                ),
+                solana_sdk::instruction::AccountMeta::new_readonly(
+                    Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+                    false,
+                ),
+                solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
+            ],
+            data: switchboard_solana::get_ixn_discriminator("register_referrer").to_vec(),
+        }
+    }
+
     #[tokio::test]
     async fn test_buy_with_referrer() {
         let setup = setup_test_environment(true).await;
         let base_amount = 100_000;
         let max_quote_amount = 1001001;
 
-        // Create a new keypair for the referrer
+        // Create a new keypair for the referrer, funded to pay for its registration
         let referrer_keypair = Keypair::new();
+        let fund_referrer_ix = solana_sdk::system_instruction::transfer(
+            &setup.keypair.pubkey(),
+            &referrer_keypair.pubkey(),
+            10_000_000,
+        );
+        let register_referrer_ix =
+            register_referrer_instruction(&setup.program_id, &referrer_keypair.pubkey());
 
         // Create an associated token account for the referrer with the setup keypair as the payer
         let referrer_ata_ix =
@@ -549,15 +671,17 @@ mod tests {
             &setup.base_mint,
             &setup.quote_mint,
             &setup.keypair.pubkey(),
-            &referrer_keypair.pubkey(),
+            Some(&referrer_keypair.pubkey()),
             base_amount,
             max_quote_amount,
         );
 
-        let mut tx =
-            Transaction::new_with_payer(&[referrer_ata_ix, ix], Some(&setup.keypair.pubkey()));
+        let mut tx = Transaction::new_with_payer(
+            &[fund_referrer_ix, register_referrer_ix, referrer_ata_ix, ix],
+            Some(&setup.keypair.pubkey()),
+        );
         tx.sign(
-            &[&setup.keypair],
+            &[&setup.keypair, &referrer_keypair],
             setup.client.get_latest_blockhash().await.unwrap(),
         );
         let result = setup.client.send_and_confirm_transaction(&tx).await;
@@ -574,6 +698,9 @@ mod tests {
                 &setup.quote_mint,
             ))
             .await
+            .unwrap()
+            .amount
+            .parse::<u64>()
             .unwrap();
         // Calculate expected referrer fee amount
         let global_parameters_info = setup
@@ -588,9 +715,61 @@ mod tests {
 
         // Check if the referrer's ATA balance has increased by the expected referrer fee amount
         assert_eq!(
-            referrer_ata_balance.amount.parse::<u64>().unwrap(),
-            expected_referrer_fee,
+            referrer_ata_balance, expected_referrer_fee,
             "Referrer's token account balance should increase by the expected referrer fee amount"
         );
+
+        // The referred swap is recorded on the referrer account
+        let referrer_info = setup
+            .client
+            .get_account(
+                &Pubkey::find_program_address(
+                    &[b"referrer", referrer_keypair.pubkey().as_ref()],
+                    &setup.program_id,
+                )
+                .0,
+            )
+            .await
+            .unwrap();
+        let mut data: &[u8] = &referrer_info.data;
+        let referrer = crate::state::Referrer::try_deserialize(&mut data).unwrap();
+        assert_eq!(referrer.fees_earned, referrer_ata_balance as u128);
+        assert!(referrer.volume > 0, "Referred volume should be recorded");
+    }
+
+    #[tokio::test]
+    async fn test_buy_with_unregistered_referrer_fails() {
+        let setup = setup_test_environment(true).await;
+        let referrer_keypair = Keypair::new();
+        let referrer_ata_ix =
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &setup.keypair.pubkey(),
+                &referrer_keypair.pubkey(),
+                &setup.quote_mint,
+                &spl_token::ID,
+            );
+        let ix = buy_instruction(
+            &setup.program_id,
+            &setup.global_parameters,
+            &setup.amm_account,
+            &setup.base_mint,
+            &setup.quote_mint,
+            &setup.keypair.pubkey(),
+            Some(&referrer_keypair.pubkey()),
+            100_000,
+            1001001,
+        );
+
+        let mut tx =
+            Transaction::new_with_payer(&[referrer_ata_ix, ix], Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.send_and_confirm_transaction(&tx).await;
+        assert!(
+            result.is_err(),
+            "Buy transaction with an unregistered referrer should fail"
+        );
     }
 }
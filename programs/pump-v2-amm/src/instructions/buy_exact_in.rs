@@ -0,0 +1,220 @@
+use crate::curve;
+use crate::error::ErrorCode;
+use crate::instructions::buy::process_buy;
+use crate::utils::{amount_after_transfer_fee, SwapAmounts};
+use crate::{Amm, Buy, FeeConfig};
+use anchor_lang::prelude::*;
+
+// This module is responsible for processing buy transactions where the user fixes the quote amount spent.
+pub mod buy_exact_in {
+    use super::*;
+
+    /// Executes a buy transaction where a user spends an exact amount of quote tokens on base tokens.
+    ///
+    /// The LP fee is deducted from `quote_amount` before it is swapped, while the protocol and
+    /// referrer fees are charged on top of it exactly as in `buy`. Token-2022 transfer fees are
+    /// withheld from both legs, see `quote_buy_exact_in`.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing all required accounts for the transaction.
+    /// - `quote_amount`: The amount of quote tokens the user wants to spend.
+    /// - `min_base_amount`: The minimum amount of base tokens the user expects to receive.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler<'a>(
+        ctx: Context<'_, '_, '_, 'a, Buy<'a>>,
+        quote_amount: u64,
+        min_base_amount: u64,
+    ) -> Result<()> {
+        let fees = ctx.accounts.amm.fee_config(&ctx.accounts.global_parameters);
+        let amounts = quote_buy_exact_in(
+            &ctx.accounts.amm,
+            &fees,
+            &ctx.accounts.base_mint.to_account_info(),
+            &ctx.accounts.quote_mint.to_account_info(),
+            quote_amount,
+        )?;
+        require_gt!(amounts.base_amount, 0, ErrorCode::ZeroOutputForBuy);
+
+        // Ensure the base amount received is not below what the user expects
+        require_gte!(
+            amounts.base_amount,
+            min_base_amount,
+            ErrorCode::BaseAmountTooLow
+        );
+
+        process_buy(ctx, amounts)
+    }
+}
+
+/// Quotes a buy spending exactly `quote_amount` quote tokens, as paid by the user.
+///
+/// The quote reserve receives `quote_amount` minus the quote mint's transfer fee, the LP fee is
+/// taken from that before it is swapped, and the user receives the curve output minus the base
+/// mint's transfer fee.
+pub(crate) fn quote_buy_exact_in(
+    amm: &Amm,
+    fees: &FeeConfig,
+    base_mint: &AccountInfo,
+    quote_mint: &AccountInfo,
+    quote_amount: u64,
+) -> Result<SwapAmounts> {
+    let quote_reserve_amount = amount_after_transfer_fee(quote_mint, quote_amount)?;
+    let lp_fee_amount = curve::fee_amount(quote_reserve_amount, fees.lp_fee_bps)?;
+    let base_reserve_amount = amm
+        .invariant(Clock::get()?.unix_timestamp)
+        .buy_exact_in_quote(
+            quote_reserve_amount - lp_fee_amount,
+            amm.base_reserve,
+            amm.quote_reserve,
+        )?;
+    let base_amount = amount_after_transfer_fee(base_mint, base_reserve_amount)?;
+
+    Ok(SwapAmounts {
+        base_amount,
+        quote_amount,
+        base_reserve_amount,
+        quote_reserve_amount,
+        lp_fee_amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::fetch_reserves;
+    use crate::fixtures::tests::setup_test_environment;
+    use crate::fixtures::tests::TestEnvironment;
+    use crate::Pubkey;
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+    use std::str::FromStr;
+
+    fn buy_exact_in_instruction(
+        setup: &TestEnvironment,
+        quote_amount: u64,
+        min_base_amount: u64,
+    ) -> solana_sdk::instruction::Instruction {
+        let program_id = &setup.program_id;
+        let global_parameters = &setup.global_parameters;
+        let amm_pubkey = &setup.amm_account;
+        let base_pubkey = &setup.base_mint;
+        let quote_pubkey = &setup.quote_mint;
+        let payer_pubkey = &setup.keypair.pubkey();
+        let mut data = switchboard_solana::get_ixn_discriminator("buy_exact_in").to_vec();
+        data.extend_from_slice(&quote_amount.to_le_bytes());
+        data.extend_from_slice(&min_base_amount.to_le_bytes());
+        // Construct the accounts required for the buy_exact_in_instruction
+        let accounts = vec![
+            solana_sdk::instruction::AccountMeta::new(*amm_pubkey, false),
+            solana_sdk::instruction::AccountMeta::new(*global_parameters, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(*payer_pubkey, true),
+            solana_sdk::instruction::AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    payer_pubkey,
+                    base_pubkey,
+                    &spl_token_2022::ID,
+                ),
+                false,
+            ),
+            solana_sdk::instruction::AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    payer_pubkey,
+                    quote_pubkey,
+                    &spl_token::ID,
+                ),
+                false,
+            ),
+            solana_sdk::instruction::AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    amm_pubkey,
+                    base_pubkey,
+                    &spl_token_2022::ID,
+                ),
+                false,
+            ),
+            solana_sdk::instruction::AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    amm_pubkey,
+                    quote_pubkey,
+                    &spl_token::ID,
+                ),
+                false,
+            ),
+            solana_sdk::instruction::AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &Pubkey::find_program_address(&[b"fee_authority"], program_id).0,
+                    quote_pubkey,
+                    &spl_token::ID,
+                ),
+                false,
+            ),
+            solana_sdk::instruction::AccountMeta::new(*base_pubkey, false),
+            solana_sdk::instruction::AccountMeta::new(*quote_pubkey, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(spl_token_2022::ID, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(spl_token::ID, false),
+            // No observations account
+            solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(
+                Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+                false,
+            ),
+            solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
+        ];
+        // Create the instruction using the program_id, accounts, and data
+        solana_sdk::instruction::Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_buy_exact_in_success() {
+        let setup = setup_test_environment(true).await;
+        let quote_amount = 1_000_000;
+        let min_base_amount = 1;
+
+        let (initial_base_reserve, initial_quote_reserve) = fetch_reserves(&setup).await;
+
+        let ix = buy_exact_in_instruction(&setup, quote_amount, min_base_amount);
+
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.send_and_confirm_transaction(&tx).await;
+        assert!(result.is_ok(), "Buy exact in transaction should succeed");
+
+        let (updated_base_reserve, updated_quote_reserve) = fetch_reserves(&setup).await;
+        assert_eq!(
+            updated_quote_reserve,
+            initial_quote_reserve + quote_amount,
+            "Quote reserve should grow by exactly the quote amount spent"
+        );
+        assert!(
+            updated_base_reserve < initial_base_reserve,
+            "Base reserve should shrink after buy exact in transaction"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_buy_exact_in_failure_insufficient_base() {
+        let setup = setup_test_environment(true).await;
+        let quote_amount = 1_000_000;
+        let min_base_amount = 100_000_000; // Deliberately high to trigger failure
+
+        let ix = buy_exact_in_instruction(&setup, quote_amount, min_base_amount);
+
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.send_and_confirm_transaction(&tx).await;
+        assert!(
+            result.is_err(),
+            "Transaction should fail due to insufficient base amount"
+        );
+    }
+}
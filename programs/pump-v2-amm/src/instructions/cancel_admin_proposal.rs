@@ -0,0 +1,48 @@
+/// This submodule lets the admin withdraw a pending admin proposal before it is accepted, e.g. when
+/// the wrong key was proposed or the proposed key is compromised.
+pub mod cancel_admin_proposal {
+    use crate::error::ErrorCode;
+    use crate::CancelAdminProposal;
+    use anchor_lang::prelude::*;
+
+    // CancelAdminProposalEvent event
+    #[event]
+    pub struct CancelAdminProposalEvent {
+        pub admin: Pubkey,
+        pub proposed_admin: Pubkey,
+        pub timestamp: u64,
+    }
+
+    /// Cancels the pending admin proposal
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing all required accounts for the transaction.
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<CancelAdminProposal>) -> Result<()> {
+        let global_parameters = &mut ctx.accounts.global_parameters;
+        let proposed_admin = global_parameters.proposed_admin;
+        require_keys_neq!(
+            proposed_admin,
+            Pubkey::default(),
+            ErrorCode::NoAdminProposal
+        );
+        global_parameters.proposed_admin = Pubkey::default();
+        global_parameters.proposed_admin_expiry = 0;
+
+        // Emit the event
+        emit_cpi!(CancelAdminProposalEvent {
+            admin: *ctx.accounts.admin.to_account_info().key,
+            proposed_admin,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(CancelAdminProposalEvent {
+            admin: *ctx.accounts.admin.to_account_info().key,
+            proposed_admin,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use anchor_lang::prelude::*;
+
+// CancelParametersEvent event
+#[event]
+pub struct CancelParametersEvent {
+    pub execute_after: i64,
+    pub timestamp: u64,
+}
+
+/// Drops the parameters queued by `queue_parameters` before they are executed.
+pub mod cancel_parameters {
+    use crate::CancelParameters;
+
+    use super::*;
+
+    /// Cancels the queued parameters of the AMM
+    ///
+    /// # Parameters:
+    /// - `ctx`: The context of the AMM.
+    ///
+    /// # Returns:
+    /// - `Result<()>`: A success indicator if the queued parameters are cancelled.
+    pub fn handler(ctx: Context<CancelParameters>) -> Result<()> {
+        let execute_after = ctx.accounts.proposal.execute_after;
+
+        // Emit the event
+        emit_cpi!(CancelParametersEvent {
+            execute_after,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(CancelParametersEvent {
+            execute_after,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::queue_parameters_instruction;
+    use crate::fixtures::tests::setup_test_environment;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+    use std::str::FromStr;
+    use switchboard_solana::Pubkey;
+
+    #[tokio::test]
+    async fn test_cancel_queued_parameters() {
+        let setup = setup_test_environment(true).await;
+        let proposal = Pubkey::find_program_address(&[b"parameters_proposal"], &setup.program_id).0;
+        let cancel_ix = Instruction {
+            program_id: setup.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(setup.global_parameters, false),
+                AccountMeta::new(setup.keypair.pubkey(), true),
+                AccountMeta::new(proposal, false),
+                // No role account, the test keypair is the admin
+                AccountMeta::new_readonly(setup.program_id, false),
+                AccountMeta::new_readonly(
+                    Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+                    false,
+                ),
+                AccountMeta::new_readonly(setup.program_id, false),
+            ],
+            data: switchboard_solana::get_ixn_discriminator("cancel_parameters").to_vec(),
+        };
+
+        let mut tx = Transaction::new_with_payer(
+            &[
+                queue_parameters_instruction(
+                    &setup.program_id,
+                    &setup.global_parameters,
+                    &setup.keypair.pubkey(),
+                ),
+                cancel_ix,
+            ],
+            Some(&setup.keypair.pubkey()),
+        );
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.send_and_confirm_transaction(&tx).await;
+        assert!(result.is_ok(), "Queue and cancel should succeed");
+        assert!(
+            setup.client.get_account(&proposal).await.is_err(),
+            "The cancelled proposal should be closed"
+        );
+    }
+}
@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+// CancelPoolParametersEvent event
+#[event]
+pub struct CancelPoolParametersEvent {
+    pub amm: Pubkey,
+    pub execute_after: i64,
+    pub timestamp: u64,
+}
+
+/// Drops the pool fees queued by `queue_pool_parameters` before they are executed.
+pub mod cancel_pool_parameters {
+    use crate::CancelPoolParameters;
+
+    use super::*;
+
+    /// Cancels the queued parameters of a pool
+    ///
+    /// # Parameters:
+    /// - `ctx`: The context of the AMM.
+    ///
+    /// # Returns:
+    /// - `Result<()>`: A success indicator if the queued parameters are cancelled.
+    pub fn handler(ctx: Context<CancelPoolParameters>) -> Result<()> {
+        let execute_after = ctx.accounts.proposal.execute_after;
+
+        // Emit the event
+        emit_cpi!(CancelPoolParametersEvent {
+            amm: ctx.accounts.amm.key(),
+            execute_after,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(CancelPoolParametersEvent {
+            amm: ctx.accounts.amm.key(),
+            execute_after,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
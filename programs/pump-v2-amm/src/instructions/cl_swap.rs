@@ -0,0 +1,673 @@
+use crate::curve::concentrated::{
+    add_liquidity_delta, compute_swap_step, fee_growth_delta, sqrt_price_at_tick,
+    tick_at_sqrt_price, MAX_SQRT_PRICE_X64, MAX_TICK, MIN_SQRT_PRICE_X64, MIN_TICK,
+};
+use crate::curve::to_u64;
+use crate::error::ErrorCode;
+use crate::utils::{
+    amount_after_transfer_fee, amount_with_transfer_fee, spl_token_transfer, TokenTransferParams,
+};
+use crate::{ClPool, SwapDirection, TickArray, PAUSE_SWAPS};
+use anchor_lang::prelude::*;
+
+// ClSwapEvent event, amounts are the ones paid and received by the user including transfer fees
+#[event]
+pub struct ClSwapEvent {
+    pub pool: Pubkey,
+    pub direction: SwapDirection,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub lp_fee_amount: u64,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub timestamp: u64,
+    pub user: Pubkey,
+}
+
+/// Amounts moved through the vaults by a swap against a `ClPool`.
+pub(crate) struct ClSwapAmounts {
+    /// Input taken by the pool, LP fee included.
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub lp_fee_amount: u64,
+}
+
+/// Walks the curve of `pool` with `amount` of input, crossing initialized ticks on the way, and
+/// updates the pool price, liquidity and fee growth.
+///
+/// Selling base moves the price down, buying base moves it up. The swap stops once the input is
+/// used up or the price reaches `sqrt_price_limit_x64`. `tick_array_infos` must start with the
+/// array holding the current tick and follow in swap direction.
+pub(crate) fn swap(
+    pool: &mut ClPool,
+    pool_key: &Pubkey,
+    tick_array_infos: &[AccountInfo],
+    direction: SwapDirection,
+    amount: u64,
+    sqrt_price_limit_x64: u128,
+) -> Result<ClSwapAmounts> {
+    let base_in = direction == SwapDirection::Sell;
+    let tick_spacing = pool.tick_spacing;
+
+    // A zero limit lets the price move as far as the input takes it
+    let sqrt_price_limit_x64 = match (sqrt_price_limit_x64, base_in) {
+        (0, true) => MIN_SQRT_PRICE_X64,
+        (0, false) => MAX_SQRT_PRICE_X64,
+        (limit, _) => limit,
+    };
+    if base_in {
+        require!(
+            sqrt_price_limit_x64 < pool.sqrt_price_x64
+                && sqrt_price_limit_x64 >= MIN_SQRT_PRICE_X64,
+            ErrorCode::InvalidSqrtPrice
+        );
+    } else {
+        require!(
+            sqrt_price_limit_x64 > pool.sqrt_price_x64
+                && sqrt_price_limit_x64 <= MAX_SQRT_PRICE_X64,
+            ErrorCode::InvalidSqrtPrice
+        );
+    }
+
+    // The arrays must follow each other from the one holding the current tick
+    let step = if base_in {
+        -pool.ticks_per_array()
+    } else {
+        pool.ticks_per_array()
+    };
+    let mut tick_arrays = Vec::with_capacity(tick_array_infos.len());
+    let mut expected_start = pool.tick_array_start(pool.tick_current);
+    for info in tick_array_infos {
+        let tick_array = TickArray::load(info, pool_key)?;
+        require_eq!(
+            tick_array.start_tick_index,
+            expected_start,
+            ErrorCode::InvalidTickArray
+        );
+        expected_start += step;
+        tick_arrays.push(tick_array);
+    }
+
+    let mut amount_remaining = amount as u128;
+    let mut amount_out: u128 = 0;
+    let mut lp_fee_amount: u128 = 0;
+    let mut array_index = 0;
+    while amount_remaining > 0 && pool.sqrt_price_x64 != sqrt_price_limit_x64 {
+        let tick_array = tick_arrays
+            .get_mut(array_index)
+            .ok_or(ErrorCode::TickArraysExhausted)?;
+
+        let (target_tick, initialized) =
+            match tick_array.next_initialized_tick(pool.tick_current, tick_spacing, base_in) {
+                Some(tick) => (tick, true),
+                None => {
+                    // Nothing left to cross in this array: walk to its edge, then to the next one
+                    let edge = if base_in {
+                        tick_array.start_tick_index.max(MIN_TICK)
+                    } else {
+                        (tick_array.start_tick_index + pool.ticks_per_array() - tick_spacing as i32)
+                            .min(MAX_TICK)
+                    };
+                    if (base_in && edge > pool.tick_current)
+                        || (!base_in && edge <= pool.tick_current)
+                    {
+                        array_index += 1;
+                        continue;
+                    }
+                    (edge, false)
+                }
+            };
+
+        let sqrt_price_target_tick = sqrt_price_at_tick(target_tick)?;
+        let sqrt_price_target = if base_in {
+            sqrt_price_target_tick.max(sqrt_price_limit_x64)
+        } else {
+            sqrt_price_target_tick.min(sqrt_price_limit_x64)
+        };
+        let swap_step = compute_swap_step(
+            pool.sqrt_price_x64,
+            sqrt_price_target,
+            pool.liquidity,
+            amount_remaining,
+            pool.fee_bps as u64,
+        )?;
+        amount_remaining = swap_step
+            .amount_in
+            .checked_add(swap_step.fee_amount)
+            .and_then(|consumed| amount_remaining.checked_sub(consumed))
+            .ok_or(ErrorCode::MathOverflow)?;
+        amount_out = amount_out
+            .checked_add(swap_step.amount_out)
+            .ok_or(ErrorCode::MathOverflow)?;
+        lp_fee_amount = lp_fee_amount
+            .checked_add(swap_step.fee_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // Fees are earned in the input token by the liquidity in range
+        let fee_growth = fee_growth_delta(swap_step.fee_amount, pool.liquidity)?;
+        if base_in {
+            pool.fee_growth_global_base_x64 =
+                pool.fee_growth_global_base_x64.wrapping_add(fee_growth);
+        } else {
+            pool.fee_growth_global_quote_x64 =
+                pool.fee_growth_global_quote_x64.wrapping_add(fee_growth);
+        }
+
+        if swap_step.sqrt_price_x64 == sqrt_price_target_tick {
+            if initialized {
+                let liquidity_net = tick_array.tick_mut(target_tick, tick_spacing)?.cross(pool);
+                // Moving down crosses the tick the other way
+                let liquidity_delta = if base_in {
+                    liquidity_net.checked_neg().ok_or(ErrorCode::MathOverflow)?
+                } else {
+                    liquidity_net
+                };
+                pool.liquidity = add_liquidity_delta(pool.liquidity, liquidity_delta)?;
+            }
+            pool.tick_current = if base_in {
+                target_tick - 1
+            } else {
+                target_tick
+            };
+        } else if swap_step.sqrt_price_x64 != pool.sqrt_price_x64 {
+            pool.tick_current = tick_at_sqrt_price(swap_step.sqrt_price_x64)?;
+        }
+        pool.sqrt_price_x64 = swap_step.sqrt_price_x64;
+    }
+
+    for (tick_array, info) in tick_arrays.iter().zip(tick_array_infos) {
+        tick_array.save(info)?;
+    }
+
+    Ok(ClSwapAmounts {
+        amount_in: to_u64((amount as u128) - amount_remaining)?,
+        amount_out: to_u64(amount_out)?,
+        lp_fee_amount: to_u64(lp_fee_amount)?,
+    })
+}
+
+/// The `cl_swap` module is responsible for swaps against concentrated liquidity pools. The
+/// input is exact, the swap crosses as many ticks as needed using the tick arrays passed as
+/// remaining accounts.
+pub mod cl_swap {
+    use super::*;
+    use crate::ClSwap;
+
+    /// Handles a swap of an exact input amount, ensuring the output meets the user's expectations.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing all necessary accounts, followed by the tick arrays to cross.
+    /// - `direction`: `Sell` swaps base for quote, `Buy` swaps quote for base.
+    /// - `amount_in`: The amount of input tokens the user pays.
+    /// - `min_amount_out`: The minimum amount of output tokens the user expects to receive.
+    /// - `sqrt_price_limit_x64`: Price past which the swap stops, 0 for no limit.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler<'a>(
+        ctx: Context<'_, '_, 'a, 'a, ClSwap<'a>>,
+        direction: SwapDirection,
+        amount_in: u64,
+        min_amount_out: u64,
+        sqrt_price_limit_x64: u128,
+    ) -> Result<()> {
+        ctx.accounts
+            .global_parameters
+            .require_not_paused(PAUSE_SWAPS)?;
+        require_gt!(amount_in, 0, ErrorCode::InvalidTokenAmounts);
+        let base_mint = ctx.accounts.base_mint.to_account_info();
+        let quote_mint = ctx.accounts.quote_mint.to_account_info();
+        let (input_mint, output_mint) = match direction {
+            SwapDirection::Sell => (&base_mint, &quote_mint),
+            SwapDirection::Buy => (&quote_mint, &base_mint),
+        };
+
+        // Only what reaches the vault after transfer fees is swapped
+        let amount_in_net = amount_after_transfer_fee(input_mint, amount_in)?;
+        let pool_key = ctx.accounts.pool.key();
+        let amounts = swap(
+            &mut ctx.accounts.pool,
+            &pool_key,
+            ctx.remaining_accounts,
+            direction,
+            amount_in_net,
+            sqrt_price_limit_x64,
+        )?;
+
+        // A swap stopped by the price limit only takes the input it consumed
+        let amount_paid = if amounts.amount_in == amount_in_net {
+            amount_in
+        } else {
+            amount_with_transfer_fee(input_mint, amounts.amount_in)?.min(amount_in)
+        };
+        let amount_received = amount_after_transfer_fee(output_mint, amounts.amount_out)?;
+        match direction {
+            SwapDirection::Sell => {
+                require_gt!(amounts.amount_out, 0, ErrorCode::ZeroOutputForSell);
+                require!(
+                    amount_received >= min_amount_out,
+                    ErrorCode::QuoteAmountTooLow
+                );
+            }
+            SwapDirection::Buy => {
+                require_gt!(amounts.amount_out, 0, ErrorCode::ZeroOutputForBuy);
+                require!(
+                    amount_received >= min_amount_out,
+                    ErrorCode::BaseAmountTooLow
+                );
+            }
+        }
+
+        let pool = &ctx.accounts.pool;
+        let signer_seeds = [
+            b"cl_pool",
+            pool.creator.as_ref(),
+            pool.base_mint.as_ref(),
+            pool.quote_mint.as_ref(),
+            &[pool.bump],
+        ];
+        let base_leg = (
+            ctx.accounts.user_base_ata.to_account_info(),
+            ctx.accounts.base_vault.to_account_info(),
+            ctx.accounts.base_mint.decimals,
+            base_mint.clone(),
+            ctx.accounts.base_token_program.to_account_info(),
+        );
+        let quote_leg = (
+            ctx.accounts.user_quote_ata.to_account_info(),
+            ctx.accounts.quote_vault.to_account_info(),
+            ctx.accounts.quote_mint.decimals,
+            quote_mint.clone(),
+            ctx.accounts.quote_token_program.to_account_info(),
+        );
+        let (input_leg, output_leg) = match direction {
+            SwapDirection::Sell => (base_leg, quote_leg),
+            SwapDirection::Buy => (quote_leg, base_leg),
+        };
+
+        // Transfer the input from the user to the pool
+        spl_token_transfer(TokenTransferParams {
+            source: input_leg.0,
+            destination: input_leg.1,
+            amount: amount_paid,
+            authority: ctx.accounts.user.to_account_info(),
+            authority_signer_seeds: &[],
+            decimals: input_leg.2,
+            mint: input_leg.3,
+            token_program: input_leg.4,
+        })?;
+
+        // Transfer the output from the pool to the user
+        spl_token_transfer(TokenTransferParams {
+            source: output_leg.1,
+            destination: output_leg.0,
+            amount: amounts.amount_out,
+            authority: pool.to_account_info(),
+            authority_signer_seeds: &signer_seeds,
+            decimals: output_leg.2,
+            mint: output_leg.3,
+            token_program: output_leg.4,
+        })?;
+
+        // Emit the event
+        emit_cpi!(ClSwapEvent {
+            pool: pool_key,
+            direction,
+            amount_in: amount_paid,
+            amount_out: amount_received,
+            lp_fee_amount: amounts.lp_fee_amount,
+            sqrt_price_x64: ctx.accounts.pool.sqrt_price_x64,
+            tick_current: ctx.accounts.pool.tick_current,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+            user: ctx.accounts.user.key(),
+        });
+
+        emit!(ClSwapEvent {
+            pool: pool_key,
+            direction,
+            amount_in: amount_paid,
+            amount_out: amount_received,
+            lp_fee_amount: amounts.lp_fee_amount,
+            sqrt_price_x64: ctx.accounts.pool.sqrt_price_x64,
+            tick_current: ctx.accounts.pool.tick_current,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+            user: ctx.accounts.user.key(),
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::setup_test_environment;
+    use crate::fixtures::tests::TestEnvironment;
+    use crate::Pubkey;
+    use crate::SwapDirection;
+    use anchor_lang::AnchorSerialize;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+    use std::str::FromStr;
+
+    const TICK_SPACING: u16 = 10;
+    const TICK_LOWER: i32 = -100;
+    const TICK_UPPER: i32 = 100;
+    const LIQUIDITY: u128 = 1_000_000_000;
+
+    struct ClTestEnvironment {
+        pool: Pubkey,
+        position: Pubkey,
+        // Tick arrays starting at 0 and at -320, the ones crossed when selling from price 1
+        tick_arrays: [Pubkey; 2],
+        pool_base_vault: Pubkey,
+        pool_quote_vault: Pubkey,
+    }
+
+    fn event_accounts(setup: &TestEnvironment) -> [AccountMeta; 2] {
+        [
+            AccountMeta::new_readonly(
+                Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+                false,
+            ),
+            AccountMeta::new_readonly(setup.program_id, false),
+        ]
+    }
+
+    fn create_cl_pool_instruction(setup: &TestEnvironment, cl: &ClTestEnvironment) -> Instruction {
+        let mut data = switchboard_solana::get_ixn_discriminator("create_cl_pool").to_vec();
+        data.extend_from_slice(&TICK_SPACING.to_le_bytes());
+        data.extend_from_slice(&30u16.to_le_bytes());
+        data.extend_from_slice(&(1u128 << 64).to_le_bytes());
+        let mut accounts = vec![
+            AccountMeta::new(cl.pool, false),
+            AccountMeta::new(setup.keypair.pubkey(), true),
+            AccountMeta::new(cl.pool_base_vault, false),
+            AccountMeta::new(cl.pool_quote_vault, false),
+            AccountMeta::new_readonly(setup.base_mint, false),
+            AccountMeta::new_readonly(setup.quote_mint, false),
+            AccountMeta::new_readonly(spl_token_2022::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ];
+        accounts.extend(event_accounts(setup));
+        Instruction {
+            program_id: setup.program_id,
+            accounts,
+            data,
+        }
+    }
+
+    fn initialize_tick_array_instruction(
+        setup: &TestEnvironment,
+        cl: &ClTestEnvironment,
+        start_tick_index: i32,
+        tick_array: Pubkey,
+    ) -> Instruction {
+        let mut data = switchboard_solana::get_ixn_discriminator("initialize_tick_array").to_vec();
+        data.extend_from_slice(&start_tick_index.to_le_bytes());
+        Instruction {
+            program_id: setup.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(cl.pool, false),
+                AccountMeta::new(tick_array, false),
+                AccountMeta::new(setup.keypair.pubkey(), true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+            data,
+        }
+    }
+
+    fn open_position_instruction(setup: &TestEnvironment, cl: &ClTestEnvironment) -> Instruction {
+        let mut data = switchboard_solana::get_ixn_discriminator("open_position").to_vec();
+        data.extend_from_slice(&TICK_LOWER.to_le_bytes());
+        data.extend_from_slice(&TICK_UPPER.to_le_bytes());
+        let mut accounts = vec![
+            AccountMeta::new_readonly(cl.pool, false),
+            AccountMeta::new(cl.position, false),
+            AccountMeta::new(setup.keypair.pubkey(), true),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ];
+        accounts.extend(event_accounts(setup));
+        Instruction {
+            program_id: setup.program_id,
+            accounts,
+            data,
+        }
+    }
+
+    fn modify_position_instruction(
+        setup: &TestEnvironment,
+        cl: &ClTestEnvironment,
+        name: &str,
+        args: &[u8],
+    ) -> Instruction {
+        let mut data = switchboard_solana::get_ixn_discriminator(name).to_vec();
+        data.extend_from_slice(args);
+        // Both bounds of the test position live in different arrays
+        let mut accounts = vec![
+            AccountMeta::new(cl.pool, false),
+            AccountMeta::new_readonly(setup.global_parameters, false),
+            AccountMeta::new(cl.position, false),
+            AccountMeta::new_readonly(setup.keypair.pubkey(), true),
+            AccountMeta::new(cl.tick_arrays[1], false),
+            AccountMeta::new(cl.tick_arrays[0], false),
+            AccountMeta::new(cl.pool_base_vault, false),
+            AccountMeta::new(cl.pool_quote_vault, false),
+            AccountMeta::new(setup.user_base, false),
+            AccountMeta::new(setup.user_quote, false),
+            AccountMeta::new_readonly(setup.base_mint, false),
+            AccountMeta::new_readonly(setup.quote_mint, false),
+            AccountMeta::new_readonly(spl_token_2022::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+        ];
+        accounts.extend(event_accounts(setup));
+        Instruction {
+            program_id: setup.program_id,
+            accounts,
+            data,
+        }
+    }
+
+    fn cl_swap_instruction(
+        setup: &TestEnvironment,
+        cl: &ClTestEnvironment,
+        direction: SwapDirection,
+        amount_in: u64,
+        min_amount_out: u64,
+        tick_arrays: &[Pubkey],
+    ) -> Instruction {
+        let mut data = switchboard_solana::get_ixn_discriminator("cl_swap").to_vec();
+        direction.serialize(&mut data).unwrap();
+        data.extend_from_slice(&amount_in.to_le_bytes());
+        data.extend_from_slice(&min_amount_out.to_le_bytes());
+        data.extend_from_slice(&0u128.to_le_bytes());
+        let mut accounts = vec![
+            AccountMeta::new(cl.pool, false),
+            AccountMeta::new_readonly(setup.global_parameters, false),
+            AccountMeta::new_readonly(setup.keypair.pubkey(), true),
+            AccountMeta::new(setup.user_base, false),
+            AccountMeta::new(setup.user_quote, false),
+            AccountMeta::new(cl.pool_base_vault, false),
+            AccountMeta::new(cl.pool_quote_vault, false),
+            AccountMeta::new_readonly(setup.base_mint, false),
+            AccountMeta::new_readonly(setup.quote_mint, false),
+            AccountMeta::new_readonly(spl_token_2022::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+        ];
+        accounts.extend(event_accounts(setup));
+        accounts.extend(tick_arrays.iter().map(|key| AccountMeta::new(*key, false)));
+        Instruction {
+            program_id: setup.program_id,
+            accounts,
+            data,
+        }
+    }
+
+    async fn send(setup: &TestEnvironment, ixs: &[Instruction]) -> bool {
+        let mut tx = Transaction::new_with_payer(ixs, Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        setup.client.send_and_confirm_transaction(&tx).await.is_ok()
+    }
+
+    async fn balance(setup: &TestEnvironment, account: &Pubkey) -> u64 {
+        setup
+            .client
+            .get_token_account_balance(account)
+            .await
+            .unwrap()
+            .amount
+            .parse::<u64>()
+            .unwrap()
+    }
+
+    /// Creates a pool at price 1 with a single position around it.
+    async fn setup_cl_pool(setup: &TestEnvironment) -> ClTestEnvironment {
+        let user = setup.keypair.pubkey();
+        let (pool, _) = Pubkey::find_program_address(
+            &[
+                b"cl_pool",
+                user.as_ref(),
+                setup.base_mint.as_ref(),
+                setup.quote_mint.as_ref(),
+            ],
+            &setup.program_id,
+        );
+        let tick_array = |start: i32| {
+            Pubkey::find_program_address(
+                &[b"tick_array", pool.as_ref(), start.to_le_bytes().as_ref()],
+                &setup.program_id,
+            )
+            .0
+        };
+        let (position, _) = Pubkey::find_program_address(
+            &[
+                b"position",
+                pool.as_ref(),
+                user.as_ref(),
+                TICK_LOWER.to_le_bytes().as_ref(),
+                TICK_UPPER.to_le_bytes().as_ref(),
+            ],
+            &setup.program_id,
+        );
+        let cl = ClTestEnvironment {
+            pool,
+            position,
+            tick_arrays: [tick_array(0), tick_array(-320)],
+            pool_base_vault:
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &pool,
+                    &setup.base_mint,
+                    &spl_token_2022::ID,
+                ),
+            pool_quote_vault:
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &pool,
+                    &setup.quote_mint,
+                    &spl_token::ID,
+                ),
+        };
+
+        let mut increase_args = LIQUIDITY.to_le_bytes().to_vec();
+        increase_args.extend_from_slice(&u64::MAX.to_le_bytes());
+        increase_args.extend_from_slice(&u64::MAX.to_le_bytes());
+        assert!(
+            send(
+                setup,
+                &[
+                    create_cl_pool_instruction(setup, &cl),
+                    initialize_tick_array_instruction(setup, &cl, 0, cl.tick_arrays[0]),
+                    initialize_tick_array_instruction(setup, &cl, -320, cl.tick_arrays[1]),
+                    open_position_instruction(setup, &cl),
+                    modify_position_instruction(setup, &cl, "increase_liquidity", &increase_args),
+                ],
+            )
+            .await,
+            "Pool setup should succeed"
+        );
+        cl
+    }
+
+    #[tokio::test]
+    async fn test_cl_swap_sell_success() {
+        let setup = setup_test_environment(false).await;
+        let cl = setup_cl_pool(&setup).await;
+        let initial_quote = balance(&setup, &setup.user_quote).await;
+
+        let ix = cl_swap_instruction(
+            &setup,
+            &cl,
+            SwapDirection::Sell,
+            1_000_000,
+            1,
+            &cl.tick_arrays,
+        );
+        assert!(send(&setup, &[ix]).await, "Swap should succeed");
+
+        let updated_quote = balance(&setup, &setup.user_quote).await;
+        assert!(
+            updated_quote > initial_quote,
+            "Selling base should pay out quote"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cl_swap_failure_out_of_liquidity() {
+        let setup = setup_test_environment(false).await;
+        let cl = setup_cl_pool(&setup).await;
+
+        // Selling well past the position's lower tick runs out of tick arrays
+        let ix = cl_swap_instruction(
+            &setup,
+            &cl,
+            SwapDirection::Sell,
+            50_000_000,
+            1,
+            &cl.tick_arrays[..1],
+        );
+        assert!(
+            !send(&setup, &[ix]).await,
+            "Transaction should fail because the tick arrays are exhausted"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_collect_fees_and_decrease_liquidity_success() {
+        let setup = setup_test_environment(false).await;
+        let cl = setup_cl_pool(&setup).await;
+
+        let ix = cl_swap_instruction(
+            &setup,
+            &cl,
+            SwapDirection::Buy,
+            1_000_000,
+            1,
+            &cl.tick_arrays[..1],
+        );
+        assert!(send(&setup, &[ix]).await, "Swap should succeed");
+
+        // The position earned the LP fee of the quote paid in
+        let initial_quote = balance(&setup, &setup.user_quote).await;
+        let ix = modify_position_instruction(&setup, &cl, "collect_fees", &[]);
+        assert!(send(&setup, &[ix]).await, "Collecting fees should succeed");
+        let updated_quote = balance(&setup, &setup.user_quote).await;
+        assert!(
+            updated_quote > initial_quote,
+            "Collected fees should be paid in quote"
+        );
+
+        // Withdrawing everything leaves only rounding dust in the vaults
+        let mut decrease_args = LIQUIDITY.to_le_bytes().to_vec();
+        decrease_args.extend_from_slice(&0u64.to_le_bytes());
+        decrease_args.extend_from_slice(&0u64.to_le_bytes());
+        let ix = modify_position_instruction(&setup, &cl, "decrease_liquidity", &decrease_args);
+        assert!(
+            send(&setup, &[ix]).await,
+            "Decreasing liquidity should succeed"
+        );
+        assert!(balance(&setup, &cl.pool_quote_vault).await < 10);
+    }
+}
@@ -0,0 +1,69 @@
+use crate::utils::{spl_token_transfer, TokenTransferParams};
+use anchor_lang::prelude::*;
+
+// ClaimLockedFeesEvent event
+#[event]
+pub struct ClaimLockedFeesEvent {
+    pub amm: Pubkey,
+    pub owner: Pubkey,
+    pub fee_shares: u64,
+    pub locked_amount: u64, // shares left in the lock, still worth the locked liquidity
+    pub timestamp: u64,
+}
+
+/// Pays out the fees earned by locked LP shares, permanent locks included.
+pub mod claim_locked_fees {
+    use super::*;
+    use crate::LockedLiquidity;
+
+    /// Transfers the LP shares earned as fees since the last claim to the owner.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the owner's LP token account, the vault and the lock.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<LockedLiquidity>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let fee_shares = ctx
+            .accounts
+            .liquidity_lock
+            .claim_fees(&ctx.accounts.amm, now)?;
+
+        let amm_key = ctx.accounts.amm.key();
+        let signer_seeds = [
+            b"lp_locker".as_ref(),
+            amm_key.as_ref(),
+            &[ctx.bumps.lp_locker],
+        ];
+        spl_token_transfer(TokenTransferParams {
+            source: ctx.accounts.locker_lp_ata.to_account_info(),
+            destination: ctx.accounts.owner_lp_ata.to_account_info(),
+            amount: fee_shares,
+            authority: ctx.accounts.lp_locker.to_account_info(),
+            authority_signer_seeds: &signer_seeds,
+            decimals: ctx.accounts.lp_mint.decimals,
+            mint: ctx.accounts.lp_mint.to_account_info(),
+            token_program: ctx.accounts.lp_token_program.to_account_info(),
+        })?;
+
+        // Emit the event
+        emit_cpi!(ClaimLockedFeesEvent {
+            amm: ctx.accounts.amm.key(),
+            owner: ctx.accounts.owner.key(),
+            fee_shares,
+            locked_amount: ctx.accounts.liquidity_lock.amount,
+            timestamp: now as u64,
+        });
+
+        emit!(ClaimLockedFeesEvent {
+            amm: ctx.accounts.amm.key(),
+            owner: ctx.accounts.owner.key(),
+            fee_shares,
+            locked_amount: ctx.accounts.liquidity_lock.amount,
+            timestamp: now as u64,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use crate::error::ErrorCode;
+use crate::utils::{spl_token_transfer, TokenTransferParams};
+use anchor_lang::prelude::*;
+
+// VestingReleaseEvent event, one per claim
+#[event]
+pub struct VestingReleaseEvent {
+    pub amm: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub claimed_amount: u64, // shares claimed so far, this release included
+    pub total_amount: u64,   // shares released over the whole schedule
+    pub timestamp: u64,
+}
+
+/// Releases the vested LP shares of a schedule to its beneficiary.
+pub mod claim_vested {
+    use super::*;
+    use crate::ClaimVested;
+
+    /// Transfers the shares vested and not claimed yet to the beneficiary.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the beneficiary's LP token account, the vault and the schedule.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<ClaimVested>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let vesting_schedule = &mut ctx.accounts.vesting_schedule;
+        let amount = vesting_schedule.claimable_amount(now)?;
+        require_gt!(amount, 0, ErrorCode::NothingVested);
+        vesting_schedule.claimed_amount += amount;
+
+        // Transfer the vested shares out of the locker vault
+        let amm_key = ctx.accounts.amm.key();
+        let signer_seeds = [
+            b"lp_locker".as_ref(),
+            amm_key.as_ref(),
+            &[ctx.bumps.lp_locker],
+        ];
+        spl_token_transfer(TokenTransferParams {
+            source: ctx.accounts.locker_lp_ata.to_account_info(),
+            destination: ctx.accounts.beneficiary_lp_ata.to_account_info(),
+            amount,
+            authority: ctx.accounts.lp_locker.to_account_info(),
+            authority_signer_seeds: &signer_seeds,
+            decimals: ctx.accounts.lp_mint.decimals,
+            mint: ctx.accounts.lp_mint.to_account_info(),
+            token_program: ctx.accounts.lp_token_program.to_account_info(),
+        })?;
+
+        // Emit the event
+        emit_cpi!(VestingReleaseEvent {
+            amm: ctx.accounts.amm.key(),
+            beneficiary: ctx.accounts.beneficiary.key(),
+            amount,
+            claimed_amount: ctx.accounts.vesting_schedule.claimed_amount,
+            total_amount: ctx.accounts.vesting_schedule.amount,
+            timestamp: now as u64,
+        });
+
+        emit!(VestingReleaseEvent {
+            amm: ctx.accounts.amm.key(),
+            beneficiary: ctx.accounts.beneficiary.key(),
+            amount,
+            claimed_amount: ctx.accounts.vesting_schedule.claimed_amount,
+            total_amount: ctx.accounts.vesting_schedule.amount,
+            timestamp: now as u64,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use crate::instructions::decrease_liquidity::withdraw_from_vaults;
+use crate::instructions::increase_liquidity::modify_position;
+use crate::utils::amount_after_transfer_fee;
+use crate::{ModifyPosition, PAUSE_WITHDRAWALS};
+use anchor_lang::prelude::*;
+
+// CollectFeesEvent event, amounts are the ones received by the owner after transfer fees
+#[event]
+pub struct CollectFeesEvent {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    pub timestamp: u64,
+    pub owner: Pubkey,
+}
+
+/// The `collect_fees` module is responsible for paying out the swap fees earned by a
+/// concentrated liquidity position, along with anything else it is owed.
+pub mod collect_fees {
+    use super::*;
+
+    /// Settles the fees earned by a position and sends everything it is owed to its owner.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the pool, the position, its tick arrays and token accounts.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<ModifyPosition>) -> Result<()> {
+        ctx.accounts
+            .global_parameters
+            .require_not_paused(PAUSE_WITHDRAWALS)?;
+
+        // A zero delta only brings the owed fees up to date
+        modify_position(ctx.accounts, 0)?;
+
+        let position = &mut ctx.accounts.position;
+        let (base_amount, quote_amount) = (position.tokens_owed_base, position.tokens_owed_quote);
+        position.tokens_owed_base = 0;
+        position.tokens_owed_quote = 0;
+
+        withdraw_from_vaults(ctx.accounts, base_amount, quote_amount)?;
+
+        let base_received_amount =
+            amount_after_transfer_fee(&ctx.accounts.base_mint.to_account_info(), base_amount)?;
+        let quote_received_amount =
+            amount_after_transfer_fee(&ctx.accounts.quote_mint.to_account_info(), quote_amount)?;
+
+        // Emit the event
+        emit_cpi!(CollectFeesEvent {
+            pool: ctx.accounts.pool.key(),
+            position: ctx.accounts.position.key(),
+            base_amount: base_received_amount,
+            quote_amount: quote_received_amount,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+            owner: ctx.accounts.owner.key(),
+        });
+
+        emit!(CollectFeesEvent {
+            pool: ctx.accounts.pool.key(),
+            position: ctx.accounts.position.key(),
+            base_amount: base_received_amount,
+            quote_amount: quote_received_amount,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+            owner: ctx.accounts.owner.key(),
+        });
+
+        Ok(())
+    }
+}
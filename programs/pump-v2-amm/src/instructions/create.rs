@@ -1,3 +1,7 @@
+use crate::curve::stable::{MAX_AMP, MIN_AMP};
+use crate::error::ErrorCode;
+use crate::utils::create_fee_receiver_ata;
+use crate::{AmpRamp, Curve};
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::program::invoke;
 // CreateEvent event
@@ -6,6 +10,7 @@ pub struct CreateEvent {
     pub base_mint: Pubkey,
     pub quote_mint: Pubkey,
     pub lp_mint: Pubkey,
+    pub curve: Curve,
     pub timestamp: u64,
     pub user: Pubkey,
 }
@@ -18,10 +23,17 @@ pub mod create {
     ///
     /// # Parameters:
     /// - `ctx`: Context containing all the accounts required for AMM initialization.
+    /// - `amp`: Amplification coefficient of a stable pool, `None` for a constant product pool.
     ///
     /// # Returns:
     /// - Result indicating success or an error.
-    pub fn handler(ctx: Context<Create>) -> Result<()> {
+    pub fn handler(ctx: Context<Create>, amp: Option<u64>) -> Result<()> {
+        let curve = new_pool_curve(
+            amp,
+            ctx.accounts.base_mint.decimals,
+            ctx.accounts.quote_mint.decimals,
+        )?;
+
         // Initialize AMM state and populate fields
         let amm = &mut ctx.accounts.amm;
         amm.base_mint = ctx.accounts.base_mint.key();
@@ -31,6 +43,8 @@ pub mod create {
         amm.base_reserve_ata = ctx.accounts.base_reserve_ata.key();
         amm.quote_reserve_ata = ctx.accounts.quote_reserve_ata.key();
         amm.fee_receiver_ata = ctx.accounts.fee_receiver_ata.key();
+        amm.curve = curve;
+        amm.last_price_update_ts = Clock::get()?.unix_timestamp;
         invoke(
             &spl_associated_token_account::instruction::create_associated_token_account(
                 &ctx.accounts.user.to_account_info().key,
@@ -67,11 +81,22 @@ pub mod create {
                 ctx.accounts.quote_token_program.to_account_info(),
             ],
         )?;
+        // Protocol fees go to the fee authority, shared by every pool with the same quote mint
+        create_fee_receiver_ata(
+            ctx.accounts.user.to_account_info(),
+            ctx.accounts.fee_receiver_ata.to_account_info(),
+            ctx.accounts.fee_authority.to_account_info(),
+            ctx.accounts.quote_mint.to_account_info(),
+            ctx.accounts.quote_token_program.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.associated_token_program.to_account_info(),
+        )?;
         // Emit the event
         emit_cpi!(CreateEvent {
             base_mint: *ctx.accounts.base_mint.to_account_info().key,
             quote_mint: *ctx.accounts.quote_mint.to_account_info().key,
             lp_mint: *ctx.accounts.lp_mint.to_account_info().key,
+            curve,
             timestamp: Clock::get()?.unix_timestamp as u64,
             user: *ctx.accounts.user.to_account_info().key,
         });
@@ -80,6 +105,7 @@ pub mod create {
             base_mint: *ctx.accounts.base_mint.to_account_info().key,
             quote_mint: *ctx.accounts.quote_mint.to_account_info().key,
             lp_mint: *ctx.accounts.lp_mint.to_account_info().key,
+            curve,
             timestamp: Clock::get()?.unix_timestamp as u64,
             user: *ctx.accounts.user.to_account_info().key,
         });
@@ -87,3 +113,27 @@ pub mod create {
         Ok(())
     }
 }
+
+/// Curve of a new pool: stable with a fixed `amp` when set, constant product otherwise.
+pub(crate) fn new_pool_curve(
+    amp: Option<u64>,
+    base_decimals: u8,
+    quote_decimals: u8,
+) -> Result<Curve> {
+    match amp {
+        Some(amp) => {
+            require!(
+                (MIN_AMP..=MAX_AMP).contains(&amp),
+                ErrorCode::InvalidAmplification
+            );
+            // The stable invariant compares reserves in raw units
+            require_eq!(
+                base_decimals,
+                quote_decimals,
+                ErrorCode::StableDecimalsMismatch
+            );
+            Ok(Curve::Stable(AmpRamp::fixed(amp)))
+        }
+        None => Ok(Curve::ConstantProduct),
+    }
+}
@@ -0,0 +1,96 @@
+use crate::curve::concentrated::{tick_at_sqrt_price, MAX_SQRT_PRICE_X64};
+use crate::error::ErrorCode;
+use anchor_lang::prelude::*;
+
+/// Largest tick spacing a concentrated liquidity pool can use.
+pub const MAX_TICK_SPACING: u16 = 1_000;
+
+// CreateClPoolEvent event
+#[event]
+pub struct CreateClPoolEvent {
+    pub pool: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub tick_spacing: u16,
+    pub fee_bps: u16,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub timestamp: u64,
+    pub user: Pubkey,
+}
+
+/// This module is responsible for creating concentrated liquidity pools. They live next to the
+/// xyk `Amm` pools and are traded through `cl_swap` instead of `buy`/`sell`.
+pub mod create_cl_pool {
+    use super::*;
+    use crate::CreateClPool;
+
+    /// Creates a concentrated liquidity pool and its vaults at an initial price.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing all the accounts required for pool creation.
+    /// - `tick_spacing`: Distance between the ticks positions can use as bounds.
+    /// - `fee_bps`: LP fee charged on the input of every swap, in basis points.
+    /// - `sqrt_price_x64`: Initial square root of the quote per base price, in Q64.64.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(
+        ctx: Context<CreateClPool>,
+        tick_spacing: u16,
+        fee_bps: u16,
+        sqrt_price_x64: u128,
+    ) -> Result<()> {
+        require!(
+            tick_spacing > 0 && tick_spacing <= MAX_TICK_SPACING,
+            ErrorCode::InvalidTickSpacing
+        );
+        require!(fee_bps < 10000, ErrorCode::InvalidFeeConfiguration);
+        require_gt!(
+            MAX_SQRT_PRICE_X64,
+            sqrt_price_x64,
+            ErrorCode::InvalidSqrtPrice
+        );
+        let tick_current = tick_at_sqrt_price(sqrt_price_x64)?;
+
+        // Initialize pool state and populate fields
+        let pool = &mut ctx.accounts.pool;
+        pool.base_mint = ctx.accounts.base_mint.key();
+        pool.quote_mint = ctx.accounts.quote_mint.key();
+        pool.creator = ctx.accounts.user.key();
+        pool.base_vault = ctx.accounts.base_vault.key();
+        pool.quote_vault = ctx.accounts.quote_vault.key();
+        pool.tick_spacing = tick_spacing;
+        pool.fee_bps = fee_bps;
+        pool.bump = ctx.bumps.pool;
+        pool.sqrt_price_x64 = sqrt_price_x64;
+        pool.tick_current = tick_current;
+
+        // Emit the event
+        emit_cpi!(CreateClPoolEvent {
+            pool: ctx.accounts.pool.key(),
+            base_mint: ctx.accounts.base_mint.key(),
+            quote_mint: ctx.accounts.quote_mint.key(),
+            tick_spacing,
+            fee_bps,
+            sqrt_price_x64,
+            tick_current,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+            user: ctx.accounts.user.key(),
+        });
+
+        emit!(CreateClPoolEvent {
+            pool: ctx.accounts.pool.key(),
+            base_mint: ctx.accounts.base_mint.key(),
+            quote_mint: ctx.accounts.quote_mint.key(),
+            tick_spacing,
+            fee_bps,
+            sqrt_price_x64,
+            tick_current,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+            user: ctx.accounts.user.key(),
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use crate::utils::{spl_token_transfer, TokenTransferParams};
+use anchor_lang::prelude::*;
+
+// CreateVestingEvent event
+#[event]
+pub struct CreateVestingEvent {
+    pub amm: Pubkey,
+    pub funder: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub start_timestamp: i64,
+    pub cliff_timestamp: i64,
+    pub end_timestamp: i64,
+    pub timestamp: u64,
+}
+
+/// Vests LP shares to a beneficiary, typically a team or creator allocation.
+pub mod create_vesting {
+    use super::*;
+    use crate::CreateVesting;
+
+    /// Transfers the funder's LP shares to the locker vault, released to the beneficiary
+    /// linearly from `start_timestamp` to `end_timestamp`, nothing before `cliff_timestamp`.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the funder's LP token account, the vault and the schedule.
+    /// - `amount`: Amount of LP shares to vest.
+    /// - `start_timestamp`: When the linear release starts.
+    /// - `cliff_timestamp`: Before when nothing can be claimed.
+    /// - `end_timestamp`: When every share is released.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(
+        ctx: Context<CreateVesting>,
+        amount: u64,
+        start_timestamp: i64,
+        cliff_timestamp: i64,
+        end_timestamp: i64,
+    ) -> Result<()> {
+        let vesting_schedule = &mut ctx.accounts.vesting_schedule;
+        vesting_schedule.amm = ctx.accounts.amm.key();
+        vesting_schedule.beneficiary = ctx.accounts.beneficiary.key();
+        vesting_schedule.amount = amount;
+        vesting_schedule.claimed_amount = 0;
+        vesting_schedule.start_timestamp = start_timestamp;
+        vesting_schedule.cliff_timestamp = cliff_timestamp;
+        vesting_schedule.end_timestamp = end_timestamp;
+        vesting_schedule.validate()?;
+
+        // Transfer the shares to the locker vault
+        spl_token_transfer(TokenTransferParams {
+            source: ctx.accounts.funder_lp_ata.to_account_info(),
+            destination: ctx.accounts.locker_lp_ata.to_account_info(),
+            amount,
+            authority: ctx.accounts.funder.to_account_info(),
+            authority_signer_seeds: &[],
+            decimals: ctx.accounts.lp_mint.decimals,
+            mint: ctx.accounts.lp_mint.to_account_info(),
+            token_program: ctx.accounts.lp_token_program.to_account_info(),
+        })?;
+
+        // Emit the event
+        emit_cpi!(CreateVestingEvent {
+            amm: ctx.accounts.amm.key(),
+            funder: ctx.accounts.funder.key(),
+            beneficiary: ctx.accounts.beneficiary.key(),
+            amount,
+            start_timestamp,
+            cliff_timestamp,
+            end_timestamp,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(CreateVestingEvent {
+            amm: ctx.accounts.amm.key(),
+            funder: ctx.accounts.funder.key(),
+            beneficiary: ctx.accounts.beneficiary.key(),
+            amount,
+            start_timestamp,
+            cliff_timestamp,
+            end_timestamp,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+use crate::curve::concentrated::position_amounts;
+use crate::curve::Rounding;
+use crate::error::ErrorCode;
+use crate::instructions::increase_liquidity::modify_position;
+use crate::utils::{amount_after_transfer_fee, spl_token_transfer, TokenTransferParams};
+use crate::{ModifyPosition, PAUSE_WITHDRAWALS};
+use anchor_lang::prelude::*;
+
+// DecreaseLiquidityEvent event, amounts are the ones received by the owner after transfer fees
+#[event]
+pub struct DecreaseLiquidityEvent {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub liquidity: u128,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    pub timestamp: u64,
+    pub owner: Pubkey,
+}
+
+/// Sends tokens from the pool vaults to the position owner, signed by the pool.
+pub(crate) fn withdraw_from_vaults(
+    accounts: &ModifyPosition,
+    base_amount: u64,
+    quote_amount: u64,
+) -> Result<()> {
+    let pool = &accounts.pool;
+    let signer_seeds = [
+        b"cl_pool",
+        pool.creator.as_ref(),
+        pool.base_mint.as_ref(),
+        pool.quote_mint.as_ref(),
+        &[pool.bump],
+    ];
+
+    if base_amount > 0 {
+        spl_token_transfer(TokenTransferParams {
+            source: accounts.base_vault.to_account_info(),
+            destination: accounts.owner_base_ata.to_account_info(),
+            amount: base_amount,
+            authority: pool.to_account_info(),
+            authority_signer_seeds: &signer_seeds,
+            decimals: accounts.base_mint.decimals,
+            mint: accounts.base_mint.to_account_info(),
+            token_program: accounts.base_token_program.to_account_info(),
+        })?;
+    }
+    if quote_amount > 0 {
+        spl_token_transfer(TokenTransferParams {
+            source: accounts.quote_vault.to_account_info(),
+            destination: accounts.owner_quote_ata.to_account_info(),
+            amount: quote_amount,
+            authority: pool.to_account_info(),
+            authority_signer_seeds: &signer_seeds,
+            decimals: accounts.quote_mint.decimals,
+            mint: accounts.quote_mint.to_account_info(),
+            token_program: accounts.quote_token_program.to_account_info(),
+        })?;
+    }
+    Ok(())
+}
+
+/// The `decrease_liquidity` module is responsible for removing liquidity from a concentrated
+/// liquidity position. The owner receives the base and quote amounts backing the removed
+/// liquidity at the current pool price, fees stay owed until `collect_fees`.
+pub mod decrease_liquidity {
+    use super::*;
+
+    /// Removes `liquidity` from a position, ensuring minimum amounts are respected.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the pool, the position, its tick arrays and token accounts.
+    /// - `liquidity`: The amount of liquidity to remove from the position.
+    /// - `min_base_amount`: The minimum amount of base tokens the owner expects to receive.
+    /// - `min_quote_amount`: The minimum amount of quote tokens the owner expects to receive.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(
+        ctx: Context<ModifyPosition>,
+        liquidity: u128,
+        min_base_amount: u64,
+        min_quote_amount: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .global_parameters
+            .require_not_paused(PAUSE_WITHDRAWALS)?;
+        require_gt!(liquidity, 0, ErrorCode::InvalidTokenAmounts);
+        require!(
+            liquidity <= ctx.accounts.position.liquidity,
+            ErrorCode::LiquidityRemovalExceedsShares
+        );
+        let liquidity_delta = i128::try_from(liquidity).map_err(|_| ErrorCode::MathOverflow)?;
+
+        // Amounts backing the liquidity, rounded in favor of the pool
+        let (base_amount, quote_amount) = position_amounts(
+            ctx.accounts.pool.sqrt_price_x64,
+            ctx.accounts.position.tick_lower,
+            ctx.accounts.position.tick_upper,
+            liquidity,
+            Rounding::Down,
+        )?;
+
+        // The owner receives the withdrawn amounts minus any Token-2022 transfer fees
+        let base_received_amount =
+            amount_after_transfer_fee(&ctx.accounts.base_mint.to_account_info(), base_amount)?;
+        let quote_received_amount =
+            amount_after_transfer_fee(&ctx.accounts.quote_mint.to_account_info(), quote_amount)?;
+        require!(
+            base_received_amount >= min_base_amount,
+            ErrorCode::BaseAmountTooLow
+        );
+        require!(
+            quote_received_amount >= min_quote_amount,
+            ErrorCode::QuoteAmountTooLow
+        );
+
+        modify_position(ctx.accounts, -liquidity_delta)?;
+        withdraw_from_vaults(ctx.accounts, base_amount, quote_amount)?;
+
+        // Emit the event
+        emit_cpi!(DecreaseLiquidityEvent {
+            pool: ctx.accounts.pool.key(),
+            position: ctx.accounts.position.key(),
+            liquidity,
+            base_amount: base_received_amount,
+            quote_amount: quote_received_amount,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+            owner: ctx.accounts.owner.key(),
+        });
+
+        emit!(DecreaseLiquidityEvent {
+            pool: ctx.accounts.pool.key(),
+            position: ctx.accounts.position.key(),
+            liquidity,
+            base_amount: base_received_amount,
+            quote_amount: quote_received_amount,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+            owner: ctx.accounts.owner.key(),
+        });
+
+        Ok(())
+    }
+}
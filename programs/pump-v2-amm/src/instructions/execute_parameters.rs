@@ -0,0 +1,105 @@
+use crate::error::ErrorCode;
+use crate::{FeeConfig, FeeMode};
+use anchor_lang::prelude::*;
+
+// ExecuteParametersEvent event
+#[event]
+pub struct ExecuteParametersEvent {
+    pub fee_config: FeeConfig,
+    pub fee_mode: FeeMode,
+    pub flash_loan_fee_bps: u64,
+    pub timelock_delay: i64,
+    pub timestamp: u64,
+}
+
+/// Applies the parameters queued by `queue_parameters` once their timelock has elapsed, and
+/// closes the proposal so that a new change can be queued. A new fee mode applies to each pool
+/// from its next deposit or withdrawal, see `Amm::update_liquidity_last`.
+pub mod execute_parameters {
+    use crate::ExecuteParameters;
+
+    use super::*;
+
+    /// Sets the queued parameters of the AMM
+    ///
+    /// # Parameters:
+    /// - `ctx`: The context of the AMM.
+    ///
+    /// # Returns:
+    /// - `Result<()>`: A success indicator if the parameters are set successfully.
+    pub fn handler(ctx: Context<ExecuteParameters>) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        require_gte!(
+            Clock::get()?.unix_timestamp,
+            proposal.execute_after,
+            ErrorCode::TimelockNotElapsed
+        );
+        let fee_config = proposal.fee_config;
+        let fee_mode = proposal.fee_mode;
+        let flash_loan_fee_bps = proposal.flash_loan_fee_bps;
+        let timelock_delay = proposal.timelock_delay;
+
+        let amm_params = &mut ctx.accounts.global_parameters;
+        amm_params.protocol_fee_bps = fee_config.protocol_fee_bps;
+        amm_params.referrer_fee_bps = fee_config.referrer_fee_bps;
+        amm_params.referrer_fee_discount_bps = fee_config.referrer_fee_discount_bps;
+        amm_params.lp_fee_bps = fee_config.lp_fee_bps;
+        amm_params.fee_mode = fee_mode;
+        amm_params.flash_loan_fee_bps = flash_loan_fee_bps;
+        amm_params.timelock_delay = timelock_delay;
+
+        // Emit the event
+        emit_cpi!(ExecuteParametersEvent {
+            fee_config,
+            fee_mode,
+            flash_loan_fee_bps,
+            timelock_delay,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(ExecuteParametersEvent {
+            fee_config,
+            fee_mode,
+            flash_loan_fee_bps,
+            timelock_delay,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::{lp_share_global_parameters, stable_amm};
+    use crate::{AmpRamp, FeeMode};
+
+    #[test]
+    fn test_fee_mode_change_applies_from_the_next_deposit() {
+        let mut global_parameters = lp_share_global_parameters();
+        global_parameters.protocol_fee_bps = 100;
+        let mut amm = stable_amm(1_000_000, 1_000_000, 1_000_000, AmpRamp::fixed(100));
+        amm.update_liquidity_last(&global_parameters, 0).unwrap();
+        // LP fees left in the reserves by swaps
+        amm.quote_reserve += 10_000;
+
+        // The fees accrued under LpShare are still owed after switching to OnTop, and the pool
+        // keeps charging no protocol fee on top until its next deposit
+        global_parameters.fee_mode = FeeMode::OnTop;
+        let pending = amm.pending_protocol_fee_shares().unwrap();
+        assert!(pending > 0);
+        assert_eq!(amm.fee_config(&global_parameters).protocol_fee_bps, 0);
+        assert_eq!(amm.accrue_protocol_fee().unwrap(), pending);
+        amm.update_liquidity_last(&global_parameters, 0).unwrap();
+        assert_eq!(amm.fee_mode, FeeMode::OnTop);
+        assert_eq!(amm.fee_config(&global_parameters).protocol_fee_bps, 100);
+
+        // Switching back does not charge for the fees accrued while OnTop
+        amm.quote_reserve += 10_000;
+        global_parameters.fee_mode = FeeMode::LpShare {
+            protocol_share_bps: 5_000,
+        };
+        assert_eq!(amm.pending_protocol_fee_shares().unwrap(), 0);
+        assert_eq!(amm.fee_config(&global_parameters).protocol_fee_bps, 100);
+    }
+}
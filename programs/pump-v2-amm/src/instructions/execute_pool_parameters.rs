@@ -0,0 +1,153 @@
+use crate::error::ErrorCode;
+use crate::FeeConfig;
+use anchor_lang::prelude::*;
+
+// ExecutePoolParametersEvent event
+#[event]
+pub struct ExecutePoolParametersEvent {
+    pub amm: Pubkey,
+    pub fee_config: Option<FeeConfig>,
+    pub timestamp: u64,
+}
+
+/// Applies the pool fees queued by `queue_pool_parameters` once their timelock has elapsed, and
+/// closes the proposal so that a new change can be queued for the pool.
+pub mod execute_pool_parameters {
+    use crate::ExecutePoolParameters;
+
+    use super::*;
+
+    /// Sets or clears the queued fee configuration of a pool
+    ///
+    /// # Parameters:
+    /// - `ctx`: The context of the AMM.
+    ///
+    /// # Returns:
+    /// - `Result<()>`: A success indicator if the parameters are set successfully.
+    pub fn handler(ctx: Context<ExecutePoolParameters>) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        require_gte!(
+            Clock::get()?.unix_timestamp,
+            proposal.execute_after,
+            ErrorCode::TimelockNotElapsed
+        );
+        let fee_config = proposal.fee_config;
+
+        let amm = &mut ctx.accounts.amm;
+        amm.fee_config = fee_config;
+
+        // Emit the event
+        emit_cpi!(ExecutePoolParametersEvent {
+            amm: ctx.accounts.amm.key(),
+            fee_config,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(ExecutePoolParametersEvent {
+            amm: ctx.accounts.amm.key(),
+            fee_config,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::setup_test_environment;
+    use crate::FeeConfig;
+    use anchor_lang::{AccountDeserialize, AnchorSerialize};
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+    use std::str::FromStr;
+    use switchboard_solana::Pubkey;
+
+    fn pool_parameters_accounts(
+        program_id: &Pubkey,
+        global_parameters: &Pubkey,
+        authority: &Pubkey,
+        amm: &Pubkey,
+    ) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(*global_parameters, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(*amm, false),
+            AccountMeta::new(
+                Pubkey::find_program_address(
+                    &[b"pool_parameters_proposal", amm.as_ref()],
+                    program_id,
+                )
+                .0,
+                false,
+            ),
+            // No role account, the test keypair is the admin
+            AccountMeta::new_readonly(*program_id, false),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_queued_pool_fees_apply_on_execute() {
+        let setup = setup_test_environment(true).await;
+        let owner = setup.keypair.pubkey();
+        let fee_config = FeeConfig {
+            protocol_fee_bps: 50,
+            referrer_fee_bps: 5,
+            referrer_fee_discount_bps: 10,
+            lp_fee_bps: 4,
+        };
+
+        let mut data = switchboard_solana::get_ixn_discriminator("queue_pool_parameters").to_vec();
+        Some(fee_config).serialize(&mut data).unwrap();
+        let mut accounts = pool_parameters_accounts(
+            &setup.program_id,
+            &setup.global_parameters,
+            &owner,
+            &setup.amm_account,
+        );
+        accounts.push(AccountMeta::new_readonly(
+            solana_program::system_program::ID,
+            false,
+        ));
+        accounts.push(AccountMeta::new_readonly(
+            Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+            false,
+        ));
+        accounts.push(AccountMeta::new_readonly(setup.program_id, false));
+        let queue_ix = Instruction {
+            program_id: setup.program_id,
+            accounts,
+            data,
+        };
+
+        let mut accounts = pool_parameters_accounts(
+            &setup.program_id,
+            &setup.global_parameters,
+            &owner,
+            &setup.amm_account,
+        );
+        accounts.push(AccountMeta::new_readonly(
+            Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+            false,
+        ));
+        accounts.push(AccountMeta::new_readonly(setup.program_id, false));
+        let execute_ix = Instruction {
+            program_id: setup.program_id,
+            accounts,
+            data: switchboard_solana::get_ixn_discriminator("execute_pool_parameters").to_vec(),
+        };
+
+        // The test deployment has no timelock, so the change can be executed right away
+        let mut tx = Transaction::new_with_payer(&[queue_ix, execute_ix], Some(&owner));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.send_and_confirm_transaction(&tx).await;
+        assert!(result.is_ok(), "Queue and execute should succeed");
+
+        let account = setup.client.get_account(&setup.amm_account).await.unwrap();
+        let amm = crate::Amm::try_deserialize(&mut account.data.as_slice()).unwrap();
+        assert_eq!(amm.fee_config, Some(fee_config));
+    }
+}
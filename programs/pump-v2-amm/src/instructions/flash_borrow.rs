@@ -0,0 +1,107 @@
+use crate::curve;
+use crate::error::ErrorCode;
+use crate::utils::{spl_token_transfer, TokenTransferParams};
+use crate::{FlashLoan, PAUSE_SWAPS};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_lang::Discriminator;
+
+/// The `flash_borrow` module lends reserves of a pool for the rest of the transaction.
+///
+/// The loan has to be paid back with its fee by a `flash_repay` on the same pool later in the
+/// same transaction, which is checked through the instructions sysvar. Until then the pool is
+/// locked: swaps, liquidity changes and `sync` fail with `FlashLoanInProgress`.
+pub mod flash_borrow {
+    use super::*;
+    use crate::FlashBorrow;
+
+    /// Transfers `base_amount` and `quote_amount` from the reserve vaults to the user.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the AMM, its reserve vaults and the instructions sysvar.
+    /// - `base_amount`: Base tokens to borrow.
+    /// - `quote_amount`: Quote tokens to borrow.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<FlashBorrow>, base_amount: u64, quote_amount: u64) -> Result<()> {
+        let amm = &ctx.accounts.amm;
+        amm.require_not_paused(&ctx.accounts.global_parameters, PAUSE_SWAPS)?;
+        require!(amm.flash_loan.is_none(), ErrorCode::FlashLoanInProgress);
+        require!(
+            base_amount > 0 || quote_amount > 0,
+            ErrorCode::InvalidTokenAmounts
+        );
+        require!(
+            base_amount <= amm.base_reserve && quote_amount <= amm.quote_reserve,
+            ErrorCode::InsufficientLiquidity
+        );
+        require_repay_follows(&ctx.accounts.instructions, &amm.key())?;
+
+        let fee_bps = ctx.accounts.global_parameters.flash_loan_fee_bps;
+        ctx.accounts.amm.flash_loan = Some(FlashLoan {
+            base_amount,
+            quote_amount,
+            base_fee_amount: curve::fee_amount(base_amount, fee_bps)?,
+            quote_fee_amount: curve::fee_amount(quote_amount, fee_bps)?,
+        });
+
+        let signer_seeds = [
+            b"amm",
+            ctx.accounts.amm.creator.as_ref(),
+            ctx.accounts.base_mint.to_account_info().key.as_ref(),
+            ctx.accounts.quote_mint.to_account_info().key.as_ref(),
+            &[ctx.bumps.amm],
+        ];
+
+        // The reserves are left untouched, the loan is only out of the vaults
+        if base_amount > 0 {
+            spl_token_transfer(TokenTransferParams {
+                source: ctx.accounts.base_reserve_ata.to_account_info(),
+                destination: ctx.accounts.user_base_ata.to_account_info(),
+                amount: base_amount,
+                authority: ctx.accounts.amm.to_account_info(),
+                authority_signer_seeds: &signer_seeds,
+                decimals: ctx.accounts.base_mint.decimals,
+                mint: ctx.accounts.base_mint.to_account_info(),
+                token_program: ctx.accounts.base_token_program.to_account_info(),
+            })?;
+        }
+        if quote_amount > 0 {
+            spl_token_transfer(TokenTransferParams {
+                source: ctx.accounts.quote_reserve_ata.to_account_info(),
+                destination: ctx.accounts.user_quote_ata.to_account_info(),
+                amount: quote_amount,
+                authority: ctx.accounts.amm.to_account_info(),
+                authority_signer_seeds: &signer_seeds,
+                decimals: ctx.accounts.quote_mint.decimals,
+                mint: ctx.accounts.quote_mint.to_account_info(),
+                token_program: ctx.accounts.quote_token_program.to_account_info(),
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks that a `flash_repay` on `amm` is one of the top level instructions after the current one.
+///
+/// A failing repay fails the whole transaction, so finding it is enough for the loan to be
+/// settled before the transaction ends.
+fn require_repay_follows(instructions: &AccountInfo, amm: &Pubkey) -> Result<()> {
+    let current = load_current_index_checked(instructions)? as usize;
+    let mut index = current + 1;
+    while let Ok(instruction) = load_instruction_at_checked(index, instructions) {
+        // The AMM is the first account of `FlashRepay`
+        if instruction.program_id == crate::ID
+            && instruction.data.get(..8) == Some(&crate::instruction::FlashRepay::DISCRIMINATOR[..])
+            && instruction.accounts.first().map(|meta| meta.pubkey) == Some(*amm)
+        {
+            return Ok(());
+        }
+        index += 1;
+    }
+    err!(ErrorCode::MissingFlashRepay)
+}
@@ -0,0 +1,242 @@
+use crate::error::ErrorCode;
+use crate::utils::{
+    amount_after_transfer_fee, amount_with_transfer_fee, check_reserve_balances,
+    spl_token_transfer, TokenTransferParams,
+};
+use anchor_lang::prelude::*;
+
+// FlashLoanEvent event
+#[event]
+pub struct FlashLoanEvent {
+    pub amm: Pubkey,
+    pub user: Pubkey,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    pub base_fee_amount: u64,
+    pub quote_fee_amount: u64,
+    pub timestamp: u64,
+}
+
+/// The `flash_repay` module settles the loan opened by `flash_borrow` on the same pool.
+///
+/// The borrowed amounts and their fee go back to the reserve vaults. Whatever arrives on top of
+/// the borrowed amounts is added to the reserves, like the LP fee of a swap.
+pub mod flash_repay {
+    use super::*;
+    use crate::FlashRepay;
+
+    /// Transfers the borrowed amounts plus the flash loan fee from the user to the reserve vaults.
+    ///
+    /// The user pays the transfer fee of Token-2022 mints on top, so that the vaults receive the
+    /// full amount owed.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the AMM, its reserve vaults and the user's token accounts.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<FlashRepay>) -> Result<()> {
+        let loan = ctx
+            .accounts
+            .amm
+            .flash_loan
+            .take()
+            .ok_or(ErrorCode::NoFlashLoan)?;
+
+        let base_owed = loan
+            .base_amount
+            .checked_add(loan.base_fee_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let base_mint = ctx.accounts.base_mint.to_account_info();
+        let base_repaid = amount_with_transfer_fee(&base_mint, base_owed)?;
+        if base_repaid > 0 {
+            spl_token_transfer(TokenTransferParams {
+                source: ctx.accounts.user_base_ata.to_account_info(),
+                destination: ctx.accounts.base_reserve_ata.to_account_info(),
+                amount: base_repaid,
+                authority: ctx.accounts.user.to_account_info(),
+                authority_signer_seeds: &[],
+                decimals: ctx.accounts.base_mint.decimals,
+                mint: base_mint.clone(),
+                token_program: ctx.accounts.base_token_program.to_account_info(),
+            })?;
+        }
+
+        let quote_owed = loan
+            .quote_amount
+            .checked_add(loan.quote_fee_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let quote_mint = ctx.accounts.quote_mint.to_account_info();
+        let quote_repaid = amount_with_transfer_fee(&quote_mint, quote_owed)?;
+        if quote_repaid > 0 {
+            spl_token_transfer(TokenTransferParams {
+                source: ctx.accounts.user_quote_ata.to_account_info(),
+                destination: ctx.accounts.quote_reserve_ata.to_account_info(),
+                amount: quote_repaid,
+                authority: ctx.accounts.user.to_account_info(),
+                authority_signer_seeds: &[],
+                decimals: ctx.accounts.quote_mint.decimals,
+                mint: quote_mint.clone(),
+                token_program: ctx.accounts.quote_token_program.to_account_info(),
+            })?;
+        }
+
+        // Keep the fee, and any rounding of the transfer fee, in the pool for the liquidity providers
+        let base_kept = amount_after_transfer_fee(&base_mint, base_repaid)?
+            .checked_sub(loan.base_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let quote_kept = amount_after_transfer_fee(&quote_mint, quote_repaid)?
+            .checked_sub(loan.quote_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let amm = &mut ctx.accounts.amm;
+        amm.update_price_accumulators(Clock::get()?.unix_timestamp);
+        amm.base_reserve = amm
+            .base_reserve
+            .checked_add(base_kept)
+            .ok_or(ErrorCode::MathOverflow)?;
+        amm.quote_reserve = amm
+            .quote_reserve
+            .checked_add(quote_kept)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // Make sure the vaults back the reserves again
+        check_reserve_balances(
+            &ctx.accounts.amm,
+            &mut ctx.accounts.base_reserve_ata,
+            &mut ctx.accounts.quote_reserve_ata,
+        )?;
+
+        // Emit the event
+        emit_cpi!(FlashLoanEvent {
+            amm: ctx.accounts.amm.key(),
+            user: ctx.accounts.user.key(),
+            base_amount: loan.base_amount,
+            quote_amount: loan.quote_amount,
+            base_fee_amount: loan.base_fee_amount,
+            quote_fee_amount: loan.quote_fee_amount,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(FlashLoanEvent {
+            amm: ctx.accounts.amm.key(),
+            user: ctx.accounts.user.key(),
+            base_amount: loan.base_amount,
+            quote_amount: loan.quote_amount,
+            base_fee_amount: loan.base_fee_amount,
+            quote_fee_amount: loan.quote_fee_amount,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::setup_test_environment;
+    use crate::fixtures::tests::TestEnvironment;
+    use anchor_lang::AccountDeserialize;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+    use std::str::FromStr;
+    use switchboard_solana::Pubkey;
+
+    fn flash_borrow_instruction(
+        setup: &TestEnvironment,
+        base_amount: u64,
+        quote_amount: u64,
+    ) -> Instruction {
+        let mut data = switchboard_solana::get_ixn_discriminator("flash_borrow").to_vec();
+        data.extend_from_slice(&base_amount.to_le_bytes());
+        data.extend_from_slice(&quote_amount.to_le_bytes());
+        Instruction {
+            program_id: setup.program_id,
+            accounts: vec![
+                AccountMeta::new(setup.amm_account, false),
+                AccountMeta::new_readonly(setup.global_parameters, false),
+                AccountMeta::new_readonly(setup.keypair.pubkey(), true),
+                AccountMeta::new(setup.user_base, false),
+                AccountMeta::new(setup.user_quote, false),
+                AccountMeta::new(setup.base_vault, false),
+                AccountMeta::new(setup.quote_vault, false),
+                AccountMeta::new_readonly(setup.base_mint, false),
+                AccountMeta::new_readonly(setup.quote_mint, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(solana_sdk::sysvar::instructions::ID, false),
+            ],
+            data,
+        }
+    }
+
+    fn flash_repay_instruction(setup: &TestEnvironment) -> Instruction {
+        Instruction {
+            program_id: setup.program_id,
+            accounts: vec![
+                AccountMeta::new(setup.amm_account, false),
+                AccountMeta::new_readonly(setup.keypair.pubkey(), true),
+                AccountMeta::new(setup.user_base, false),
+                AccountMeta::new(setup.user_quote, false),
+                AccountMeta::new(setup.base_vault, false),
+                AccountMeta::new(setup.quote_vault, false),
+                AccountMeta::new_readonly(setup.base_mint, false),
+                AccountMeta::new_readonly(setup.quote_mint, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(
+                    Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+                    false,
+                ),
+                AccountMeta::new_readonly(setup.program_id, false),
+            ],
+            data: switchboard_solana::get_ixn_discriminator("flash_repay").to_vec(),
+        }
+    }
+
+    async fn fetch_amm(setup: &TestEnvironment) -> crate::state::Amm {
+        let amm_acc = setup.client.get_account(&setup.amm_account).await.unwrap();
+        crate::state::Amm::try_deserialize(&mut amm_acc.data.as_slice()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_flash_loan_repaid_with_fee() {
+        let setup = setup_test_environment(true).await;
+        let before = fetch_amm(&setup).await;
+
+        let mut tx = Transaction::new_with_payer(
+            &[
+                flash_borrow_instruction(&setup, 1_000, 2_000),
+                flash_repay_instruction(&setup),
+            ],
+            Some(&setup.keypair.pubkey()),
+        );
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.send_and_confirm_transaction(&tx).await;
+        assert!(result.is_ok(), "Flash loan should succeed");
+
+        // The fixture sets a 9 bps flash loan fee, rounded up
+        let after = fetch_amm(&setup).await;
+        assert_eq!(after.flash_loan, None);
+        assert_eq!(after.base_reserve, before.base_reserve + 1);
+        assert_eq!(after.quote_reserve, before.quote_reserve + 2);
+    }
+
+    #[tokio::test]
+    async fn test_flash_borrow_without_repay_fails() {
+        let setup = setup_test_environment(true).await;
+
+        let mut tx = Transaction::new_with_payer(
+            &[flash_borrow_instruction(&setup, 1_000, 0)],
+            Some(&setup.keypair.pubkey()),
+        );
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.send_and_confirm_transaction(&tx).await;
+        assert!(result.is_err(), "Flash borrow without repay should fail");
+    }
+}
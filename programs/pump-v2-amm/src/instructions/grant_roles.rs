@@ -0,0 +1,194 @@
+use crate::error::ErrorCode;
+use crate::ROLE_ALL;
+use anchor_lang::prelude::*;
+
+// GrantRolesEvent event, `member_roles` are the roles held once the grant is applied
+#[event]
+pub struct GrantRolesEvent {
+    pub member: Pubkey,
+    pub roles: u8,
+    pub member_roles: u8,
+    pub timestamp: u64,
+}
+
+/// Lets the admin delegate privileged instructions to other keys.
+///
+/// Each key gets a `Role` account holding its `ROLE_*` bits, created on its first grant. The admin
+/// keeps every role and stays the only key able to grant, revoke and propose a new admin.
+pub mod grant_roles {
+    use crate::GrantRoles;
+
+    use super::*;
+
+    /// Grants `roles` to the member
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the member and its `Role` account.
+    /// - `roles`: The `ROLE_*` flags to grant.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<GrantRoles>, roles: u8) -> Result<()> {
+        validate_roles(roles)?;
+
+        let role = &mut ctx.accounts.role;
+        role.member = ctx.accounts.member.key();
+        role.roles |= roles;
+        let member_roles = role.roles;
+
+        // Emit the event
+        emit_cpi!(GrantRolesEvent {
+            member: ctx.accounts.member.key(),
+            roles,
+            member_roles,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(GrantRolesEvent {
+            member: ctx.accounts.member.key(),
+            roles,
+            member_roles,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
+
+/// Checks that `roles` names at least one role and only known `ROLE_*` flags.
+pub(crate) fn validate_roles(roles: u8) -> Result<()> {
+    require!(
+        roles != 0 && roles & !ROLE_ALL == 0,
+        ErrorCode::InvalidRoles
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::setup_test_environment;
+    use crate::fixtures::tests::TestEnvironment;
+    use crate::{PAUSE_SWAPS, ROLE_PAUSER};
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::signature::Keypair;
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+    use std::str::FromStr;
+    use switchboard_solana::Pubkey;
+
+    fn event_accounts(setup: &TestEnvironment) -> [AccountMeta; 2] {
+        [
+            AccountMeta::new_readonly(
+                Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+                false,
+            ),
+            AccountMeta::new_readonly(setup.program_id, false),
+        ]
+    }
+
+    fn role_address(setup: &TestEnvironment, member: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"role", member.as_ref()], &setup.program_id).0
+    }
+
+    fn roles_instruction(
+        setup: &TestEnvironment,
+        name: &str,
+        member: &Pubkey,
+        roles: u8,
+    ) -> Instruction {
+        let mut data = switchboard_solana::get_ixn_discriminator(name).to_vec();
+        data.push(roles);
+        let mut accounts = vec![
+            AccountMeta::new_readonly(setup.global_parameters, false),
+            AccountMeta::new(setup.keypair.pubkey(), true),
+        ];
+        if name == "grant_roles" {
+            accounts.push(AccountMeta::new_readonly(*member, false));
+            accounts.push(AccountMeta::new(role_address(setup, member), false));
+            accounts.push(AccountMeta::new_readonly(
+                solana_sdk::system_program::ID,
+                false,
+            ));
+        } else {
+            accounts.push(AccountMeta::new(role_address(setup, member), false));
+        }
+        accounts.extend(event_accounts(setup));
+        Instruction {
+            program_id: setup.program_id,
+            accounts,
+            data,
+        }
+    }
+
+    fn pause_pool_instruction(setup: &TestEnvironment, authority: &Pubkey) -> Instruction {
+        let mut data = switchboard_solana::get_ixn_discriminator("pause_pool").to_vec();
+        data.push(PAUSE_SWAPS);
+        let mut accounts = vec![
+            AccountMeta::new_readonly(setup.global_parameters, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(setup.amm_account, false),
+            AccountMeta::new_readonly(role_address(setup, authority), false),
+        ];
+        accounts.extend(event_accounts(setup));
+        Instruction {
+            program_id: setup.program_id,
+            accounts,
+            data,
+        }
+    }
+
+    async fn send(setup: &TestEnvironment, instruction: Instruction, signers: &[&Keypair]) -> bool {
+        let mut tx = Transaction::new_with_payer(&[instruction], Some(&setup.keypair.pubkey()));
+        let mut all_signers = vec![setup.keypair.as_ref()];
+        all_signers.extend_from_slice(signers);
+        tx.sign(
+            &all_signers,
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        setup.client.send_and_confirm_transaction(&tx).await.is_ok()
+    }
+
+    #[tokio::test]
+    async fn test_pauser_role_granted_and_revoked() {
+        let setup = setup_test_environment(true).await;
+        let pauser = Keypair::new();
+        let pauser_key = pauser.pubkey();
+
+        assert!(
+            send(
+                &setup,
+                roles_instruction(&setup, "grant_roles", &pauser_key, ROLE_PAUSER),
+                &[]
+            )
+            .await,
+            "Granting the pauser role should succeed"
+        );
+        assert!(
+            send(
+                &setup,
+                pause_pool_instruction(&setup, &pauser_key),
+                &[&pauser]
+            )
+            .await,
+            "A pauser should be able to pause a pool"
+        );
+
+        assert!(
+            send(
+                &setup,
+                roles_instruction(&setup, "revoke_roles", &pauser_key, ROLE_PAUSER),
+                &[]
+            )
+            .await,
+            "Revoking the pauser role should succeed"
+        );
+        assert!(
+            !send(
+                &setup,
+                pause_pool_instruction(&setup, &pauser_key),
+                &[&pauser]
+            )
+            .await,
+            "A revoked pauser should not be able to pause a pool"
+        );
+    }
+}
@@ -0,0 +1,31 @@
+use crate::error::ErrorCode;
+use anchor_lang::prelude::*;
+
+/// Largest number of entries an observations account can hold.
+pub const MAX_OBSERVATIONS: u16 = 1_000;
+
+/// This module is responsible for extending the observations account of an AMM, so that it
+/// covers a longer window. The new slots are filled as swaps come in.
+pub mod grow_observations {
+    use super::*;
+    use crate::GrowObservations;
+
+    /// Raises the number of entries the observations account can hold.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the observations account and the payer of the extra rent.
+    /// - `capacity`: The new number of entries, above the current one.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<GrowObservations>, capacity: u16) -> Result<()> {
+        let observations = &mut ctx.accounts.observations;
+        require!(
+            capacity > observations.capacity && capacity <= MAX_OBSERVATIONS,
+            ErrorCode::InvalidObservationsCapacity
+        );
+        observations.capacity = capacity;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,220 @@
+use crate::curve::concentrated::{add_liquidity_delta, fees_earned, position_amounts};
+use crate::curve::Rounding;
+use crate::error::ErrorCode;
+use crate::utils::{amount_with_transfer_fee, spl_token_transfer, TokenTransferParams};
+use crate::{ModifyPosition, Tick, TickArray, PAUSE_DEPOSITS};
+use anchor_lang::prelude::*;
+
+// IncreaseLiquidityEvent event, amounts are the ones paid by the owner including transfer fees
+#[event]
+pub struct IncreaseLiquidityEvent {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub liquidity: u128,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    pub timestamp: u64,
+    pub owner: Pubkey,
+}
+
+/// Applies a liquidity delta to a position: updates its bounding ticks, settles the fees it
+/// earned since its last update and adjusts the pool liquidity if the position is in range.
+///
+/// Shared by `increase_liquidity`, `decrease_liquidity` and `collect_fees`, the latter with a
+/// zero delta to only settle fees.
+pub(crate) fn modify_position(accounts: &mut ModifyPosition, liquidity_delta: i128) -> Result<()> {
+    let pool = &mut accounts.pool;
+    let position = &mut accounts.position;
+    let (tick_lower, tick_upper) = (position.tick_lower, position.tick_upper);
+    let tick_spacing = pool.tick_spacing;
+
+    // Both bounds may live in the same tick array, which is then loaded once
+    let lower_info = accounts.tick_array_lower.to_account_info();
+    let upper_info = accounts.tick_array_upper.to_account_info();
+    let mut lower_array = TickArray::load(&lower_info, &pool.key())?;
+    let mut upper_array = if upper_info.key() == lower_info.key() {
+        None
+    } else {
+        Some(TickArray::load(&upper_info, &pool.key())?)
+    };
+    require_eq!(
+        lower_array.start_tick_index,
+        pool.tick_array_start(tick_lower),
+        ErrorCode::InvalidTickArray
+    );
+    require_eq!(
+        upper_array
+            .as_ref()
+            .unwrap_or(&lower_array)
+            .start_tick_index,
+        pool.tick_array_start(tick_upper),
+        ErrorCode::InvalidTickArray
+    );
+
+    if liquidity_delta != 0 {
+        lower_array.tick_mut(tick_lower, tick_spacing)?.update(
+            tick_lower,
+            pool,
+            liquidity_delta,
+            false,
+        )?;
+        upper_array
+            .as_mut()
+            .unwrap_or(&mut lower_array)
+            .tick_mut(tick_upper, tick_spacing)?
+            .update(tick_upper, pool, liquidity_delta, true)?;
+    }
+
+    // Settle the fees earned with the previous liquidity
+    let lower = *lower_array.tick_mut(tick_lower, tick_spacing)?;
+    let upper = *upper_array
+        .as_mut()
+        .unwrap_or(&mut lower_array)
+        .tick_mut(tick_upper, tick_spacing)?;
+    let (inside_base, inside_quote) =
+        pool.fee_growth_inside(tick_lower, &lower, tick_upper, &upper);
+    let earned_base = fees_earned(
+        position.liquidity,
+        inside_base.wrapping_sub(position.fee_growth_inside_base_last_x64),
+    )?;
+    let earned_quote = fees_earned(
+        position.liquidity,
+        inside_quote.wrapping_sub(position.fee_growth_inside_quote_last_x64),
+    )?;
+    position.tokens_owed_base = position
+        .tokens_owed_base
+        .checked_add(earned_base)
+        .ok_or(ErrorCode::MathOverflow)?;
+    position.tokens_owed_quote = position
+        .tokens_owed_quote
+        .checked_add(earned_quote)
+        .ok_or(ErrorCode::MathOverflow)?;
+    position.fee_growth_inside_base_last_x64 = inside_base;
+    position.fee_growth_inside_quote_last_x64 = inside_quote;
+    position.liquidity = add_liquidity_delta(position.liquidity, liquidity_delta)?;
+
+    // Liquidity in range of the current price is active right away
+    if pool.tick_current >= tick_lower && pool.tick_current < tick_upper {
+        pool.liquidity = add_liquidity_delta(pool.liquidity, liquidity_delta)?;
+    }
+
+    // Ticks no position uses anymore are cleared
+    if liquidity_delta < 0 {
+        for (array, tick) in [(0, tick_lower), (1, tick_upper)] {
+            let array = match (array, upper_array.as_mut()) {
+                (1, Some(upper_array)) => upper_array,
+                _ => &mut lower_array,
+            };
+            let tick = array.tick_mut(tick, tick_spacing)?;
+            if !tick.is_initialized() {
+                *tick = Tick::default();
+            }
+        }
+    }
+
+    lower_array.save(&lower_info)?;
+    if let Some(upper_array) = upper_array {
+        upper_array.save(&upper_info)?;
+    }
+    Ok(())
+}
+
+/// The `increase_liquidity` module is responsible for adding liquidity to a concentrated
+/// liquidity position. The owner deposits the base and quote amounts backing the new liquidity
+/// at the current pool price.
+pub mod increase_liquidity {
+    use super::*;
+
+    /// Adds `liquidity` to a position, ensuring the deposited amounts stay within the owner's limits.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the pool, the position, its tick arrays and token accounts.
+    /// - `liquidity`: The amount of liquidity to add to the position.
+    /// - `max_base_amount`: The maximum amount of base tokens the owner is willing to pay.
+    /// - `max_quote_amount`: The maximum amount of quote tokens the owner is willing to pay.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(
+        ctx: Context<ModifyPosition>,
+        liquidity: u128,
+        max_base_amount: u64,
+        max_quote_amount: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .global_parameters
+            .require_not_paused(PAUSE_DEPOSITS)?;
+        require_gt!(liquidity, 0, ErrorCode::InvalidTokenAmounts);
+        let liquidity_delta = i128::try_from(liquidity).map_err(|_| ErrorCode::MathOverflow)?;
+
+        // Amounts backing the liquidity, rounded in favor of the pool
+        let (base_deposit, quote_deposit) = position_amounts(
+            ctx.accounts.pool.sqrt_price_x64,
+            ctx.accounts.position.tick_lower,
+            ctx.accounts.position.tick_upper,
+            liquidity,
+            Rounding::Up,
+        )?;
+
+        // The vaults must receive the full amounts, so the owner pays any transfer fees on top
+        let base_amount =
+            amount_with_transfer_fee(&ctx.accounts.base_mint.to_account_info(), base_deposit)?;
+        let quote_amount =
+            amount_with_transfer_fee(&ctx.accounts.quote_mint.to_account_info(), quote_deposit)?;
+        require!(base_amount <= max_base_amount, ErrorCode::BaseAmountTooHigh);
+        require!(
+            quote_amount <= max_quote_amount,
+            ErrorCode::InsufficientQuoteAmount
+        );
+
+        modify_position(ctx.accounts, liquidity_delta)?;
+
+        if base_amount > 0 {
+            spl_token_transfer(TokenTransferParams {
+                source: ctx.accounts.owner_base_ata.to_account_info(),
+                destination: ctx.accounts.base_vault.to_account_info(),
+                amount: base_amount,
+                authority: ctx.accounts.owner.to_account_info(),
+                authority_signer_seeds: &[],
+                decimals: ctx.accounts.base_mint.decimals,
+                mint: ctx.accounts.base_mint.to_account_info(),
+                token_program: ctx.accounts.base_token_program.to_account_info(),
+            })?;
+        }
+        if quote_amount > 0 {
+            spl_token_transfer(TokenTransferParams {
+                source: ctx.accounts.owner_quote_ata.to_account_info(),
+                destination: ctx.accounts.quote_vault.to_account_info(),
+                amount: quote_amount,
+                authority: ctx.accounts.owner.to_account_info(),
+                authority_signer_seeds: &[],
+                decimals: ctx.accounts.quote_mint.decimals,
+                mint: ctx.accounts.quote_mint.to_account_info(),
+                token_program: ctx.accounts.quote_token_program.to_account_info(),
+            })?;
+        }
+
+        // Emit the event
+        emit_cpi!(IncreaseLiquidityEvent {
+            pool: ctx.accounts.pool.key(),
+            position: ctx.accounts.position.key(),
+            liquidity,
+            base_amount,
+            quote_amount,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+            owner: ctx.accounts.owner.key(),
+        });
+
+        emit!(IncreaseLiquidityEvent {
+            pool: ctx.accounts.pool.key(),
+            position: ctx.accounts.position.key(),
+            liquidity,
+            base_amount,
+            quote_amount,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+            owner: ctx.accounts.owner.key(),
+        });
+
+        Ok(())
+    }
+}
@@ -6,7 +6,7 @@ use anchor_lang::prelude::*;
 /// and ensuring that the AMM is ready for operations like creating new AMMs, adding liquidity, etc.
 pub mod initialize {
     use super::*;
-    use crate::Initialize;
+    use crate::{FeeMode, Initialize};
 
     pub fn handler(ctx: Context<Initialize>) -> Result<()> {
         let global_parameters = &mut ctx.accounts.global_parameters;
@@ -16,6 +16,8 @@ pub mod initialize {
         );
 
         global_parameters.admin = ctx.accounts.admin.key();
+        global_parameters.treasury = ctx.accounts.admin.key();
+        global_parameters.fee_mode = FeeMode::OnTop;
 
         Ok(())
     }
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+/// This module is responsible for creating the optional observations account of an AMM. Anyone
+/// can pay for it, it starts with room for a single entry and grows with `grow_observations`.
+pub mod initialize_observations {
+    use super::*;
+    use crate::InitializeObservations;
+
+    /// Creates the observations account of an AMM, seeded with the pool's current values.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the AMM, the observations account to create and the payer.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<InitializeObservations>) -> Result<()> {
+        let observation = ctx.accounts.amm.observe(Clock::get()?.unix_timestamp);
+
+        let observations = &mut ctx.accounts.observations;
+        observations.amm = ctx.accounts.amm.key();
+        observations.capacity = 1;
+        observations.write(observation);
+
+        Ok(())
+    }
+}
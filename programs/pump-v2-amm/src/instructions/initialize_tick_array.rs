@@ -0,0 +1,36 @@
+use crate::curve::concentrated::{MAX_TICK, MIN_TICK};
+use crate::error::ErrorCode;
+use crate::{Tick, TICK_ARRAY_SIZE};
+use anchor_lang::prelude::*;
+
+/// This module is responsible for creating the tick arrays of concentrated liquidity pools.
+/// Anyone can pay for one, positions and swaps need the arrays covering their ticks to exist.
+pub mod initialize_tick_array {
+    use super::*;
+    use crate::InitializeTickArray;
+
+    /// Creates the tick array of a pool starting at `start_tick_index`.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the pool, the tick array to create and the payer.
+    /// - `start_tick_index`: First tick of the array, a multiple of the ticks covered per array.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<InitializeTickArray>, start_tick_index: i32) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+        require!(
+            start_tick_index == pool.tick_array_start(start_tick_index)
+                && start_tick_index >= pool.tick_array_start(MIN_TICK)
+                && start_tick_index <= MAX_TICK,
+            ErrorCode::InvalidTickArray
+        );
+
+        let tick_array = &mut ctx.accounts.tick_array;
+        tick_array.pool = pool.key();
+        tick_array.start_tick_index = start_tick_index;
+        tick_array.ticks = vec![Tick::default(); TICK_ARRAY_SIZE];
+
+        Ok(())
+    }
+}
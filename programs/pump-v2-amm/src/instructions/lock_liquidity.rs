@@ -0,0 +1,319 @@
+use crate::error::ErrorCode;
+use crate::utils::{spl_token_transfer, TokenTransferParams};
+use anchor_lang::prelude::*;
+
+// LockLiquidityEvent event
+#[event]
+pub struct LockLiquidityEvent {
+    pub amm: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub locked_amount: u64, // shares locked for the owner after this lock
+    pub unlock_timestamp: Option<i64>, // None for a permanent lock
+    pub fee_shares: u64,    // fees claimed before locking
+    pub timestamp: u64,
+}
+
+/// Locks LP shares in the pool's locker vault, permanently or until a timestamp, while the
+/// owner keeps claiming the fees they earn with `claim_locked_fees`.
+pub mod lock_liquidity {
+    use super::*;
+    use crate::LockLiquidity;
+
+    /// Transfers the owner's LP shares to the locker vault and adds them to their lock.
+    ///
+    /// Adding to an existing lock can only make it longer, see `LiquidityLock::add`. The fees
+    /// pending on the existing lock are paid out first.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the owner's LP token account, the vault and the lock.
+    /// - `amount`: Amount of LP shares to lock.
+    /// - `unlock_timestamp`: When the shares can be unlocked, `None` to lock them forever.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(
+        ctx: Context<LockLiquidity>,
+        amount: u64,
+        unlock_timestamp: Option<i64>,
+    ) -> Result<()> {
+        require_gt!(amount, 0, ErrorCode::InvalidTokenAmounts);
+        let now = Clock::get()?.unix_timestamp;
+        if let Some(unlock_timestamp) = unlock_timestamp {
+            require_gt!(unlock_timestamp, now, ErrorCode::InvalidUnlockTimestamp);
+        }
+
+        let amm = &ctx.accounts.amm;
+        let liquidity_lock = &mut ctx.accounts.liquidity_lock;
+        liquidity_lock.amm = amm.key();
+        liquidity_lock.owner = ctx.accounts.owner.key();
+        let fee_shares = liquidity_lock.claim_fees(amm, now)?;
+        liquidity_lock.add(amount, unlock_timestamp, amm, now)?;
+
+        // Pay out the fees earned so far, the added shares only earn from now on
+        if fee_shares > 0 {
+            let amm_key = ctx.accounts.amm.key();
+            let signer_seeds = [
+                b"lp_locker".as_ref(),
+                amm_key.as_ref(),
+                &[ctx.bumps.lp_locker],
+            ];
+            spl_token_transfer(TokenTransferParams {
+                source: ctx.accounts.locker_lp_ata.to_account_info(),
+                destination: ctx.accounts.owner_lp_ata.to_account_info(),
+                amount: fee_shares,
+                authority: ctx.accounts.lp_locker.to_account_info(),
+                authority_signer_seeds: &signer_seeds,
+                decimals: ctx.accounts.lp_mint.decimals,
+                mint: ctx.accounts.lp_mint.to_account_info(),
+                token_program: ctx.accounts.lp_token_program.to_account_info(),
+            })?;
+        }
+
+        // Transfer the shares to the locker vault
+        spl_token_transfer(TokenTransferParams {
+            source: ctx.accounts.owner_lp_ata.to_account_info(),
+            destination: ctx.accounts.locker_lp_ata.to_account_info(),
+            amount,
+            authority: ctx.accounts.owner.to_account_info(),
+            authority_signer_seeds: &[],
+            decimals: ctx.accounts.lp_mint.decimals,
+            mint: ctx.accounts.lp_mint.to_account_info(),
+            token_program: ctx.accounts.lp_token_program.to_account_info(),
+        })?;
+
+        // Emit the event
+        emit_cpi!(LockLiquidityEvent {
+            amm: ctx.accounts.amm.key(),
+            owner: ctx.accounts.owner.key(),
+            amount,
+            locked_amount: ctx.accounts.liquidity_lock.amount,
+            unlock_timestamp: ctx.accounts.liquidity_lock.unlock_timestamp,
+            fee_shares,
+            timestamp: now as u64,
+        });
+
+        emit!(LockLiquidityEvent {
+            amm: ctx.accounts.amm.key(),
+            owner: ctx.accounts.owner.key(),
+            amount,
+            locked_amount: ctx.accounts.liquidity_lock.amount,
+            unlock_timestamp: ctx.accounts.liquidity_lock.unlock_timestamp,
+            fee_shares,
+            timestamp: now as u64,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::{lp_share_global_parameters, setup_test_environment, stable_amm};
+    use crate::state::LiquidityLock;
+    use crate::{AmpRamp, Curve};
+    use anchor_lang::{AccountDeserialize, AnchorSerialize};
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+    use std::str::FromStr;
+    use switchboard_solana::Pubkey;
+
+    fn locker_accounts(
+        program_id: &Pubkey,
+        amm: &Pubkey,
+        owner: &Pubkey,
+        lp_mint: &Pubkey,
+    ) -> Vec<AccountMeta> {
+        let lp_locker = Pubkey::find_program_address(&[b"lp_locker", amm.as_ref()], program_id).0;
+        vec![
+            AccountMeta::new_readonly(*amm, false),
+            AccountMeta::new_readonly(
+                Pubkey::find_program_address(&[b"global_parameters"], program_id).0,
+                false,
+            ),
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    owner,
+                    lp_mint,
+                    &spl_token::ID,
+                ),
+                false,
+            ),
+            AccountMeta::new_readonly(*lp_mint, false),
+            AccountMeta::new_readonly(lp_locker, false),
+            AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &lp_locker,
+                    lp_mint,
+                    &spl_token::ID,
+                ),
+                false,
+            ),
+            AccountMeta::new(
+                Pubkey::find_program_address(
+                    &[b"liquidity_lock", amm.as_ref(), owner.as_ref()],
+                    program_id,
+                )
+                .0,
+                false,
+            ),
+            AccountMeta::new_readonly(spl_token::ID, false),
+        ]
+    }
+
+    fn lock_liquidity_instruction(
+        program_id: &Pubkey,
+        amm: &Pubkey,
+        owner: &Pubkey,
+        lp_mint: &Pubkey,
+        amount: u64,
+        unlock_timestamp: Option<i64>,
+    ) -> Instruction {
+        let mut data = switchboard_solana::get_ixn_discriminator("lock_liquidity").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        unlock_timestamp.serialize(&mut data).unwrap();
+        let mut accounts = locker_accounts(program_id, amm, owner, lp_mint);
+        accounts.push(AccountMeta::new_readonly(
+            spl_associated_token_account::ID,
+            false,
+        ));
+        accounts.push(AccountMeta::new_readonly(
+            solana_program::system_program::ID,
+            false,
+        ));
+        accounts.push(AccountMeta::new_readonly(
+            Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+            false,
+        ));
+        accounts.push(AccountMeta::new_readonly(*program_id, false));
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    }
+
+    fn unlock_liquidity_instruction(
+        program_id: &Pubkey,
+        amm: &Pubkey,
+        owner: &Pubkey,
+        lp_mint: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let mut data = switchboard_solana::get_ixn_discriminator("unlock_liquidity").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        let mut accounts = locker_accounts(program_id, amm, owner, lp_mint);
+        accounts.push(AccountMeta::new_readonly(
+            Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+            false,
+        ));
+        accounts.push(AccountMeta::new_readonly(*program_id, false));
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    }
+
+    #[test]
+    fn test_amp_ramp_pays_no_locked_fees() {
+        let global_parameters = lp_share_global_parameters();
+        let ramp = AmpRamp {
+            initial_amp: 100,
+            target_amp: 1_000,
+            ramp_start_ts: 0,
+            ramp_stop_ts: 86_400,
+        };
+        let mut amm = stable_amm(1_000_000, 3_000_000, 1_000_000, ramp);
+        amm.update_liquidity_last(&global_parameters, 0).unwrap();
+        let mut lock = LiquidityLock {
+            amm: Pubkey::default(),
+            owner: Pubkey::default(),
+            amount: 0,
+            unlock_timestamp: None,
+            liquidity_per_share_x64: 0,
+            amp: 0,
+        };
+        lock.add(500_000, None, &amm, 0).unwrap();
+
+        // Ramping the amp up and back down pays nothing
+        assert_eq!(lock.claim_fees(&amm, 86_400).unwrap(), 0);
+        amm.curve = Curve::Stable(AmpRamp {
+            initial_amp: 1_000,
+            target_amp: 100,
+            ramp_start_ts: 86_400,
+            ramp_stop_ts: 172_800,
+        });
+        assert_eq!(lock.claim_fees(&amm, 172_800).unwrap(), 0);
+        assert_eq!(lock.amount, 500_000);
+
+        // Fees added to the reserves are still paid out
+        amm.quote_reserve += 30_000;
+        assert!(lock.claim_fees(&amm, 172_800).unwrap() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_lock_liquidity_until_timestamp() {
+        let setup = setup_test_environment(true).await;
+        let owner = setup.keypair.pubkey();
+        let unlock_timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64
+            + 3600;
+
+        let ix = lock_liquidity_instruction(
+            &setup.program_id,
+            &setup.amm_account,
+            &owner,
+            &setup.lp_mint,
+            10_000,
+            Some(unlock_timestamp),
+        );
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&owner));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        setup
+            .client
+            .send_and_confirm_transaction(&tx)
+            .await
+            .unwrap();
+
+        let liquidity_lock = Pubkey::find_program_address(
+            &[
+                b"liquidity_lock",
+                setup.amm_account.as_ref(),
+                owner.as_ref(),
+            ],
+            &setup.program_id,
+        )
+        .0;
+        let account = setup.client.get_account(&liquidity_lock).await.unwrap();
+        let mut data: &[u8] = &account.data;
+        let lock = LiquidityLock::try_deserialize(&mut data).unwrap();
+        assert!(lock.amount >= 10_000);
+        assert!(lock.unlock_timestamp.unwrap() >= unlock_timestamp);
+
+        // The shares cannot be unlocked before the timestamp
+        let ix = unlock_liquidity_instruction(
+            &setup.program_id,
+            &setup.amm_account,
+            &owner,
+            &setup.lp_mint,
+            10_000,
+        );
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&owner));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        assert!(setup
+            .client
+            .send_and_confirm_transaction(&tx)
+            .await
+            .is_err());
+    }
+}
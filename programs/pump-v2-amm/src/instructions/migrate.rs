@@ -0,0 +1,503 @@
+use crate::error::ErrorCode;
+use crate::instructions::add_liquidity::MINIMUM_LIQUIDITY;
+use crate::instructions::create::new_pool_curve;
+use crate::utils::{
+    amount_after_transfer_fee, check_reserve_balances, create_fee_receiver_ata, spl_token_transfer,
+    TokenTransferParams,
+};
+use crate::{Curve, MigrationLp};
+use anchor_lang::prelude::*;
+
+// MigrationEvent event, amounts are the starting reserves, after transfer fees
+#[event]
+pub struct MigrationEvent {
+    pub amm: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub curve: Curve,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    pub shares: u64,
+    pub lp: MigrationLp,
+    pub price_x64: u128, // starting quote per base price, Q64.64
+    pub timestamp: u64,
+    pub user: Pubkey,
+}
+
+/// Migrates a token into a new pool in a single instruction.
+///
+/// Creating the pool, its vaults and the first deposit atomically means nobody can get a deposit
+/// in between and set the starting price, as they could between `create` and `add_liquidity`.
+/// The LP shares of the deposit are kept by the user, burned or locked, see `MigrationLp`.
+///
+/// The vaults are associated token accounts of the pool, whose address anyone can derive and
+/// create ahead of the migration. Tokens already in them are credited to the starting reserves
+/// along with the deposit, so that such accounts cannot block the migration.
+pub mod migrate {
+    use super::*;
+    use crate::{Migrate, PAUSE_DEPOSITS};
+
+    /// Creates the AMM and seeds it with the user's base and quote tokens.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing all the accounts required to create and seed the AMM.
+    /// - `amp`: Amplification coefficient of a stable pool, `None` for a constant product pool.
+    /// - `base_amount`: Amount of the base currency deposited.
+    /// - `quote_amount`: Amount of the quote currency deposited.
+    /// - `lp`: Whether the LP shares are minted to the user, burned or locked for the user.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(
+        ctx: Context<Migrate>,
+        amp: Option<u64>,
+        base_amount: u64,
+        quote_amount: u64,
+        lp: MigrationLp,
+    ) -> Result<()> {
+        ctx.accounts
+            .global_parameters
+            .require_not_paused(PAUSE_DEPOSITS)?;
+        let curve = new_pool_curve(
+            amp,
+            ctx.accounts.base_mint.decimals,
+            ctx.accounts.quote_mint.decimals,
+        )?;
+        let now = Clock::get()?.unix_timestamp;
+
+        // Only what reaches the vaults after Token-2022 transfer fees is credited to the pool, on
+        // top of whatever the vaults already hold
+        let base_reserve =
+            amount_after_transfer_fee(&ctx.accounts.base_mint.to_account_info(), base_amount)?
+                .checked_add(ctx.accounts.base_reserve_ata.amount)
+                .ok_or(ErrorCode::MathOverflow)?;
+        let quote_reserve =
+            amount_after_transfer_fee(&ctx.accounts.quote_mint.to_account_info(), quote_amount)?
+                .checked_add(ctx.accounts.quote_reserve_ata.amount)
+                .ok_or(ErrorCode::MathOverflow)?;
+
+        // Initialize AMM state with the vault balances as its first reserves
+        let amm = &mut ctx.accounts.amm;
+        amm.base_mint = ctx.accounts.base_mint.key();
+        amm.quote_mint = ctx.accounts.quote_mint.key();
+        amm.lp_mint = ctx.accounts.lp_mint.key();
+        amm.creator = ctx.accounts.user.key();
+        amm.base_reserve_ata = ctx.accounts.base_reserve_ata.key();
+        amm.quote_reserve_ata = ctx.accounts.quote_reserve_ata.key();
+        amm.fee_receiver_ata = ctx.accounts.fee_receiver_ata.key();
+        amm.curve = curve;
+        amm.last_price_update_ts = now;
+        let shares = amm
+            .invariant(now)
+            .calculate_shares(base_reserve, quote_reserve, 0, 0, 0)?;
+        require_gt!(shares, MINIMUM_LIQUIDITY, ErrorCode::InsufficientLiquidity);
+        amm.base_reserve = base_reserve;
+        amm.quote_reserve = quote_reserve;
+        amm.total_shares = shares;
+        amm.update_liquidity_last(&ctx.accounts.global_parameters, now)?;
+        let price_x64 = ((quote_reserve as u128) << 64) / base_reserve as u128;
+
+        // Protocol fees go to the fee authority, shared by every pool with the same quote mint
+        create_fee_receiver_ata(
+            ctx.accounts.user.to_account_info(),
+            ctx.accounts.fee_receiver_ata.to_account_info(),
+            ctx.accounts.fee_authority.to_account_info(),
+            ctx.accounts.quote_mint.to_account_info(),
+            ctx.accounts.quote_token_program.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.associated_token_program.to_account_info(),
+        )?;
+
+        // Transfer base tokens to the AMM reserves
+        spl_token_transfer(TokenTransferParams {
+            source: ctx.accounts.user_base_ata.to_account_info(),
+            destination: ctx.accounts.base_reserve_ata.to_account_info(),
+            amount: base_amount,
+            authority: ctx.accounts.user.to_account_info(),
+            authority_signer_seeds: &[],
+            decimals: ctx.accounts.base_mint.decimals,
+            mint: ctx.accounts.base_mint.to_account_info(),
+            token_program: ctx.accounts.base_token_program.to_account_info(),
+        })?;
+
+        // Transfer quote tokens to the AMM reserves
+        spl_token_transfer(TokenTransferParams {
+            source: ctx.accounts.user_quote_ata.to_account_info(),
+            destination: ctx.accounts.quote_reserve_ata.to_account_info(),
+            amount: quote_amount,
+            authority: ctx.accounts.user.to_account_info(),
+            authority_signer_seeds: &[],
+            decimals: ctx.accounts.quote_mint.decimals,
+            mint: ctx.accounts.quote_mint.to_account_info(),
+            token_program: ctx.accounts.quote_token_program.to_account_info(),
+        })?;
+
+        // The minimum liquidity is never minted, as on the first `add_liquidity`
+        let user_shares = shares - MINIMUM_LIQUIDITY;
+        let lp_destination = match lp {
+            MigrationLp::Keep => Some(ctx.accounts.user_lp_ata.to_account_info()),
+            MigrationLp::Burn => None,
+            MigrationLp::Lock { unlock_timestamp } => {
+                if let Some(unlock_timestamp) = unlock_timestamp {
+                    require_gt!(unlock_timestamp, now, ErrorCode::InvalidUnlockTimestamp);
+                }
+                let (Some(locker_lp_ata), Some(liquidity_lock)) = (
+                    &ctx.accounts.locker_lp_ata,
+                    &mut ctx.accounts.liquidity_lock,
+                ) else {
+                    return err!(ErrorCode::MissingLockerAccounts);
+                };
+                liquidity_lock.amm = ctx.accounts.amm.key();
+                liquidity_lock.owner = ctx.accounts.user.key();
+                liquidity_lock.add(user_shares, unlock_timestamp, &ctx.accounts.amm, now)?;
+                Some(locker_lp_ata.to_account_info())
+            }
+        };
+        if let Some(lp_destination) = lp_destination {
+            let signer_seeds = [
+                b"amm",
+                ctx.accounts.amm.creator.as_ref(),
+                ctx.accounts.base_mint.to_account_info().key.as_ref(),
+                ctx.accounts.quote_mint.to_account_info().key.as_ref(),
+                &[ctx.bumps.amm],
+            ];
+            anchor_spl::token_interface::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.lp_token_program.to_account_info(),
+                    anchor_spl::token_interface::MintTo {
+                        mint: ctx.accounts.lp_mint.to_account_info(),
+                        to: lp_destination,
+                        authority: ctx.accounts.amm.to_account_info(),
+                    },
+                    &[&signer_seeds],
+                ),
+                user_shares,
+            )?;
+        }
+
+        // Make sure the vaults back the recorded reserves
+        check_reserve_balances(
+            &ctx.accounts.amm,
+            &mut ctx.accounts.base_reserve_ata,
+            &mut ctx.accounts.quote_reserve_ata,
+        )?;
+
+        // Emit the event
+        emit_cpi!(MigrationEvent {
+            amm: ctx.accounts.amm.key(),
+            base_mint: ctx.accounts.base_mint.key(),
+            quote_mint: ctx.accounts.quote_mint.key(),
+            lp_mint: ctx.accounts.lp_mint.key(),
+            curve,
+            base_amount: base_reserve,
+            quote_amount: quote_reserve,
+            shares: user_shares,
+            lp,
+            price_x64,
+            timestamp: now as u64,
+            user: ctx.accounts.user.key(),
+        });
+
+        emit!(MigrationEvent {
+            amm: ctx.accounts.amm.key(),
+            base_mint: ctx.accounts.base_mint.key(),
+            quote_mint: ctx.accounts.quote_mint.key(),
+            lp_mint: ctx.accounts.lp_mint.key(),
+            curve,
+            base_amount: base_reserve,
+            quote_amount: quote_reserve,
+            shares: user_shares,
+            lp,
+            price_x64,
+            timestamp: now as u64,
+            user: ctx.accounts.user.key(),
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::add_liquidity_instruction;
+    use crate::fixtures::tests::setup_mints_and_accounts;
+    use crate::fixtures::tests::setup_test_environment;
+    use crate::fixtures::tests::TestEnvironment;
+    use crate::instructions::add_liquidity::MINIMUM_LIQUIDITY;
+    use crate::{Amm, MigrationLp};
+    use anchor_lang::{AccountDeserialize, AnchorSerialize};
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::signature::Keypair;
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+    use std::str::FromStr;
+    use switchboard_solana::Pubkey;
+
+    /// Funds the payer with fresh mints and migrates 100M base and 1B quote into a new pool,
+    /// after creating its quote vault with `quote_donation` in it when non-zero.
+    ///
+    /// Returns the pool, its base, quote and LP mints.
+    async fn migrate_pool(
+        setup: &TestEnvironment,
+        lp: MigrationLp,
+        quote_donation: u64,
+    ) -> (Pubkey, Pubkey, Pubkey, Pubkey) {
+        let payer = setup.keypair.pubkey();
+        let (base_mint, quote_mint) =
+            setup_mints_and_accounts(setup.client.clone(), &setup.keypair)
+                .await
+                .unwrap();
+        let user_base_ata =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &payer,
+                &base_mint,
+                &spl_token_2022::ID,
+            );
+        let user_quote_ata =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &payer,
+                &quote_mint,
+                &spl_token::ID,
+            );
+        let amm = Pubkey::find_program_address(
+            &[
+                b"amm",
+                payer.as_ref(),
+                base_mint.as_ref(),
+                quote_mint.as_ref(),
+            ],
+            &setup.program_id,
+        )
+        .0;
+        let quote_vault =
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                &amm,
+                &quote_mint,
+                &spl_token::ID,
+            );
+        let mut fund_ixs = vec![
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &payer,
+                &payer,
+                &base_mint,
+                &spl_token_2022::ID,
+            ),
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &payer,
+                &payer,
+                &quote_mint,
+                &spl_token::ID,
+            ),
+            spl_token_2022::instruction::mint_to(
+                &spl_token_2022::ID,
+                &base_mint,
+                &user_base_ata,
+                &payer,
+                &[],
+                200_000_000,
+            )
+            .unwrap(),
+            spl_token::instruction::mint_to(
+                &spl_token::ID,
+                &quote_mint,
+                &user_quote_ata,
+                &payer,
+                &[],
+                2_000_000_000,
+            )
+            .unwrap(),
+        ];
+        if quote_donation > 0 {
+            fund_ixs.push(
+                spl_associated_token_account::instruction::create_associated_token_account(
+                    &payer,
+                    &amm,
+                    &quote_mint,
+                    &spl_token::ID,
+                ),
+            );
+            fund_ixs.push(
+                spl_token::instruction::transfer(
+                    &spl_token::ID,
+                    &user_quote_ata,
+                    &quote_vault,
+                    &payer,
+                    &[],
+                    quote_donation,
+                )
+                .unwrap(),
+            );
+        }
+
+        let lp_mint = Keypair::new();
+        let fee_authority = Pubkey::find_program_address(&[b"fee_authority"], &setup.program_id).0;
+        let mut data = switchboard_solana::get_ixn_discriminator("migrate").to_vec();
+        None::<u64>.serialize(&mut data).unwrap();
+        data.extend_from_slice(&100_000_000u64.to_le_bytes());
+        data.extend_from_slice(&1_000_000_000u64.to_le_bytes());
+        lp.serialize(&mut data).unwrap();
+        let migrate_ix = Instruction {
+            program_id: setup.program_id,
+            accounts: vec![
+                AccountMeta::new(amm, false),
+                AccountMeta::new_readonly(setup.global_parameters, false),
+                AccountMeta::new(payer, true),
+                AccountMeta::new(user_base_ata, false),
+                AccountMeta::new(user_quote_ata, false),
+                AccountMeta::new(lp_mint.pubkey(), true),
+                AccountMeta::new(
+                    spl_associated_token_account::get_associated_token_address_with_program_id(
+                        &payer,
+                        &lp_mint.pubkey(),
+                        &spl_token::ID,
+                    ),
+                    false,
+                ),
+                AccountMeta::new(
+                    spl_associated_token_account::get_associated_token_address_with_program_id(
+                        &amm,
+                        &base_mint,
+                        &spl_token_2022::ID,
+                    ),
+                    false,
+                ),
+                AccountMeta::new(quote_vault, false),
+                AccountMeta::new(
+                    spl_associated_token_account::get_associated_token_address_with_program_id(
+                        &fee_authority,
+                        &quote_mint,
+                        &spl_token::ID,
+                    ),
+                    false,
+                ),
+                AccountMeta::new_readonly(fee_authority, false),
+                AccountMeta::new_readonly(base_mint, false),
+                AccountMeta::new_readonly(quote_mint, false),
+                AccountMeta::new_readonly(spl_token_2022::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+                AccountMeta::new_readonly(solana_program::system_program::ID, false),
+                // No locker accounts, the LP shares are kept or burned
+                AccountMeta::new_readonly(setup.program_id, false),
+                AccountMeta::new_readonly(setup.program_id, false),
+                AccountMeta::new_readonly(setup.program_id, false),
+                AccountMeta::new_readonly(
+                    Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+                    false,
+                ),
+                AccountMeta::new_readonly(setup.program_id, false),
+            ],
+            data,
+        };
+
+        let mut ixs = fund_ixs;
+        ixs.push(migrate_ix);
+        let mut tx = Transaction::new_with_payer(&ixs, Some(&payer));
+        tx.sign(
+            &[&setup.keypair, &lp_mint],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        setup
+            .client
+            .send_and_confirm_transaction(&tx)
+            .await
+            .unwrap();
+
+        (amm, base_mint, quote_mint, lp_mint.pubkey())
+    }
+
+    async fn fetch_amm(setup: &TestEnvironment, amm: &Pubkey) -> Amm {
+        let amm_info = setup.client.get_account(amm).await.unwrap();
+        let mut data: &[u8] = &amm_info.data;
+        Amm::try_deserialize(&mut data).unwrap()
+    }
+
+    async fn lp_balance(setup: &TestEnvironment, lp_mint: &Pubkey) -> u64 {
+        setup
+            .client
+            .get_token_account_balance(
+                &spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &setup.keypair.pubkey(),
+                    lp_mint,
+                    &spl_token::ID,
+                ),
+            )
+            .await
+            .unwrap()
+            .amount
+            .parse::<u64>()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_migrate_creates_and_seeds_pool() {
+        // Only used for its client, payer and program
+        let setup = setup_test_environment(false).await;
+        let (amm, _, _, lp_mint) = migrate_pool(&setup, MigrationLp::Keep, 0).await;
+
+        let amm_state = fetch_amm(&setup, &amm).await;
+        assert_eq!(amm_state.base_reserve, 100_000_000);
+        assert_eq!(amm_state.quote_reserve, 1_000_000_000);
+
+        assert_eq!(
+            lp_balance(&setup, &lp_mint).await,
+            amm_state.total_shares - MINIMUM_LIQUIDITY,
+            "The user should receive every share but the minimum liquidity"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_migrate_credits_precreated_vault() {
+        let setup = setup_test_environment(false).await;
+        let (amm, _, _, lp_mint) = migrate_pool(&setup, MigrationLp::Keep, 5_000).await;
+
+        // The vault created ahead of the migration does not block it, its balance is credited
+        let amm_state = fetch_amm(&setup, &amm).await;
+        assert_eq!(amm_state.base_reserve, 100_000_000);
+        assert_eq!(amm_state.quote_reserve, 1_000_005_000);
+        assert_eq!(
+            lp_balance(&setup, &lp_mint).await,
+            amm_state.total_shares - MINIMUM_LIQUIDITY
+        );
+    }
+
+    #[tokio::test]
+    async fn test_add_liquidity_after_burned_migration() {
+        let setup = setup_test_environment(false).await;
+        let (amm, base_mint, quote_mint, lp_mint) =
+            migrate_pool(&setup, MigrationLp::Burn, 0).await;
+        let shares_before = fetch_amm(&setup, &amm).await.total_shares;
+        assert_eq!(lp_balance(&setup, &lp_mint).await, 0);
+
+        // The pool has shares but no LP supply, this is not a first deposit
+        let ix = add_liquidity_instruction(
+            &setup.program_id,
+            &amm,
+            &base_mint,
+            &quote_mint,
+            &setup.keypair.pubkey(),
+            &lp_mint,
+            10_000_000,
+            100_000_000,
+            0,
+        );
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        setup
+            .client
+            .send_and_confirm_transaction(&tx)
+            .await
+            .unwrap();
+
+        let amm_state = fetch_amm(&setup, &amm).await;
+        assert_eq!(
+            lp_balance(&setup, &lp_mint).await,
+            amm_state.total_shares - shares_before,
+            "No minimum liquidity should be burned from the depositor"
+        );
+    }
+}
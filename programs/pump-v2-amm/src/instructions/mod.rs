@@ -1,9 +1,54 @@
 pub mod accept_admin;
 pub mod add_liquidity;
 pub mod buy;
+pub mod buy_exact_in;
+pub mod cancel_admin_proposal;
+pub mod cancel_parameters;
+pub mod cancel_pool_parameters;
+pub mod cl_swap;
+pub mod claim_locked_fees;
+pub mod claim_vested;
+pub mod collect_fees;
 pub mod create;
+pub mod create_cl_pool;
+pub mod create_vesting;
+pub mod decrease_liquidity;
+pub mod execute_parameters;
+pub mod execute_pool_parameters;
+pub mod flash_borrow;
+pub mod flash_repay;
+pub mod grant_roles;
+pub mod grow_observations;
+pub mod increase_liquidity;
 pub mod initialize;
+pub mod initialize_observations;
+pub mod initialize_tick_array;
+pub mod lock_liquidity;
+pub mod migrate;
+pub mod observe;
+pub mod observe_at;
+pub mod open_position;
+pub mod pause;
+pub mod pause_pool;
 pub mod propose_admin;
+pub mod queue_parameters;
+pub mod queue_pool_parameters;
+pub mod ramp_amp;
+pub mod register_referrer;
 pub mod remove_liquidity;
+pub mod revoke_roles;
+pub mod route;
 pub mod sell;
-pub mod set_parameters;
+pub mod sell_exact_out;
+pub mod set_referrer_fee_tier;
+pub mod set_treasury;
+pub mod sync;
+pub mod unlock_liquidity;
+pub mod unpause;
+pub mod unpause_pool;
+pub mod update_fee_receiver;
+pub mod upgrade_amm;
+pub mod upgrade_global_parameters;
+pub mod withdraw_protocol_fees;
+pub mod zap_in;
+pub mod zap_out;
@@ -0,0 +1,73 @@
+use crate::Observation;
+use anchor_lang::prelude::*;
+
+/// The `observe` module exposes the price accumulators of an AMM to other programs. It is
+/// read-only: callers CPI into it, keep the returned observations and average the price between
+/// two of them, which cannot be moved within a single transaction.
+pub mod observe {
+    use super::*;
+    use crate::Observe;
+
+    /// Returns the AMM's price accumulators brought forward to the current time.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the AMM.
+    ///
+    /// # Returns:
+    /// - Result containing the current `Observation`, also set as the instruction's return data.
+    pub fn handler(ctx: Context<Observe>) -> Result<Observation> {
+        Ok(ctx.accounts.amm.observe(Clock::get()?.unix_timestamp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::setup_test_environment;
+    use crate::fixtures::tests::TestEnvironment;
+    use crate::Observation;
+    use anchor_lang::AnchorDeserialize;
+    use base64::Engine;
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+
+    fn observe_instruction(setup: &TestEnvironment) -> solana_sdk::instruction::Instruction {
+        let data = switchboard_solana::get_ixn_discriminator("observe").to_vec();
+        solana_sdk::instruction::Instruction {
+            program_id: setup.program_id,
+            accounts: vec![solana_sdk::instruction::AccountMeta::new_readonly(
+                setup.amm_account,
+                false,
+            )],
+            data,
+        }
+    }
+
+    async fn observe(setup: &TestEnvironment) -> Observation {
+        let mut tx = Transaction::new_with_payer(
+            &[observe_instruction(setup)],
+            Some(&setup.keypair.pubkey()),
+        );
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.simulate_transaction(&tx).await.unwrap();
+        let (data, _) = result.value.return_data.unwrap().data;
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .unwrap();
+        Observation::deserialize(&mut &data[..]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_observe_average_price() {
+        let setup = setup_test_environment(true).await;
+
+        let earlier = observe(&setup).await;
+        tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+        let later = observe(&setup).await;
+
+        // The fixture pool holds 10 quote per base and nothing traded in between
+        let (base_price_x64, _) = later.average_prices_since(&earlier).unwrap();
+        assert_eq!(base_price_x64 >> 64, 10);
+    }
+}
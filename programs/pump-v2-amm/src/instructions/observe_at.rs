@@ -0,0 +1,230 @@
+use crate::Observation;
+use anchor_lang::prelude::*;
+
+/// The `observe_at` module exposes the observation history of an AMM to other programs. Like
+/// `observe` it is read-only, but it can answer for any timestamp inside the recorded window.
+pub mod observe_at {
+    use super::*;
+    use crate::ObserveAt;
+
+    /// Returns the AMM's cumulative values at a past timestamp.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the AMM and its observations account.
+    /// - `timestamp`: Unix timestamp to observe, no older than the oldest recorded entry.
+    ///
+    /// # Returns:
+    /// - Result containing the interpolated `Observation`, also set as the instruction's return data.
+    pub fn handler(ctx: Context<ObserveAt>, timestamp: i64) -> Result<Observation> {
+        require!(
+            timestamp <= Clock::get()?.unix_timestamp,
+            crate::error::ErrorCode::InvalidObservationWindow
+        );
+        ctx.accounts
+            .observations
+            .observe_at(&ctx.accounts.amm, timestamp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::setup_test_environment;
+    use crate::fixtures::tests::TestEnvironment;
+    use crate::{Amm, Observation, Observations};
+    use anchor_lang::AnchorDeserialize;
+    use base64::Engine;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+    use switchboard_solana::Pubkey;
+
+    fn observation(timestamp: i64) -> Observation {
+        // A constant price of 2 and liquidity of 10 since timestamp 0
+        Observation {
+            timestamp,
+            base_price_cumulative_x64: (2u128 << 64) * timestamp as u128,
+            quote_price_cumulative_x64: (1u128 << 63) * timestamp as u128,
+            liquidity_cumulative: 10 * timestamp as u128,
+        }
+    }
+
+    fn observations(capacity: u16, timestamps: &[i64]) -> Observations {
+        let mut observations = Observations {
+            amm: Pubkey::default(),
+            capacity,
+            index: 0,
+            entries: vec![],
+        };
+        for timestamp in timestamps {
+            observations.write(observation(*timestamp));
+        }
+        observations
+    }
+
+    fn amm_at(timestamp: i64) -> Amm {
+        let current = observation(timestamp);
+        Amm {
+            base_mint: Pubkey::default(),
+            quote_mint: Pubkey::default(),
+            base_reserve: 10,
+            quote_reserve: 20,
+            total_shares: 0,
+            lp_mint: Pubkey::default(),
+            creator: Pubkey::default(),
+            base_reserve_ata: Pubkey::default(),
+            quote_reserve_ata: Pubkey::default(),
+            fee_receiver_ata: Pubkey::default(),
+            fee_config: None,
+            curve: crate::Curve::ConstantProduct,
+            base_price_cumulative_x64: current.base_price_cumulative_x64,
+            quote_price_cumulative_x64: current.quote_price_cumulative_x64,
+            last_price_update_ts: timestamp,
+            liquidity_cumulative: current.liquidity_cumulative,
+            flash_loan: None,
+            paused: 0,
+            liquidity_last: 0,
+            liquidity_last_amp: 0,
+            fee_mode: crate::FeeMode::OnTop,
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer_overwrites_oldest() {
+        let observations = observations(3, &[10, 20, 20, 30, 40, 50]);
+        let timestamps: Vec<i64> = observations.entries.iter().map(|o| o.timestamp).collect();
+        assert_eq!(timestamps, vec![40, 50, 30]);
+        assert_eq!(observations.index, 1);
+    }
+
+    #[test]
+    fn test_grown_buffer_keeps_order() {
+        // The slot after the latest write is reused before the added capacity
+        let mut observations = observations(2, &[10, 20, 30]);
+        observations.capacity = 4;
+        for timestamp in [40, 50, 60] {
+            observations.write(observation(timestamp));
+        }
+        let timestamps: Vec<i64> = observations.entries.iter().map(|o| o.timestamp).collect();
+        assert_eq!(timestamps, vec![30, 40, 50, 60]);
+    }
+
+    #[test]
+    fn test_observe_at_interpolates() {
+        let observations = observations(4, &[10, 20, 40, 50]);
+        let amm = amm_at(70);
+        for timestamp in [10, 15, 20, 33, 50, 60, 70] {
+            assert_eq!(
+                observations.observe_at(&amm, timestamp).unwrap(),
+                observation(timestamp)
+            );
+        }
+        assert!(observations.observe_at(&amm, 9).is_err());
+    }
+
+    fn observations_address(setup: &TestEnvironment) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"observations", setup.amm_account.as_ref()],
+            &setup.program_id,
+        )
+        .0
+    }
+
+    fn initialize_observations_instruction(setup: &TestEnvironment) -> Instruction {
+        Instruction {
+            program_id: setup.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(setup.amm_account, false),
+                AccountMeta::new(observations_address(setup), false),
+                AccountMeta::new(setup.keypair.pubkey(), true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+            data: switchboard_solana::get_ixn_discriminator("initialize_observations").to_vec(),
+        }
+    }
+
+    fn grow_observations_instruction(setup: &TestEnvironment, capacity: u16) -> Instruction {
+        let mut data = switchboard_solana::get_ixn_discriminator("grow_observations").to_vec();
+        data.extend_from_slice(&capacity.to_le_bytes());
+        Instruction {
+            program_id: setup.program_id,
+            accounts: vec![
+                AccountMeta::new(observations_address(setup), false),
+                AccountMeta::new(setup.keypair.pubkey(), true),
+                AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+            ],
+            data,
+        }
+    }
+
+    fn observe_at_instruction(setup: &TestEnvironment, timestamp: i64) -> Instruction {
+        let mut data = switchboard_solana::get_ixn_discriminator("observe_at").to_vec();
+        data.extend_from_slice(&timestamp.to_le_bytes());
+        Instruction {
+            program_id: setup.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(setup.amm_account, false),
+                AccountMeta::new_readonly(observations_address(setup), false),
+            ],
+            data,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_observe_at_history() {
+        let setup = setup_test_environment(true).await;
+
+        let mut tx = Transaction::new_with_payer(
+            &[
+                initialize_observations_instruction(&setup),
+                grow_observations_instruction(&setup, 16),
+            ],
+            Some(&setup.keypair.pubkey()),
+        );
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.send_and_confirm_transaction(&tx).await;
+        assert!(result.is_ok(), "Observations setup should succeed");
+
+        let data = setup
+            .client
+            .get_account_data(&observations_address(&setup))
+            .await
+            .unwrap();
+        let observations =
+            <Observations as anchor_lang::AccountDeserialize>::try_deserialize(&mut &data[..])
+                .unwrap();
+        assert_eq!(observations.capacity, 16);
+        let first = observations.entries[0];
+
+        // The first entry can be observed, anything before it cannot
+        let mut tx = Transaction::new_with_payer(
+            &[observe_at_instruction(&setup, first.timestamp)],
+            Some(&setup.keypair.pubkey()),
+        );
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.simulate_transaction(&tx).await.unwrap();
+        let (data, _) = result.value.return_data.unwrap().data;
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .unwrap();
+        assert_eq!(Observation::deserialize(&mut &data[..]).unwrap(), first);
+
+        let mut tx = Transaction::new_with_payer(
+            &[observe_at_instruction(&setup, first.timestamp - 1)],
+            Some(&setup.keypair.pubkey()),
+        );
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.simulate_transaction(&tx).await.unwrap();
+        assert!(
+            result.value.err.is_some(),
+            "Observing before the first entry should fail"
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use crate::curve::concentrated::{MAX_TICK, MIN_TICK};
+use crate::error::ErrorCode;
+use anchor_lang::prelude::*;
+
+// OpenPositionEvent event
+#[event]
+pub struct OpenPositionEvent {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub timestamp: u64,
+}
+
+/// This module is responsible for opening empty positions on concentrated liquidity pools.
+/// Liquidity is then added with `increase_liquidity`.
+pub mod open_position {
+    use super::*;
+    use crate::OpenPosition;
+
+    /// Opens a position of the signer between two ticks.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the pool, the position to create and its owner.
+    /// - `tick_lower`: Lower bound of the position, a multiple of the pool's tick spacing.
+    /// - `tick_upper`: Upper bound of the position, a multiple of the pool's tick spacing.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<OpenPosition>, tick_lower: i32, tick_upper: i32) -> Result<()> {
+        let tick_spacing = ctx.accounts.pool.tick_spacing as i32;
+        require!(
+            tick_lower < tick_upper
+                && tick_lower >= MIN_TICK
+                && tick_upper <= MAX_TICK
+                && tick_lower % tick_spacing == 0
+                && tick_upper % tick_spacing == 0,
+            ErrorCode::InvalidTickRange
+        );
+
+        let position = &mut ctx.accounts.position;
+        position.pool = ctx.accounts.pool.key();
+        position.owner = ctx.accounts.owner.key();
+        position.tick_lower = tick_lower;
+        position.tick_upper = tick_upper;
+
+        // Emit the event
+        emit_cpi!(OpenPositionEvent {
+            pool: ctx.accounts.pool.key(),
+            position: ctx.accounts.position.key(),
+            owner: ctx.accounts.owner.key(),
+            tick_lower,
+            tick_upper,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(OpenPositionEvent {
+            pool: ctx.accounts.pool.key(),
+            position: ctx.accounts.position.key(),
+            owner: ctx.accounts.owner.key(),
+            tick_lower,
+            tick_upper,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use crate::error::ErrorCode;
+use crate::PAUSE_ALL;
+use anchor_lang::prelude::*;
+
+// PauseEvent event, `amm` is `None` for the global flags
+#[event]
+pub struct PauseEvent {
+    pub amm: Option<Pubkey>,
+    pub paused: u8,
+    pub timestamp: u64,
+}
+
+/// Halts operations on every pool, e.g. while an incident is investigated.
+///
+/// Swaps, deposits and withdrawals are paused separately with the `PAUSE_*` flags, so that
+/// liquidity providers can still withdraw while trading is halted.
+pub mod pause {
+    use crate::SetPause;
+
+    use super::*;
+
+    /// Adds `flags` to the globally paused operations
+    ///
+    /// # Parameters:
+    /// - `ctx`: The context of the AMM.
+    /// - `flags`: The `PAUSE_*` flags of the operations to halt.
+    ///
+    /// # Returns:
+    /// - `Result<()>`: A success indicator if the operations are paused.
+    pub fn handler(ctx: Context<SetPause>, flags: u8) -> Result<()> {
+        validate_pause_flags(flags)?;
+
+        let global_parameters = &mut ctx.accounts.global_parameters;
+        global_parameters.paused |= flags;
+        let paused = global_parameters.paused;
+
+        // Emit the event
+        emit_cpi!(PauseEvent {
+            amm: None,
+            paused,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(PauseEvent {
+            amm: None,
+            paused,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
+
+/// Checks that `flags` names at least one operation and only known `PAUSE_*` flags.
+pub(crate) fn validate_pause_flags(flags: u8) -> Result<()> {
+    require!(
+        flags != 0 && flags & !PAUSE_ALL == 0,
+        ErrorCode::InvalidPauseFlags
+    );
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use crate::instructions::pause::{validate_pause_flags, PauseEvent};
+use anchor_lang::prelude::*;
+
+/// Halts operations on a single AMM, e.g. when one of its mints gets frozen, while the other
+/// pools keep running.
+pub mod pause_pool {
+    use crate::SetPoolPause;
+
+    use super::*;
+
+    /// Adds `flags` to the paused operations of the pool
+    ///
+    /// # Parameters:
+    /// - `ctx`: The context of the AMM.
+    /// - `flags`: The `PAUSE_*` flags of the operations to halt.
+    ///
+    /// # Returns:
+    /// - `Result<()>`: A success indicator if the operations are paused.
+    pub fn handler(ctx: Context<SetPoolPause>, flags: u8) -> Result<()> {
+        validate_pause_flags(flags)?;
+
+        let amm = &mut ctx.accounts.amm;
+        amm.paused |= flags;
+        let paused = amm.paused;
+
+        // Emit the event
+        emit_cpi!(PauseEvent {
+            amm: Some(ctx.accounts.amm.key()),
+            paused,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(PauseEvent {
+            amm: Some(ctx.accounts.amm.key()),
+            paused,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::add_liquidity_instruction;
+    use crate::fixtures::tests::setup_test_environment;
+    use crate::fixtures::tests::TestEnvironment;
+    use crate::PAUSE_DEPOSITS;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+    use std::str::FromStr;
+    use switchboard_solana::Pubkey;
+
+    fn set_pool_pause_instruction(setup: &TestEnvironment, name: &str, flags: u8) -> Instruction {
+        let mut data = switchboard_solana::get_ixn_discriminator(name).to_vec();
+        data.push(flags);
+        Instruction {
+            program_id: setup.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(setup.global_parameters, false),
+                AccountMeta::new_readonly(setup.keypair.pubkey(), true),
+                AccountMeta::new(setup.amm_account, false),
+                // No role account, the test keypair is the admin
+                AccountMeta::new_readonly(setup.program_id, false),
+                AccountMeta::new_readonly(
+                    Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+                    false,
+                ),
+                AccountMeta::new_readonly(setup.program_id, false),
+            ],
+            data,
+        }
+    }
+
+    async fn send(setup: &TestEnvironment, instructions: &[Instruction]) -> bool {
+        let mut tx = Transaction::new_with_payer(instructions, Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        setup.client.send_and_confirm_transaction(&tx).await.is_ok()
+    }
+
+    #[tokio::test]
+    async fn test_pause_pool_deposits() {
+        let setup = setup_test_environment(true).await;
+        let add_liquidity_ix = add_liquidity_instruction(
+            &setup.program_id,
+            &setup.amm_account,
+            &setup.base_mint,
+            &setup.quote_mint,
+            &setup.keypair.pubkey(),
+            &setup.lp_mint,
+            1_000,
+            1_000,
+            0,
+        );
+
+        assert!(
+            send(
+                &setup,
+                &[set_pool_pause_instruction(
+                    &setup,
+                    "pause_pool",
+                    PAUSE_DEPOSITS
+                )]
+            )
+            .await,
+            "Pausing the pool should succeed"
+        );
+        assert!(
+            !send(&setup, std::slice::from_ref(&add_liquidity_ix)).await,
+            "Deposits into a paused pool should fail"
+        );
+
+        assert!(
+            send(
+                &setup,
+                &[set_pool_pause_instruction(
+                    &setup,
+                    "unpause_pool",
+                    PAUSE_DEPOSITS
+                )]
+            )
+            .await,
+            "Unpausing the pool should succeed"
+        );
+        assert!(
+            send(&setup, &[add_liquidity_ix]).await,
+            "Deposits should resume once the pool is unpaused"
+        );
+    }
+}
@@ -2,17 +2,24 @@
 /// It includes functions and structures necessary for processing admin proposal transactions, ensuring that
 /// the proposed admin is correctly recorded and prepared for potential integration into the AMM's administrative controls.
 pub mod propose_admin {
+    use crate::error::ErrorCode;
     use crate::ProposeAdmin;
     use anchor_lang::prelude::*;
 
-    // BuyEvent event
+    /// Seconds a proposed admin has to accept before the proposal expires.
+    pub const ADMIN_PROPOSAL_DURATION: i64 = 7 * 86_400;
+
+    // ProposeAdminEvent event
     #[event]
     pub struct ProposeAdminEvent {
+        pub admin: Pubkey,
         pub proposed_admin: Pubkey,
+        pub expiry: i64,
         pub timestamp: u64,
     }
 
-    /// Proposes a new admin
+    /// Proposes a new admin, who has `ADMIN_PROPOSAL_DURATION` seconds to accept. A new proposal
+    /// replaces the pending one.
     ///
     /// # Parameters:
     /// - `ctx`: Context containing all required accounts for the transaction.
@@ -20,17 +27,26 @@ pub mod propose_admin {
     /// - Result indicating success or an error.
 
     pub fn handler(ctx: Context<ProposeAdmin>) -> Result<()> {
+        let expiry = Clock::get()?
+            .unix_timestamp
+            .checked_add(ADMIN_PROPOSAL_DURATION)
+            .ok_or(ErrorCode::MathOverflow)?;
         let global_parameters = &mut ctx.accounts.global_parameters;
         global_parameters.proposed_admin = ctx.accounts.proposed_admin.key();
+        global_parameters.proposed_admin_expiry = expiry;
 
         // Emit the event
         emit_cpi!(ProposeAdminEvent {
+            admin: *ctx.accounts.admin.to_account_info().key,
             proposed_admin: *ctx.accounts.proposed_admin.to_account_info().key,
+            expiry,
             timestamp: Clock::get()?.unix_timestamp as u64,
         });
 
         emit!(ProposeAdminEvent {
+            admin: *ctx.accounts.admin.to_account_info().key,
             proposed_admin: *ctx.accounts.proposed_admin.to_account_info().key,
+            expiry,
             timestamp: Clock::get()?.unix_timestamp as u64,
         });
 
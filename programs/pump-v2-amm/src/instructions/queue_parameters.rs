@@ -0,0 +1,90 @@
+use crate::error::ErrorCode;
+use crate::{FeeConfig, FeeMode, QueuedParameters};
+use anchor_lang::prelude::*;
+
+/// Longest delay that can be set between queueing and executing a parameter change.
+pub const MAX_TIMELOCK_DELAY: i64 = 30 * 86_400;
+
+// QueueParametersEvent event
+#[event]
+pub struct QueueParametersEvent {
+    pub fee_config: FeeConfig,
+    pub fee_mode: FeeMode,
+    pub flash_loan_fee_bps: u64,
+    pub timelock_delay: i64,
+    pub execute_after: i64,
+    pub timestamp: u64,
+}
+
+/// Queues a change of the AMM's global parameters: the protocol, referrer and LP fees, how the
+/// protocol is paid, the flash loan fee and the timelock delay itself.
+///
+/// The change can only be applied by `execute_parameters` once the current timelock delay has
+/// elapsed, which gives users and integrators time to react to upcoming fee changes. Changing the
+/// delay goes through the current delay too, so it cannot be used to skip it.
+pub mod queue_parameters {
+    use crate::QueueParameters;
+
+    use super::*;
+
+    /// Queues new parameters for the AMM
+    ///
+    /// # Parameters:
+    /// - `ctx`: The context of the AMM.
+    /// - `parameters`: The protocol, referrer and LP fees, whether the protocol fee is charged on
+    ///   top of swaps or taken from the LP fee, the flash loan fee and the delay in seconds applied
+    ///   to the parameter changes queued next.
+    ///
+    /// # Returns:
+    /// - `Result<()>`: A success indicator if the parameters are queued successfully.
+    pub fn handler(ctx: Context<QueueParameters>, parameters: QueuedParameters) -> Result<()> {
+        let QueuedParameters {
+            fee_config,
+            fee_mode,
+            flash_loan_fee_bps,
+            timelock_delay,
+        } = parameters;
+        fee_config.validate()?;
+        fee_mode.validate()?;
+        require!(
+            flash_loan_fee_bps < 10000,
+            ErrorCode::InvalidFeeConfiguration
+        );
+        require!(
+            (0..=MAX_TIMELOCK_DELAY).contains(&timelock_delay),
+            ErrorCode::InvalidTimelockDelay
+        );
+
+        let execute_after = Clock::get()?
+            .unix_timestamp
+            .checked_add(ctx.accounts.global_parameters.timelock_delay)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.fee_config = fee_config;
+        proposal.fee_mode = fee_mode;
+        proposal.flash_loan_fee_bps = flash_loan_fee_bps;
+        proposal.timelock_delay = timelock_delay;
+        proposal.execute_after = execute_after;
+
+        // Emit the event
+        emit_cpi!(QueueParametersEvent {
+            fee_config,
+            fee_mode,
+            flash_loan_fee_bps,
+            timelock_delay,
+            execute_after,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(QueueParametersEvent {
+            fee_config,
+            fee_mode,
+            flash_loan_fee_bps,
+            timelock_delay,
+            execute_after,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
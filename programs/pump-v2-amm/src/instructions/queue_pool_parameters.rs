@@ -0,0 +1,62 @@
+use crate::error::ErrorCode;
+use crate::FeeConfig;
+use anchor_lang::prelude::*;
+
+// QueuePoolParametersEvent event
+#[event]
+pub struct QueuePoolParametersEvent {
+    pub amm: Pubkey,
+    pub fee_config: Option<FeeConfig>,
+    pub execute_after: i64,
+    pub timestamp: u64,
+}
+
+/// Queues a change of the fees charged by a single AMM, for pools that need different fees than
+/// the global parameters. Clearing the override makes the pool fall back to the global fees again.
+///
+/// The change can only be applied by `execute_pool_parameters` once the global timelock delay has
+/// elapsed, as for `queue_parameters`, so that a pool's fees cannot be raised ahead of a swap.
+pub mod queue_pool_parameters {
+    use crate::QueuePoolParameters;
+
+    use super::*;
+
+    /// Queues a new fee configuration for a pool
+    ///
+    /// # Parameters:
+    /// - `ctx`: The context of the AMM.
+    /// - `fee_config`: The pool's fees, or `None` to use the global parameters.
+    ///
+    /// # Returns:
+    /// - `Result<()>`: A success indicator if the parameters are queued successfully.
+    pub fn handler(ctx: Context<QueuePoolParameters>, fee_config: Option<FeeConfig>) -> Result<()> {
+        if let Some(fee_config) = fee_config {
+            fee_config.validate()?;
+        }
+
+        let execute_after = Clock::get()?
+            .unix_timestamp
+            .checked_add(ctx.accounts.global_parameters.timelock_delay)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.fee_config = fee_config;
+        proposal.execute_after = execute_after;
+
+        // Emit the event
+        emit_cpi!(QueuePoolParametersEvent {
+            amm: ctx.accounts.amm.key(),
+            fee_config,
+            execute_after,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(QueuePoolParametersEvent {
+            amm: ctx.accounts.amm.key(),
+            fee_config,
+            execute_after,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use crate::curve::stable::{MAX_AMP, MIN_AMP};
+use crate::error::ErrorCode;
+use crate::{AmpRamp, Curve};
+use anchor_lang::prelude::*;
+
+/// Shortest time over which the amplification coefficient can be changed.
+pub const MIN_RAMP_DURATION: i64 = 86_400;
+/// Largest factor by which a single ramp can change the amplification coefficient.
+pub const MAX_AMP_CHANGE: u64 = 10;
+
+// RampAmpEvent event
+#[event]
+pub struct RampAmpEvent {
+    pub amm: Pubkey,
+    pub initial_amp: u64,
+    pub target_amp: u64,
+    pub ramp_start_ts: i64,
+    pub ramp_stop_ts: i64,
+    pub timestamp: u64,
+}
+
+/// Moves the amplification coefficient of a stable pool to a new value over time. Changing it
+/// at once would shift the pool price in a single block and hand the difference to arbitrageurs.
+pub mod ramp_amp {
+    use crate::RampAmp;
+
+    use super::*;
+
+    /// Starts ramping the amplification coefficient of a stable pool from its current value
+    ///
+    /// # Parameters:
+    /// - `ctx`: The context of the AMM.
+    /// - `target_amp`: The amplification coefficient to reach.
+    /// - `ramp_stop_ts`: Unix timestamp at which `target_amp` is reached.
+    ///
+    /// # Returns:
+    /// - `Result<()>`: A success indicator if the ramp is started successfully.
+    pub fn handler(ctx: Context<RampAmp>, target_amp: u64, ramp_stop_ts: i64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let amm = &mut ctx.accounts.amm;
+        let Curve::Stable(ramp) = amm.curve else {
+            return err!(ErrorCode::NotStablePool);
+        };
+
+        // The ramp starts from wherever a previous ramp has got to
+        let initial_amp = ramp.amp(now);
+        require!(
+            (MIN_AMP..=MAX_AMP).contains(&target_amp),
+            ErrorCode::InvalidAmplification
+        );
+        require!(
+            ramp_stop_ts >= now + MIN_RAMP_DURATION
+                && target_amp <= initial_amp * MAX_AMP_CHANGE
+                && initial_amp <= target_amp * MAX_AMP_CHANGE,
+            ErrorCode::InvalidAmpRamp
+        );
+
+        let ramp = AmpRamp {
+            initial_amp,
+            target_amp,
+            ramp_start_ts: now,
+            ramp_stop_ts,
+        };
+        amm.curve = Curve::Stable(ramp);
+
+        // Emit the event
+        emit_cpi!(RampAmpEvent {
+            amm: ctx.accounts.amm.key(),
+            initial_amp,
+            target_amp,
+            ramp_start_ts: now,
+            ramp_stop_ts,
+            timestamp: now as u64,
+        });
+
+        emit!(RampAmpEvent {
+            amm: ctx.accounts.amm.key(),
+            initial_amp,
+            target_amp,
+            ramp_start_ts: now,
+            ramp_stop_ts,
+            timestamp: now as u64,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::{lp_share_global_parameters, stable_amm};
+    use crate::AmpRamp;
+
+    #[test]
+    fn test_amp_ramp_accrues_no_protocol_fee() {
+        let global_parameters = lp_share_global_parameters();
+        let ramp = AmpRamp {
+            initial_amp: 100,
+            target_amp: 1_000,
+            ramp_start_ts: 0,
+            ramp_stop_ts: 86_400,
+        };
+        let mut amm = stable_amm(1_000_000, 3_000_000, 1_000_000, ramp);
+        amm.update_liquidity_last(&global_parameters, 0).unwrap();
+
+        // The ramp alone raises the liquidity of the same reserves
+        let liquidity = amm
+            .invariant(86_400)
+            .liquidity(amm.base_reserve, amm.quote_reserve)
+            .unwrap();
+        assert!(liquidity > amm.liquidity_last);
+
+        // Without any swap there is no fee for the protocol
+        assert_eq!(amm.pending_protocol_fee_shares().unwrap(), 0);
+        assert_eq!(amm.accrue_protocol_fee().unwrap(), 0);
+        assert_eq!(amm.total_shares, 1_000_000);
+    }
+}
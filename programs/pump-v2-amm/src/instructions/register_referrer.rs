@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+// RegisterReferrerEvent event
+#[event]
+pub struct RegisterReferrerEvent {
+    pub owner: Pubkey,
+    pub referrer: Pubkey,
+    pub timestamp: u64,
+}
+
+/// Registers the signer as a referrer.
+///
+/// Swaps only pay referrers registered here: they pass the referrer's quote token account and
+/// its `Referrer` account, which keeps the lifetime volume and fees of the referred swaps.
+pub mod register_referrer {
+    use crate::RegisterReferrer;
+
+    use super::*;
+
+    /// Creates the `Referrer` account of the owner
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the owner and its new `Referrer` account.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<RegisterReferrer>) -> Result<()> {
+        let referrer = &mut ctx.accounts.referrer;
+        referrer.owner = ctx.accounts.owner.key();
+        referrer.fee_tier = None;
+
+        // Emit the event
+        emit_cpi!(RegisterReferrerEvent {
+            owner: ctx.accounts.owner.key(),
+            referrer: ctx.accounts.referrer.key(),
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(RegisterReferrerEvent {
+            owner: ctx.accounts.owner.key(),
+            referrer: ctx.accounts.referrer.key(),
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
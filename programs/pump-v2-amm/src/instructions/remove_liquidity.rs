@@ -1,42 +1,26 @@
 use crate::error::ErrorCode;
-use crate::utils::{spl_token_transfer, TokenTransferParams};
+use crate::utils::{
+    amount_after_transfer_fee, check_reserve_balances, spl_token_transfer, TokenTransferParams,
+};
 use anchor_lang::prelude::*;
 
-// RemoveLiquidityEvent event
+// RemoveLiquidityEvent event, amounts are the ones received by the user after transfer fees
 #[event]
 pub struct RemoveLiquidityEvent {
     pub base_amount: u64,
     pub quote_amount: u64,
     pub shares: u64,
+    pub protocol_fee_shares: u64,
     pub timestamp: u64,
     pub user: Pubkey,
 }
 
-/// Helper function to calculate the amount of base and quote tokens proportional to the shares being removed.
-///
-/// # Parameters:
-/// - `shares`: Number of liquidity shares being removed.
-/// - `amm`: Reference to the current state of the AMM.
-///
-/// # Returns:
-/// - Result containing the tuple of base and quote tokens calculated or an error.
-fn remove_quote(
-    shares: u64,
-    base_reserve: u64,
-    quote_reserve: u64,
-    total_shares: u64,
-) -> Result<(u64, u64)> {
-    let base_amount = (shares * base_reserve) / total_shares;
-    let quote_amount = (shares * quote_reserve) / total_shares;
-    Ok((base_amount, quote_amount))
-}
-
 /// The `remove_liquidity` module is responsible for handling the removal of liquidity from the AMM.
 /// This includes calculating the amounts of base and quote tokens to be returned for the shares being removed,
 /// ensuring the returned amounts meet the user's expectations, and updating the AMM's reserves accordingly.
 pub mod remove_liquidity {
     use super::*;
-    use crate::RemoveLiquidity;
+    use crate::{RemoveLiquidity, PAUSE_WITHDRAWALS};
 
     /// Handles the removal of liquidity by a user, ensuring minimum amounts are respected.
     ///
@@ -54,32 +38,60 @@ pub mod remove_liquidity {
         quote_min_amount: u64,
         base_min_amount: u64,
     ) -> Result<()> {
+        ctx.accounts
+            .amm
+            .require_not_paused(&ctx.accounts.global_parameters, PAUSE_WITHDRAWALS)?;
+
         let base_mint = &ctx.accounts.base_mint;
         let quote_mint = &ctx.accounts.quote_mint;
         let user_lp_ata = &mut ctx.accounts.user_lp_ata;
         let base_reserve_ata = &mut ctx.accounts.base_reserve_ata;
         let quote_reserve_ata = &mut ctx.accounts.quote_reserve_ata;
 
+        // Pay the protocol its share of the fees accrued since the last liquidity change
+        let now = Clock::get()?.unix_timestamp;
+        let protocol_fee_shares = ctx.accounts.amm.accrue_protocol_fee()?;
+
         // Calculate the amount of base and quote tokens to be returned for the shares
-        let (base_amount, quote_amount) = remove_quote(
+        let (base_amount, quote_amount) = ctx.accounts.amm.invariant(now).remove_quote(
             shares,
             ctx.accounts.amm.base_reserve,
             ctx.accounts.amm.quote_reserve,
             ctx.accounts.amm.total_shares,
         )?;
 
-        // Ensure the returned amounts meet the user's expectations
+        // The user receives the withdrawn amounts minus any Token-2022 transfer fees
+        let base_received_amount =
+            amount_after_transfer_fee(&base_mint.to_account_info(), base_amount)?;
+        let quote_received_amount =
+            amount_after_transfer_fee(&quote_mint.to_account_info(), quote_amount)?;
+
+        // Ensure the received amounts meet the user's expectations
         require!(
-            quote_amount >= quote_min_amount,
+            quote_received_amount >= quote_min_amount,
             ErrorCode::QuoteAmountTooLow
         );
-        require!(base_amount >= base_min_amount, ErrorCode::BaseAmountTooLow);
+        require!(
+            base_received_amount >= base_min_amount,
+            ErrorCode::BaseAmountTooLow
+        );
 
         // Update the AMM's reserves by subtracting the amounts to be removed
         let amm = &mut ctx.accounts.amm;
-        amm.base_reserve -= base_amount;
-        amm.quote_reserve -= quote_amount;
-        amm.total_shares -= shares;
+        amm.update_price_accumulators(now);
+        amm.base_reserve = amm
+            .base_reserve
+            .checked_sub(base_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        amm.quote_reserve = amm
+            .quote_reserve
+            .checked_sub(quote_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        amm.total_shares = amm
+            .total_shares
+            .checked_sub(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+        amm.update_liquidity_last(&ctx.accounts.global_parameters, now)?;
 
         // Define seeds for signing operations that require the AMM's authority
         let signer_seeds = [
@@ -90,15 +102,36 @@ pub mod remove_liquidity {
             &[ctx.bumps.amm],
         ];
 
+        // Mint the protocol's share of the accrued fees to the treasury
+        if protocol_fee_shares > 0 {
+            let treasury_lp_ata = ctx
+                .accounts
+                .treasury_lp_ata
+                .as_ref()
+                .ok_or(ErrorCode::MissingTreasuryLpAccount)?;
+            anchor_spl::token_interface::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.lp_token_program.to_account_info(),
+                    anchor_spl::token_interface::MintTo {
+                        mint: ctx.accounts.lp_mint.to_account_info(),
+                        to: treasury_lp_ata.to_account_info(),
+                        authority: ctx.accounts.amm.to_account_info(),
+                    },
+                    &[&signer_seeds],
+                ),
+                protocol_fee_shares,
+            )?;
+        }
+
         // Transfer the base tokens from the AMM's reserves to the user's account
         let base_token_program = &ctx.accounts.base_token_program.to_account_info();
         let quote_token_program = &ctx.accounts.quote_token_program.to_account_info();
 
         // Burn the LP shares to reflect the removal of liquidity
-        anchor_spl::token::burn(
+        anchor_spl::token_interface::burn(
             CpiContext::new(
-                ctx.accounts.base_token_program.to_account_info(),
-                anchor_spl::token::Burn {
+                ctx.accounts.lp_token_program.to_account_info(),
+                anchor_spl::token_interface::Burn {
                     mint: ctx.accounts.lp_mint.to_account_info(),
                     from: user_lp_ata.to_account_info(),
                     authority: ctx.accounts.user.to_account_info(),
@@ -130,19 +163,28 @@ pub mod remove_liquidity {
             token_program: quote_token_program.clone(),
         })?;
 
+        // Make sure the vaults still back the recorded reserves
+        check_reserve_balances(
+            &ctx.accounts.amm,
+            &mut ctx.accounts.base_reserve_ata,
+            &mut ctx.accounts.quote_reserve_ata,
+        )?;
+
         // Emit the event
         emit_cpi!(RemoveLiquidityEvent {
-            base_amount,
-            quote_amount,
+            base_amount: base_received_amount,
+            quote_amount: quote_received_amount,
             shares,
+            protocol_fee_shares,
             timestamp: Clock::get()?.unix_timestamp as u64,
             user: *ctx.accounts.user.to_account_info().key,
         });
 
         emit!(RemoveLiquidityEvent {
-            base_amount,
-            quote_amount,
+            base_amount: base_received_amount,
+            quote_amount: quote_received_amount,
             shares,
+            protocol_fee_shares,
             timestamp: Clock::get()?.unix_timestamp as u64,
             user: *ctx.accounts.user.to_account_info().key,
         });
@@ -154,19 +196,11 @@ pub mod remove_liquidity {
 #[cfg(test)]
 mod tests {
     use crate::fixtures::tests::fetch_reserves;
-    use crate::fixtures::tests::setup_amm;
-    use crate::fixtures::tests::setup_mints_and_accounts;
     use crate::fixtures::tests::setup_test_environment;
-    use crate::fixtures::tests::setup_user_accounts;
     use anchor_lang::AccountDeserialize;
-    use solana_client::nonblocking::rpc_client::RpcClient;
-    use solana_sdk::{
-        signature::{Keypair, Signer},
-        transaction::Transaction,
-    };
+    use solana_sdk::{signature::Signer, transaction::Transaction};
     use solana_transaction_status::option_serializer::OptionSerializer;
     use std::str::FromStr;
-    use std::sync::Arc;
     use switchboard_solana::Pubkey;
 
     fn remove_liquidity_instruction(
@@ -187,6 +221,10 @@ mod tests {
         // Construct the accounts required for the add_liquidity_instruction
         let accounts = vec![
             solana_sdk::instruction::AccountMeta::new(*amm_pubkey, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(
+                Pubkey::find_program_address(&[b"global_parameters"], program_id).0,
+                false,
+            ),
             solana_sdk::instruction::AccountMeta::new_readonly(*payer_pubkey, true),
             solana_sdk::instruction::AccountMeta::new(
                 spl_associated_token_account::get_associated_token_address_with_program_id(
@@ -233,10 +271,13 @@ mod tests {
             solana_sdk::instruction::AccountMeta::new(*quote_pubkey, false),
             solana_sdk::instruction::AccountMeta::new_readonly(spl_token_2022::ID, false),
             solana_sdk::instruction::AccountMeta::new_readonly(spl_token::ID, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(spl_token::ID, false),
             solana_sdk::instruction::AccountMeta::new_readonly(
                 solana_program::system_program::ID,
                 false,
             ),
+            // No treasury LP account, fees are charged on top
+            solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
             solana_sdk::instruction::AccountMeta::new_readonly(
                 Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
                 false,
@@ -280,7 +321,7 @@ mod tests {
             "Remove liquidity transaction should succeed"
         );
     }
-    use crate::instructions::remove_liquidity::remove_quote;
+    use crate::curve::remove_quote;
 
     #[tokio::test]
     async fn test_events_on_remove_liquidity_success() {
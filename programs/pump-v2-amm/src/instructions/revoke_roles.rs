@@ -0,0 +1,51 @@
+use crate::instructions::grant_roles::validate_roles;
+use anchor_lang::prelude::*;
+
+// RevokeRolesEvent event, `member_roles` are the roles left once the revocation is applied
+#[event]
+pub struct RevokeRolesEvent {
+    pub member: Pubkey,
+    pub roles: u8,
+    pub member_roles: u8,
+    pub timestamp: u64,
+}
+
+/// Lets the admin take back roles granted with `grant_roles`.
+pub mod revoke_roles {
+    use crate::RevokeRoles;
+
+    use super::*;
+
+    /// Revokes `roles` from the member of the `Role` account
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the member's `Role` account.
+    /// - `roles`: The `ROLE_*` flags to revoke.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<RevokeRoles>, roles: u8) -> Result<()> {
+        validate_roles(roles)?;
+
+        let role = &mut ctx.accounts.role;
+        role.roles &= !roles;
+        let member_roles = role.roles;
+
+        // Emit the event
+        emit_cpi!(RevokeRolesEvent {
+            member: ctx.accounts.role.member,
+            roles,
+            member_roles,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(RevokeRolesEvent {
+            member: ctx.accounts.role.member,
+            roles,
+            member_roles,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
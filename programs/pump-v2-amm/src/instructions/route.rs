@@ -0,0 +1,617 @@
+use crate::curve::{self, Rounding};
+use crate::error::ErrorCode;
+use crate::instructions::buy::BuyEvent;
+use crate::instructions::buy_exact_in::quote_buy_exact_in;
+use crate::instructions::sell::{quote_sell, SellEvent};
+use crate::utils::{check_reserve_balances, spl_token_transfer, TokenTransferParams};
+use crate::{Amm, Observations, Route, SwapDirection, PAUSE_SWAPS};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+/// Number of remaining accounts taken by each hop of a route.
+pub const ROUTE_HOP_ACCOUNTS: usize = 11;
+
+// RouteEvent event
+#[event]
+pub struct RouteEvent {
+    pub user: Pubkey,
+    pub pools: Vec<Pubkey>,
+    pub directions: Vec<SwapDirection>,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub timestamp: u64,
+}
+
+/// The accounts of a single hop, passed as remaining accounts in this order:
+/// amm, base reserve, quote reserve, fee receiver, base mint, quote mint, base token program,
+/// quote token program, user base account, user quote account, observations. The program id
+/// stands in for the observations of a pool that records none.
+struct Hop<'info> {
+    amm: Account<'info, Amm>,
+    amm_bump: u8,
+    base_reserve_ata: InterfaceAccount<'info, TokenAccount>,
+    quote_reserve_ata: InterfaceAccount<'info, TokenAccount>,
+    fee_receiver_ata: AccountInfo<'info>,
+    base_mint: InterfaceAccount<'info, Mint>,
+    quote_mint: InterfaceAccount<'info, Mint>,
+    base_token_program: Interface<'info, TokenInterface>,
+    quote_token_program: Interface<'info, TokenInterface>,
+    user_base_ata: InterfaceAccount<'info, TokenAccount>,
+    user_quote_ata: InterfaceAccount<'info, TokenAccount>,
+    observations: Option<Account<'info, Observations>>,
+}
+
+impl<'info> Hop<'info> {
+    /// Loads the accounts of a hop and checks them against the pool, the same way the `Buy` and
+    /// `Sell` account constraints do.
+    fn load(accounts: &'info [AccountInfo<'info>], user: &Pubkey) -> Result<Self> {
+        let amm = Account::<Amm>::try_from(&accounts[0])?;
+        let base_mint = InterfaceAccount::<Mint>::try_from(&accounts[4])?;
+        let quote_mint = InterfaceAccount::<Mint>::try_from(&accounts[5])?;
+        let (amm_key, amm_bump) = Pubkey::find_program_address(
+            &[
+                b"amm",
+                amm.creator.as_ref(),
+                base_mint.key().as_ref(),
+                quote_mint.key().as_ref(),
+            ],
+            &crate::ID,
+        );
+        require_keys_eq!(amm_key, amm.key(), ErrorCode::InvalidRouteAccounts);
+        let observations = if accounts[10].key() == crate::ID {
+            None
+        } else {
+            let observations = Account::<Observations>::try_from(&accounts[10])?;
+            require_keys_eq!(observations.amm, amm.key(), ErrorCode::InvalidRouteAccounts);
+            Some(observations)
+        };
+
+        let hop = Hop {
+            amm_bump,
+            base_reserve_ata: InterfaceAccount::try_from(&accounts[1])?,
+            quote_reserve_ata: InterfaceAccount::try_from(&accounts[2])?,
+            fee_receiver_ata: accounts[3].clone(),
+            base_token_program: Interface::try_from(&accounts[6])?,
+            quote_token_program: Interface::try_from(&accounts[7])?,
+            user_base_ata: InterfaceAccount::try_from(&accounts[8])?,
+            user_quote_ata: InterfaceAccount::try_from(&accounts[9])?,
+            observations,
+            amm,
+            base_mint,
+            quote_mint,
+        };
+
+        require_keys_eq!(
+            hop.base_reserve_ata.key(),
+            hop.amm.base_reserve_ata,
+            ErrorCode::InvalidRouteAccounts
+        );
+        require_keys_eq!(
+            hop.quote_reserve_ata.key(),
+            hop.amm.quote_reserve_ata,
+            ErrorCode::InvalidRouteAccounts
+        );
+        require_keys_eq!(
+            hop.fee_receiver_ata.key(),
+            hop.amm.fee_receiver_ata,
+            ErrorCode::InvalidRouteAccounts
+        );
+        require_keys_eq!(
+            *hop.base_mint.to_account_info().owner,
+            hop.base_token_program.key(),
+            ErrorCode::InvalidRouteAccounts
+        );
+        require_keys_eq!(
+            *hop.quote_mint.to_account_info().owner,
+            hop.quote_token_program.key(),
+            ErrorCode::InvalidRouteAccounts
+        );
+        require_keys_eq!(
+            hop.user_base_ata.owner,
+            *user,
+            ErrorCode::InvalidRouteAccounts
+        );
+        require_keys_eq!(
+            hop.user_base_ata.mint,
+            hop.base_mint.key(),
+            ErrorCode::InvalidRouteAccounts
+        );
+        require_keys_eq!(
+            hop.user_quote_ata.owner,
+            *user,
+            ErrorCode::InvalidRouteAccounts
+        );
+        require_keys_eq!(
+            hop.user_quote_ata.mint,
+            hop.quote_mint.key(),
+            ErrorCode::InvalidRouteAccounts
+        );
+
+        Ok(hop)
+    }
+
+    /// The user account the hop spends from.
+    fn input_ata(&self, direction: SwapDirection) -> Pubkey {
+        match direction {
+            SwapDirection::Buy => self.user_quote_ata.key(),
+            SwapDirection::Sell => self.user_base_ata.key(),
+        }
+    }
+
+    /// The user account the hop pays out to.
+    fn output_ata(&self, direction: SwapDirection) -> Pubkey {
+        match direction {
+            SwapDirection::Buy => self.user_base_ata.key(),
+            SwapDirection::Sell => self.user_quote_ata.key(),
+        }
+    }
+}
+
+// This module is responsible for routing a swap through several pools in a single instruction.
+pub mod route {
+    use super::*;
+
+    /// Swaps `amount_in` through an ordered list of pools, feeding the output of each hop into the next.
+    ///
+    /// Every hop is an exact-in `sell` or `buy_exact_in` on its pool. The protocol fee of each hop
+    /// comes out of the amount routed through it and no referrer is paid, so the user never spends
+    /// more than `amount_in` of the first token. Only the final output is checked for slippage.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the global parameters, the user and the accounts of every hop
+    ///   as remaining accounts, `ROUTE_HOP_ACCOUNTS` per hop.
+    /// - `amount_in`: The amount of tokens spent on the first hop.
+    /// - `min_amount_out`: The minimum amount of tokens the user expects from the last hop.
+    /// - `directions`: The side traded on each pool, one per hop.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler<'a>(
+        ctx: Context<'_, '_, 'a, 'a, Route<'a>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        directions: Vec<SwapDirection>,
+    ) -> Result<()> {
+        require!(!directions.is_empty(), ErrorCode::InvalidRoute);
+        require_eq!(
+            ctx.remaining_accounts.len(),
+            directions.len() * ROUTE_HOP_ACCOUNTS,
+            ErrorCode::InvalidRouteAccounts
+        );
+
+        let mut amount = amount_in;
+        let mut pools = Vec::with_capacity(directions.len());
+        let mut previous_output_ata = None;
+        for (accounts, direction) in ctx
+            .remaining_accounts
+            .chunks(ROUTE_HOP_ACCOUNTS)
+            .zip(directions.iter().copied())
+        {
+            let mut hop = Hop::load(accounts, ctx.accounts.user.key)?;
+
+            // Each hop has to spend the tokens paid out by the previous one
+            if let Some(previous_output_ata) = previous_output_ata {
+                require_keys_eq!(
+                    hop.input_ata(direction),
+                    previous_output_ata,
+                    ErrorCode::InvalidRoute
+                );
+            }
+
+            amount = swap_hop(&ctx, &mut hop, direction, amount)?;
+            previous_output_ata = Some(hop.output_ata(direction));
+            pools.push(hop.amm.key());
+        }
+
+        // Ensure the route as a whole pays out what the user expects
+        require_gte!(amount, min_amount_out, ErrorCode::RouteOutputTooLow);
+
+        // Emit the event
+        emit_cpi!(RouteEvent {
+            user: *ctx.accounts.user.key,
+            pools: pools.clone(),
+            directions: directions.clone(),
+            amount_in,
+            amount_out: amount,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(RouteEvent {
+            user: *ctx.accounts.user.key,
+            pools,
+            directions,
+            amount_in,
+            amount_out: amount,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
+
+/// Executes one hop of a route spending `amount_in`, emits its `BuyEvent` or `SellEvent` and
+/// returns the amount the user ends up with on the other side, after the protocol fee.
+fn swap_hop<'a>(
+    ctx: &Context<'_, '_, '_, 'a, Route<'a>>,
+    hop: &mut Hop<'a>,
+    direction: SwapDirection,
+    amount_in: u64,
+) -> Result<u64> {
+    let fees = hop.amm.fee_config(&ctx.accounts.global_parameters);
+    let base_mint = hop.base_mint.to_account_info();
+    let quote_mint = hop.quote_mint.to_account_info();
+    let user = ctx.accounts.user.to_account_info();
+
+    // Define seeds for signing transactions involving the AMM
+    let amm_creator = hop.amm.creator;
+    let signer_seeds = [
+        b"amm",
+        amm_creator.as_ref(),
+        base_mint.key.as_ref(),
+        quote_mint.key.as_ref(),
+        &[hop.amm_bump],
+    ];
+
+    hop.amm
+        .require_not_paused(&ctx.accounts.global_parameters, PAUSE_SWAPS)?;
+    require!(hop.amm.flash_loan.is_none(), ErrorCode::FlashLoanInProgress);
+
+    // Accumulate the pool price up to this hop before it moves
+    let now = Clock::get()?.unix_timestamp;
+    hop.amm.update_price_accumulators(now);
+    if let Some(observations) = hop.observations.as_mut() {
+        observations.write(hop.amm.observe(now));
+    }
+
+    let amount_out = match direction {
+        SwapDirection::Buy => {
+            // The protocol fee comes out of the quote amount routed into the hop
+            let protocol_fee_amount =
+                curve::apply_bps(amount_in, fees.protocol_fee_bps, Rounding::Down)?;
+            let amounts = quote_buy_exact_in(
+                &hop.amm,
+                &fees,
+                &base_mint,
+                &quote_mint,
+                amount_in - protocol_fee_amount,
+            )?;
+            require_gt!(amounts.base_amount, 0, ErrorCode::ZeroOutputForBuy);
+
+            hop.amm.base_reserve = hop
+                .amm
+                .base_reserve
+                .checked_sub(amounts.base_reserve_amount)
+                .ok_or(ErrorCode::MathOverflow)?;
+            hop.amm.quote_reserve = hop
+                .amm
+                .quote_reserve
+                .checked_add(amounts.quote_reserve_amount)
+                .ok_or(ErrorCode::MathOverflow)?;
+
+            // Transfer the protocol fee to the fee receiver
+            spl_token_transfer(TokenTransferParams {
+                source: hop.user_quote_ata.to_account_info(),
+                destination: hop.fee_receiver_ata.clone(),
+                amount: protocol_fee_amount,
+                authority: user.clone(),
+                authority_signer_seeds: &[],
+                decimals: hop.quote_mint.decimals,
+                mint: quote_mint.clone(),
+                token_program: hop.quote_token_program.to_account_info(),
+            })?;
+
+            // Transfer the quote amount to the AMM reserves
+            spl_token_transfer(TokenTransferParams {
+                source: hop.user_quote_ata.to_account_info(),
+                destination: hop.quote_reserve_ata.to_account_info(),
+                amount: amounts.quote_amount,
+                authority: user.clone(),
+                authority_signer_seeds: &[],
+                decimals: hop.quote_mint.decimals,
+                mint: quote_mint.clone(),
+                token_program: hop.quote_token_program.to_account_info(),
+            })?;
+
+            // Transfer the purchased base amount to the user
+            spl_token_transfer(TokenTransferParams {
+                source: hop.base_reserve_ata.to_account_info(),
+                destination: hop.user_base_ata.to_account_info(),
+                amount: amounts.base_reserve_amount,
+                authority: hop.amm.to_account_info(),
+                authority_signer_seeds: &signer_seeds,
+                decimals: hop.base_mint.decimals,
+                mint: base_mint.clone(),
+                token_program: hop.base_token_program.to_account_info(),
+            })?;
+
+            emit_cpi!(BuyEvent {
+                base_amount: amounts.base_amount,
+                quote_amount: amounts.quote_amount,
+                user: *user.key,
+                timestamp: Clock::get()?.unix_timestamp as u64,
+                referrer: None,
+                referrer_fee_amount: None,
+                protocol_fee_amount,
+                lp_fee_amount: amounts.lp_fee_amount,
+            });
+
+            emit!(BuyEvent {
+                base_amount: amounts.base_amount,
+                quote_amount: amounts.quote_amount,
+                user: *user.key,
+                timestamp: Clock::get()?.unix_timestamp as u64,
+                referrer: None,
+                referrer_fee_amount: None,
+                protocol_fee_amount,
+                lp_fee_amount: amounts.lp_fee_amount,
+            });
+
+            amounts.base_amount
+        }
+        SwapDirection::Sell => {
+            let amounts = quote_sell(&hop.amm, &fees, &base_mint, &quote_mint, amount_in)?;
+            require_gt!(amounts.quote_amount, 0, ErrorCode::ZeroOutputForSell);
+
+            hop.amm.base_reserve = hop
+                .amm
+                .base_reserve
+                .checked_add(amounts.base_reserve_amount)
+                .ok_or(ErrorCode::MathOverflow)?;
+            hop.amm.quote_reserve = hop
+                .amm
+                .quote_reserve
+                .checked_sub(amounts.quote_reserve_amount)
+                .ok_or(ErrorCode::MathOverflow)?;
+
+            // Transfer base amount from user to base reserve
+            spl_token_transfer(TokenTransferParams {
+                source: hop.user_base_ata.to_account_info(),
+                destination: hop.base_reserve_ata.to_account_info(),
+                amount: amounts.base_amount,
+                authority: user.clone(),
+                authority_signer_seeds: &[],
+                decimals: hop.base_mint.decimals,
+                mint: base_mint.clone(),
+                token_program: hop.base_token_program.to_account_info(),
+            })?;
+
+            // Transfer quote amount from quote reserve to user
+            spl_token_transfer(TokenTransferParams {
+                source: hop.quote_reserve_ata.to_account_info(),
+                destination: hop.user_quote_ata.to_account_info(),
+                amount: amounts.quote_reserve_amount,
+                authority: hop.amm.to_account_info(),
+                authority_signer_seeds: &signer_seeds,
+                decimals: hop.quote_mint.decimals,
+                mint: quote_mint.clone(),
+                token_program: hop.quote_token_program.to_account_info(),
+            })?;
+
+            // Transfer the protocol fee to the fee receiver out of the quote amount received
+            let protocol_fee_amount =
+                curve::apply_bps(amounts.quote_amount, fees.protocol_fee_bps, Rounding::Down)?;
+            spl_token_transfer(TokenTransferParams {
+                source: hop.user_quote_ata.to_account_info(),
+                destination: hop.fee_receiver_ata.clone(),
+                amount: protocol_fee_amount,
+                authority: user.clone(),
+                authority_signer_seeds: &[],
+                decimals: hop.quote_mint.decimals,
+                mint: quote_mint.clone(),
+                token_program: hop.quote_token_program.to_account_info(),
+            })?;
+
+            emit_cpi!(SellEvent {
+                base_amount: amounts.base_amount,
+                quote_amount: amounts.quote_amount,
+                user: *user.key,
+                timestamp: Clock::get()?.unix_timestamp as u64,
+                referrer: None,
+                referrer_fee_amount: None,
+                protocol_fee_amount,
+                lp_fee_amount: amounts.lp_fee_amount,
+            });
+
+            emit!(SellEvent {
+                base_amount: amounts.base_amount,
+                quote_amount: amounts.quote_amount,
+                user: *user.key,
+                timestamp: Clock::get()?.unix_timestamp as u64,
+                referrer: None,
+                referrer_fee_amount: None,
+                protocol_fee_amount,
+                lp_fee_amount: amounts.lp_fee_amount,
+            });
+
+            amounts.quote_amount - protocol_fee_amount
+        }
+    };
+
+    // Persist the reserves and observations so a later hop through the same pool sees them
+    hop.amm.exit(&crate::ID)?;
+    if let Some(observations) = &hop.observations {
+        observations.exit(&crate::ID)?;
+    }
+
+    // Make sure the vaults still back the recorded reserves
+    check_reserve_balances(
+        &hop.amm,
+        &mut hop.base_reserve_ata,
+        &mut hop.quote_reserve_ata,
+    )?;
+
+    Ok(amount_out)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::fetch_reserves;
+    use crate::fixtures::tests::setup_test_environment;
+    use crate::fixtures::tests::TestEnvironment;
+    use crate::Pubkey;
+    use crate::SwapDirection;
+    use anchor_lang::AnchorSerialize;
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+    use std::str::FromStr;
+
+    fn route_instruction(
+        setup: &TestEnvironment,
+        amount_in: u64,
+        min_amount_out: u64,
+        directions: Vec<SwapDirection>,
+    ) -> solana_sdk::instruction::Instruction {
+        let payer_pubkey = setup.keypair.pubkey();
+        let mut data = switchboard_solana::get_ixn_discriminator("route").to_vec();
+        data.extend_from_slice(&amount_in.to_le_bytes());
+        data.extend_from_slice(&min_amount_out.to_le_bytes());
+        directions.serialize(&mut data).unwrap();
+        // Construct the accounts required for the route_instruction
+        let mut accounts = vec![
+            solana_sdk::instruction::AccountMeta::new_readonly(setup.global_parameters, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(payer_pubkey, true),
+            solana_sdk::instruction::AccountMeta::new_readonly(
+                Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+                false,
+            ),
+            solana_sdk::instruction::AccountMeta::new_readonly(setup.program_id, false),
+        ];
+        // Every hop goes through the fixture pool
+        for _ in &directions {
+            accounts.extend_from_slice(&[
+                solana_sdk::instruction::AccountMeta::new(setup.amm_account, false),
+                solana_sdk::instruction::AccountMeta::new(
+                    spl_associated_token_account::get_associated_token_address_with_program_id(
+                        &setup.amm_account,
+                        &setup.base_mint,
+                        &spl_token_2022::ID,
+                    ),
+                    false,
+                ),
+                solana_sdk::instruction::AccountMeta::new(
+                    spl_associated_token_account::get_associated_token_address_with_program_id(
+                        &setup.amm_account,
+                        &setup.quote_mint,
+                        &spl_token::ID,
+                    ),
+                    false,
+                ),
+                solana_sdk::instruction::AccountMeta::new(
+                    spl_associated_token_account::get_associated_token_address_with_program_id(
+                        &Pubkey::find_program_address(&[b"fee_authority"], &setup.program_id).0,
+                        &setup.quote_mint,
+                        &spl_token::ID,
+                    ),
+                    false,
+                ),
+                solana_sdk::instruction::AccountMeta::new_readonly(setup.base_mint, false),
+                solana_sdk::instruction::AccountMeta::new_readonly(setup.quote_mint, false),
+                solana_sdk::instruction::AccountMeta::new_readonly(spl_token_2022::ID, false),
+                solana_sdk::instruction::AccountMeta::new_readonly(spl_token::ID, false),
+                solana_sdk::instruction::AccountMeta::new(
+                    spl_associated_token_account::get_associated_token_address_with_program_id(
+                        &payer_pubkey,
+                        &setup.base_mint,
+                        &spl_token_2022::ID,
+                    ),
+                    false,
+                ),
+                solana_sdk::instruction::AccountMeta::new(
+                    spl_associated_token_account::get_associated_token_address_with_program_id(
+                        &payer_pubkey,
+                        &setup.quote_mint,
+                        &spl_token::ID,
+                    ),
+                    false,
+                ),
+                // No observations account
+                solana_sdk::instruction::AccountMeta::new_readonly(setup.program_id, false),
+            ]);
+        }
+        // Create the instruction using the program_id, accounts, and data
+        solana_sdk::instruction::Instruction {
+            program_id: setup.program_id,
+            accounts,
+            data,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_route_round_trip_success() {
+        let setup = setup_test_environment(true).await;
+        let (initial_base_reserve, initial_quote_reserve) = fetch_reserves(&setup).await;
+
+        // Buy base with quote then sell it straight back through the same pool
+        let ix = route_instruction(
+            &setup,
+            1_000_000,
+            1,
+            vec![SwapDirection::Buy, SwapDirection::Sell],
+        );
+
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.send_and_confirm_transaction(&tx).await;
+        assert!(result.is_ok(), "Route transaction should succeed");
+
+        let (updated_base_reserve, updated_quote_reserve) = fetch_reserves(&setup).await;
+        assert_eq!(
+            updated_base_reserve, initial_base_reserve,
+            "Base reserve should be back where it started"
+        );
+        assert!(
+            updated_quote_reserve > initial_quote_reserve,
+            "Quote reserve should keep the LP fees of both hops"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_route_failure_min_amount_out() {
+        let setup = setup_test_environment(true).await;
+
+        // A round trip can never pay out more than it spent
+        let ix = route_instruction(
+            &setup,
+            1_000_000,
+            1_000_000,
+            vec![SwapDirection::Buy, SwapDirection::Sell],
+        );
+
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.send_and_confirm_transaction(&tx).await;
+        assert!(
+            result.is_err(),
+            "Transaction should fail due to the route output being too low"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_route_failure_broken_chain() {
+        let setup = setup_test_environment(true).await;
+
+        // The second hop would spend base tokens while the first one paid out quote tokens
+        let ix = route_instruction(
+            &setup,
+            1_000_000,
+            1,
+            vec![SwapDirection::Sell, SwapDirection::Sell],
+        );
+
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.send_and_confirm_transaction(&tx).await;
+        assert!(
+            result.is_err(),
+            "Transaction should fail because the hops do not chain"
+        );
+    }
+}
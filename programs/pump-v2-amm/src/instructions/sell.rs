@@ -1,6 +1,10 @@
+use crate::curve::{self, Rounding};
 use crate::error::ErrorCode;
-use crate::utils::{spl_token_transfer, TokenTransferParams};
-use crate::Amm;
+use crate::utils::{
+    amount_after_transfer_fee, check_reserve_balances, load_referrer, spl_token_transfer,
+    SwapAmounts, TokenTransferParams,
+};
+use crate::{Amm, FeeConfig, Sell, PAUSE_SWAPS};
 use anchor_lang::prelude::*;
 
 // SellEvent event
@@ -13,26 +17,13 @@ pub struct SellEvent {
     pub protocol_fee_amount: u64,
     pub referrer: Option<Pubkey>,
     pub referrer_fee_amount: Option<u64>,
+    pub lp_fee_amount: u64,
 }
-/// Calculates the amount of base tokens received for selling a given amount of quote tokens using the xyk invariant without any fees.
-///
-/// # Arguments
-/// * `base_amount` - The amount of base tokens being sold by the user.
-/// * `amm` - The AMM account containing the reserves for the base and quote tokens.
-///
-/// # Returns
-/// * `Result<u64>` - The amount of base tokens received for the given quote amount.
-fn sell_quote(base_amount: u64, amm: &Amm) -> Result<u64> {
-    let quote_amount = (base_amount * amm.quote_reserve) / (amm.base_reserve + base_amount);
-    Ok(quote_amount)
-}
-
 /// This Sell module is responsible for handling the selling of tokens from the AMM.
 /// It includes the logic for calculating the required quote amount, transferring tokens,
 /// and emitting events.
 pub mod sell {
     use super::*;
-    use crate::Sell;
 
     /// Executes a sell transaction where a user sells base tokens for quote tokens.
     ///
@@ -48,135 +39,218 @@ pub mod sell {
         base_amount: u64,
         min_quote_amount: u64,
     ) -> Result<()> {
-        let base_token_program = ctx.accounts.base_token_program.to_account_info();
-        let quote_token_program = ctx.accounts.quote_token_program.to_account_info();
+        let fees = ctx.accounts.amm.fee_config(&ctx.accounts.global_parameters);
+        let amounts = quote_sell(
+            &ctx.accounts.amm,
+            &fees,
+            &ctx.accounts.base_mint.to_account_info(),
+            &ctx.accounts.quote_mint.to_account_info(),
+            base_amount,
+        )?;
 
-        // Calculate the required quote amount using AMM reserves
-        let quote_amount = sell_quote(base_amount, &*ctx.accounts.amm)?;
+        // Ensure the quote amount received is not below what the user expects
         require_gte!(
-            quote_amount,
+            amounts.quote_amount,
             min_quote_amount,
             ErrorCode::InsufficientQuoteAmount
         );
 
-        // Update AMM reserves
-        let amm = &mut ctx.accounts.amm;
-        amm.base_reserve += base_amount;
-        amm.quote_reserve -= quote_amount;
-
-        // Transfer base amount from user to base reserve
-        spl_token_transfer(TokenTransferParams {
-            source: ctx.accounts.user_base_ata.to_account_info(),
-            destination: ctx.accounts.base_reserve_ata.to_account_info(),
-            amount: base_amount,
-            authority: ctx.accounts.user.to_account_info(),
-            authority_signer_seeds: &[],
-            decimals: ctx.accounts.base_mint.decimals,
-            mint: ctx.accounts.base_mint.to_account_info(),
-            token_program: base_token_program.clone(),
-        })?;
-
-        let signer_seeds = [
-            b"amm",
-            ctx.accounts.amm.creator.as_ref(),
-            ctx.accounts.base_mint.to_account_info().key.as_ref(),
-            ctx.accounts.quote_mint.to_account_info().key.as_ref(),
-            &[ctx.bumps.amm],
-        ];
+        process_sell(ctx, amounts)
+    }
+}
 
-        // Transfer quote amount from quote reserve to user
-        spl_token_transfer(TokenTransferParams {
-            source: ctx.accounts.quote_reserve_ata.to_account_info(),
-            destination: ctx.accounts.user_quote_ata.to_account_info(),
-            amount: quote_amount,
-            authority: ctx.accounts.amm.to_account_info(),
-            authority_signer_seeds: &signer_seeds,
-            decimals: ctx.accounts.quote_mint.decimals,
-            mint: ctx.accounts.quote_mint.to_account_info(),
-            token_program: quote_token_program.clone(),
-        })?;
+/// Quotes a sell of exactly `base_amount` base tokens, as paid by the user.
+///
+/// The base reserve receives `base_amount` minus the base mint's transfer fee, the LP fee is kept
+/// out of the curve output, and the user receives what leaves the quote reserve minus the quote
+/// mint's transfer fee.
+pub(crate) fn quote_sell(
+    amm: &Amm,
+    fees: &FeeConfig,
+    base_mint: &AccountInfo,
+    quote_mint: &AccountInfo,
+    base_amount: u64,
+) -> Result<SwapAmounts> {
+    let base_reserve_amount = amount_after_transfer_fee(base_mint, base_amount)?;
+    let swap_quote_amount = amm.invariant(Clock::get()?.unix_timestamp).sell_quote(
+        base_reserve_amount,
+        amm.base_reserve,
+        amm.quote_reserve,
+    )?;
+    let lp_fee_amount = curve::fee_amount(swap_quote_amount, fees.lp_fee_bps)?;
+    let quote_reserve_amount = swap_quote_amount - lp_fee_amount;
+    let quote_amount = amount_after_transfer_fee(quote_mint, quote_reserve_amount)?;
+
+    Ok(SwapAmounts {
+        base_amount,
+        quote_amount,
+        base_reserve_amount,
+        quote_reserve_amount,
+        lp_fee_amount,
+    })
+}
 
-        let mut protocol_fee_bps = ctx.accounts.global_parameters.protocol_fee_bps;
-        let mut referrer_fee_amount = None;
-
-        // Transfer the fee to refferer if set and apply protocol fee discount.
-        if ctx.remaining_accounts.len() > 0 {
-            protocol_fee_bps -= ctx.accounts.global_parameters.referrer_fee_discount_bps;
-            let referrer_fee_bps = ctx.accounts.global_parameters.referrer_fee_bps;
-            referrer_fee_amount = Some((quote_amount * referrer_fee_bps) / 10000);
-
-            spl_token_transfer(TokenTransferParams {
-                source: ctx.accounts.user_quote_ata.to_account_info(),
-                // This account is checked in one of the token program instructions
-                destination: ctx.remaining_accounts.get(0).unwrap().to_account_info(),
-                amount: referrer_fee_amount.unwrap(),
-                authority: ctx.accounts.user.to_account_info(),
-                authority_signer_seeds: &[],
-                decimals: ctx.accounts.quote_mint.decimals,
-                mint: ctx.accounts.quote_mint.to_account_info(),
-                token_program: quote_token_program.clone(),
-            })?;
-        }
+/// Settles a quoted sell: updates the reserves, moves the tokens, pays the referrer
+/// and protocol fees and emits the `SellEvent`.
+///
+/// Shared by `sell` and `sell_exact_out`, which only differ in which side of the swap is fixed.
+///
+/// # Parameters:
+/// * `ctx` - The context in which this handler is executed, containing all necessary accounts.
+/// * `amounts` - The quoted amounts, see `SwapAmounts`.
+///
+/// # Returns:
+/// * `Result<()>` - Returns `Ok(())` if the transaction is successful.
+pub(crate) fn process_sell<'a>(
+    ctx: Context<'_, '_, '_, 'a, Sell<'a>>,
+    amounts: SwapAmounts,
+) -> Result<()> {
+    let SwapAmounts {
+        base_amount,
+        quote_amount,
+        base_reserve_amount,
+        quote_reserve_amount,
+        lp_fee_amount,
+    } = amounts;
+    ctx.accounts
+        .amm
+        .require_not_paused(&ctx.accounts.global_parameters, PAUSE_SWAPS)?;
+    let base_token_program = ctx.accounts.base_token_program.to_account_info();
+    let quote_token_program = ctx.accounts.quote_token_program.to_account_info();
+
+    // Update AMM reserves by what actually moves through the vaults
+    let amm = &mut ctx.accounts.amm;
+    let now = Clock::get()?.unix_timestamp;
+    amm.update_price_accumulators(now);
+    if let Some(observations) = ctx.accounts.observations.as_mut() {
+        observations.write(amm.observe(now));
+    }
+    amm.base_reserve = amm
+        .base_reserve
+        .checked_add(base_reserve_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    amm.quote_reserve = amm
+        .quote_reserve
+        .checked_sub(quote_reserve_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    // Transfer base amount from user to base reserve
+    spl_token_transfer(TokenTransferParams {
+        source: ctx.accounts.user_base_ata.to_account_info(),
+        destination: ctx.accounts.base_reserve_ata.to_account_info(),
+        amount: base_amount,
+        authority: ctx.accounts.user.to_account_info(),
+        authority_signer_seeds: &[],
+        decimals: ctx.accounts.base_mint.decimals,
+        mint: ctx.accounts.base_mint.to_account_info(),
+        token_program: base_token_program.clone(),
+    })?;
+
+    let signer_seeds = [
+        b"amm",
+        ctx.accounts.amm.creator.as_ref(),
+        ctx.accounts.base_mint.to_account_info().key.as_ref(),
+        ctx.accounts.quote_mint.to_account_info().key.as_ref(),
+        &[ctx.bumps.amm],
+    ];
+
+    // Transfer quote amount from quote reserve to user, the transfer fee is withheld on the way
+    spl_token_transfer(TokenTransferParams {
+        source: ctx.accounts.quote_reserve_ata.to_account_info(),
+        destination: ctx.accounts.user_quote_ata.to_account_info(),
+        amount: quote_reserve_amount,
+        authority: ctx.accounts.amm.to_account_info(),
+        authority_signer_seeds: &signer_seeds,
+        decimals: ctx.accounts.quote_mint.decimals,
+        mint: ctx.accounts.quote_mint.to_account_info(),
+        token_program: quote_token_program.clone(),
+    })?;
+
+    // Use the pool's own fees when set, the global ones otherwise
+    let fees = ctx.accounts.amm.fee_config(&ctx.accounts.global_parameters);
+    let mut protocol_fee_bps = fees.protocol_fee_bps;
+    let mut referrer_fee_amount = None;
+    // Transfer the fee to the referrer if a registered one is set and apply its protocol fee discount
+    let referrer = load_referrer(ctx.remaining_accounts, &ctx.accounts.quote_mint.key())?;
+    if let Some(mut referrer) = referrer {
+        let fee_tier = referrer.referrer.fee_tier(&fees, ctx.accounts.amm.fee_mode);
+        protocol_fee_bps = protocol_fee_bps.saturating_sub(fee_tier.referrer_fee_discount_bps);
+        let referrer_fee =
+            curve::apply_bps(quote_amount, fee_tier.referrer_fee_bps, Rounding::Down)?;
+        referrer_fee_amount = Some(referrer_fee);
 
-        // Transfer the protocol fee to the fee receiver
-        let protocol_fee_amount = (quote_amount * protocol_fee_bps) / 10000;
         spl_token_transfer(TokenTransferParams {
             source: ctx.accounts.user_quote_ata.to_account_info(),
-            destination: ctx.accounts.fee_receiver_ata.to_account_info(),
-            amount: protocol_fee_amount,
+            destination: referrer.token_account.clone(),
+            amount: referrer_fee,
             authority: ctx.accounts.user.to_account_info(),
             authority_signer_seeds: &[],
             decimals: ctx.accounts.quote_mint.decimals,
             mint: ctx.accounts.quote_mint.to_account_info(),
             token_program: quote_token_program.clone(),
         })?;
-
-        // Emit the event
-        emit_cpi!(SellEvent {
-            base_amount,
-            quote_amount,
-            timestamp: Clock::get()?.unix_timestamp as u64,
-            referrer: ctx
-                .remaining_accounts
-                .get(0)
-                .map(|r| *r.to_account_info().key),
-            referrer_fee_amount,
-            protocol_fee_amount,
-            user: *ctx.accounts.user.to_account_info().key,
-        });
-
-        emit!(SellEvent {
-            base_amount,
-            quote_amount,
-            timestamp: Clock::get()?.unix_timestamp as u64,
-            referrer: ctx
-                .remaining_accounts
-                .get(0)
-                .map(|r| *r.to_account_info().key),
-            referrer_fee_amount,
-            protocol_fee_amount,
-            user: *ctx.accounts.user.to_account_info().key,
-        });
-
-        Ok(())
+        referrer.record(quote_amount, referrer_fee)?;
     }
+
+    // Transfer the protocol fee to the fee receiver
+    let protocol_fee_amount = curve::apply_bps(quote_amount, protocol_fee_bps, Rounding::Down)?;
+    spl_token_transfer(TokenTransferParams {
+        source: ctx.accounts.user_quote_ata.to_account_info(),
+        destination: ctx.accounts.fee_receiver_ata.to_account_info(),
+        amount: protocol_fee_amount,
+        authority: ctx.accounts.user.to_account_info(),
+        authority_signer_seeds: &[],
+        decimals: ctx.accounts.quote_mint.decimals,
+        mint: ctx.accounts.quote_mint.to_account_info(),
+        token_program: quote_token_program.clone(),
+    })?;
+
+    // Make sure the vaults still back the recorded reserves
+    check_reserve_balances(
+        &ctx.accounts.amm,
+        &mut ctx.accounts.base_reserve_ata,
+        &mut ctx.accounts.quote_reserve_ata,
+    )?;
+
+    // Emit the event
+    emit_cpi!(SellEvent {
+        base_amount,
+        quote_amount,
+        timestamp: Clock::get()?.unix_timestamp as u64,
+        referrer: ctx
+            .remaining_accounts
+            .get(0)
+            .map(|r| *r.to_account_info().key),
+        referrer_fee_amount,
+        protocol_fee_amount,
+        lp_fee_amount,
+        user: *ctx.accounts.user.to_account_info().key,
+    });
+
+    emit!(SellEvent {
+        base_amount,
+        quote_amount,
+        timestamp: Clock::get()?.unix_timestamp as u64,
+        referrer: ctx
+            .remaining_accounts
+            .get(0)
+            .map(|r| *r.to_account_info().key),
+        referrer_fee_amount,
+        protocol_fee_amount,
+        lp_fee_amount,
+        user: *ctx.accounts.user.to_account_info().key,
+    });
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use crate::fixtures::tests::fetch_reserves;
-    use crate::fixtures::tests::setup_amm;
-    use crate::fixtures::tests::setup_mints_and_accounts;
     use crate::fixtures::tests::setup_test_environment;
-    use crate::fixtures::tests::setup_user_accounts;
-    use solana_client::nonblocking::rpc_client::RpcClient;
-    use solana_sdk::{
-        signature::{Keypair, Signer},
-        transaction::Transaction,
-    };
+    use solana_sdk::{signature::Signer, transaction::Transaction};
     use solana_transaction_status::option_serializer::OptionSerializer;
     use std::str::FromStr;
-    use std::sync::Arc;
     use switchboard_solana::Pubkey;
 
     fn sell_instruction(
@@ -232,7 +306,7 @@ mod tests {
             ),
             solana_sdk::instruction::AccountMeta::new(
                 spl_associated_token_account::get_associated_token_address_with_program_id(
-                    &payer_pubkey,
+                    &Pubkey::find_program_address(&[b"fee_authority"], program_id).0,
                     &quote_pubkey,
                     &spl_token::ID,
                 ),
@@ -242,19 +316,13 @@ mod tests {
             solana_sdk::instruction::AccountMeta::new(*quote_pubkey, false),
             solana_sdk::instruction::AccountMeta::new_readonly(spl_token_2022::ID, false),
             solana_sdk::instruction::AccountMeta::new_readonly(spl_token::ID, false),
+            // No observations account
+            solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
             solana_sdk::instruction::AccountMeta::new_readonly(
                 Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
                 false,
             ),
             solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
-            solana_sdk::instruction::AccountMeta::new(
-                spl_associated_token_account::get_associated_token_address_with_program_id(
-                    &payer_pubkey,
-                    &quote_pubkey,
-                    &spl_token::ID,
-                ),
-                false,
-            ),
         ];
         // Create the instruction using the program_id, accounts, and data
         solana_sdk::instruction::Instruction {
@@ -451,7 +519,7 @@ mod tests {
             .client
             .get_token_account_balance(
                 &spl_associated_token_account::get_associated_token_address_with_program_id(
-                    &setup.keypair.pubkey(),
+                    &Pubkey::find_program_address(&[b"fee_authority"], &setup.program_id).0,
                     &setup.quote_mint,
                     &spl_token::ID,
                 ),
@@ -488,7 +556,7 @@ mod tests {
             .client
             .get_token_account_balance(
                 &spl_associated_token_account::get_associated_token_address_with_program_id(
-                    &setup.keypair.pubkey(),
+                    &Pubkey::find_program_address(&[b"fee_authority"], &setup.program_id).0,
                     &setup.quote_mint,
                     &spl_token::ID,
                 ),
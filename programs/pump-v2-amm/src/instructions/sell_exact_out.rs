@@ -0,0 +1,217 @@
+use crate::curve;
+use crate::error::ErrorCode;
+use crate::instructions::sell::process_sell;
+use crate::utils::{amount_after_transfer_fee, amount_with_transfer_fee, SwapAmounts};
+use crate::{Amm, FeeConfig, Sell};
+use anchor_lang::prelude::*;
+
+/// This module is responsible for handling sells where the user fixes the quote amount received.
+/// It reuses the settlement path of `sell`, so fees and events are identical.
+pub mod sell_exact_out {
+    use super::*;
+
+    /// Executes a sell transaction where a user receives an exact amount of quote tokens for base tokens.
+    ///
+    /// # Parameters:
+    /// * `ctx` - The context in which this handler is executed, containing all necessary accounts.
+    /// * `quote_amount` - The amount of quote tokens the user wants to receive.
+    /// * `max_base_amount` - The maximum amount of base tokens the user is willing to sell.
+    ///
+    /// # Returns:
+    /// * `Result<()>` - Returns `Ok(())` if the transaction is successful.
+    pub fn handler<'a>(
+        ctx: Context<'_, '_, '_, 'a, Sell<'a>>,
+        quote_amount: u64,
+        max_base_amount: u64,
+    ) -> Result<()> {
+        require_gt!(quote_amount, 0, ErrorCode::ZeroOutputForSell);
+
+        let fees = ctx.accounts.amm.fee_config(&ctx.accounts.global_parameters);
+        let amounts = quote_sell_exact_out(
+            &ctx.accounts.amm,
+            &fees,
+            &ctx.accounts.base_mint.to_account_info(),
+            &ctx.accounts.quote_mint.to_account_info(),
+            quote_amount,
+        )?;
+
+        // Ensure the base amount paid does not exceed what the user is willing to sell
+        require_gte!(
+            max_base_amount,
+            amounts.base_amount,
+            ErrorCode::BaseAmountTooHigh
+        );
+
+        process_sell(ctx, amounts)
+    }
+}
+
+/// Quotes a sell receiving exactly `quote_amount` quote tokens, as received by the user.
+///
+/// The quote reserve has to release `quote_amount` plus the quote mint's transfer fee, the curve
+/// has to produce that plus the LP fee that stays in the reserves, and the user pays the base
+/// amount required by the curve grossed up by the base mint's transfer fee, of which the base
+/// reserve is credited with what actually lands in the vault.
+pub(crate) fn quote_sell_exact_out(
+    amm: &Amm,
+    fees: &FeeConfig,
+    base_mint: &AccountInfo,
+    quote_mint: &AccountInfo,
+    quote_amount: u64,
+) -> Result<SwapAmounts> {
+    let quote_reserve_amount = amount_with_transfer_fee(quote_mint, quote_amount)?;
+    let swap_quote_amount = curve::amount_with_fee(quote_reserve_amount, fees.lp_fee_bps)?;
+    let swap_base_amount = amm
+        .invariant(Clock::get()?.unix_timestamp)
+        .sell_exact_out_quote(swap_quote_amount, amm.base_reserve, amm.quote_reserve)?;
+    let base_amount = amount_with_transfer_fee(base_mint, swap_base_amount)?;
+    let base_reserve_amount = amount_after_transfer_fee(base_mint, base_amount)?;
+
+    Ok(SwapAmounts {
+        base_amount,
+        quote_amount,
+        base_reserve_amount,
+        quote_reserve_amount,
+        lp_fee_amount: swap_quote_amount - quote_reserve_amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::fetch_reserves;
+    use crate::fixtures::tests::setup_test_environment;
+    use crate::fixtures::tests::TestEnvironment;
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+    use std::str::FromStr;
+    use switchboard_solana::Pubkey;
+
+    fn sell_exact_out_instruction(
+        setup: &TestEnvironment,
+        quote_amount: u64,
+        max_base_amount: u64,
+    ) -> solana_sdk::instruction::Instruction {
+        let program_id = &setup.program_id;
+        let global_parameters = &setup.global_parameters;
+        let amm_pubkey = &setup.amm_account;
+        let base_pubkey = &setup.base_mint;
+        let quote_pubkey = &setup.quote_mint;
+        let payer_pubkey = &setup.keypair.pubkey();
+        let mut data = switchboard_solana::get_ixn_discriminator("sell_exact_out").to_vec();
+        data.extend_from_slice(&quote_amount.to_le_bytes());
+        data.extend_from_slice(&max_base_amount.to_le_bytes());
+
+        // Construct the accounts required for the sell_exact_out_instruction
+        let accounts = vec![
+            solana_sdk::instruction::AccountMeta::new(*amm_pubkey, false),
+            solana_sdk::instruction::AccountMeta::new(*global_parameters, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(*payer_pubkey, true),
+            solana_sdk::instruction::AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    payer_pubkey,
+                    base_pubkey,
+                    &spl_token_2022::ID,
+                ),
+                false,
+            ),
+            solana_sdk::instruction::AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    payer_pubkey,
+                    quote_pubkey,
+                    &spl_token::ID,
+                ),
+                false,
+            ),
+            solana_sdk::instruction::AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    amm_pubkey,
+                    base_pubkey,
+                    &spl_token_2022::ID,
+                ),
+                false,
+            ),
+            solana_sdk::instruction::AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    amm_pubkey,
+                    quote_pubkey,
+                    &spl_token::ID,
+                ),
+                false,
+            ),
+            solana_sdk::instruction::AccountMeta::new(
+                spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &Pubkey::find_program_address(&[b"fee_authority"], program_id).0,
+                    quote_pubkey,
+                    &spl_token::ID,
+                ),
+                false,
+            ),
+            solana_sdk::instruction::AccountMeta::new(*base_pubkey, false),
+            solana_sdk::instruction::AccountMeta::new(*quote_pubkey, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(spl_token_2022::ID, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(spl_token::ID, false),
+            // No observations account
+            solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(
+                Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+                false,
+            ),
+            solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
+        ];
+        // Create the instruction using the program_id, accounts, and data
+        solana_sdk::instruction::Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sell_exact_out_success() {
+        let setup = setup_test_environment(true).await;
+        let quote_amount = 100_000;
+        let max_base_amount = 1_000_000;
+
+        let (initial_base_reserve, initial_quote_reserve) = fetch_reserves(&setup).await;
+
+        let ix = sell_exact_out_instruction(&setup, quote_amount, max_base_amount);
+
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.send_and_confirm_transaction(&tx).await;
+        assert!(result.is_ok(), "Sell exact out transaction should succeed");
+
+        let (updated_base_reserve, updated_quote_reserve) = fetch_reserves(&setup).await;
+        assert_eq!(
+            updated_quote_reserve,
+            initial_quote_reserve - quote_amount,
+            "Quote reserve should shrink by exactly the quote amount received"
+        );
+        assert!(
+            updated_base_reserve > initial_base_reserve,
+            "Base reserve should grow after sell exact out transaction"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sell_exact_out_failure_excessive_base() {
+        let setup = setup_test_environment(true).await;
+        let quote_amount = 100_000;
+        let max_base_amount = 1; // Deliberately low to trigger failure
+
+        let ix = sell_exact_out_instruction(&setup, quote_amount, max_base_amount);
+
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.send_and_confirm_transaction(&tx).await;
+        assert!(
+            result.is_err(),
+            "Transaction should fail due to excessive base amount"
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use crate::ReferrerFeeTier;
+use anchor_lang::prelude::*;
+
+// SetReferrerFeeTierEvent event
+#[event]
+pub struct SetReferrerFeeTierEvent {
+    pub owner: Pubkey,
+    pub fee_tier: Option<ReferrerFeeTier>,
+    pub timestamp: u64,
+}
+
+/// Gives a registered referrer its own referrer fee and protocol fee discount, overriding the
+/// ones of the pools it refers swaps to.
+pub mod set_referrer_fee_tier {
+    use crate::SetReferrerFeeTier;
+
+    use super::*;
+
+    /// Sets or clears the fee tier of the referrer
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the `Referrer` account.
+    /// - `fee_tier`: The referrer's fees, `None` to fall back to the pool's.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(
+        ctx: Context<SetReferrerFeeTier>,
+        fee_tier: Option<ReferrerFeeTier>,
+    ) -> Result<()> {
+        if let Some(fee_tier) = fee_tier {
+            fee_tier.validate()?;
+        }
+        ctx.accounts.referrer.fee_tier = fee_tier;
+
+        // Emit the event
+        emit_cpi!(SetReferrerFeeTierEvent {
+            owner: ctx.accounts.referrer.owner,
+            fee_tier,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(SetReferrerFeeTierEvent {
+            owner: ctx.accounts.referrer.owner,
+            fee_tier,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
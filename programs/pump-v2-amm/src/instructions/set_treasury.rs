@@ -0,0 +1,42 @@
+/// This submodule lets the admin choose the treasury, the owner of the token accounts that
+/// `withdraw_protocol_fees` sends the protocol fees to. It is set to the admin on `initialize`.
+pub mod set_treasury {
+    use crate::SetTreasury;
+    use anchor_lang::prelude::*;
+
+    // SetTreasuryEvent event
+    #[event]
+    pub struct SetTreasuryEvent {
+        pub previous_treasury: Pubkey,
+        pub treasury: Pubkey,
+        pub timestamp: u64,
+    }
+
+    /// Sets the treasury
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing all required accounts for the transaction.
+    /// - `treasury`: The new owner of the protocol fee withdrawals.
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<SetTreasury>, treasury: Pubkey) -> Result<()> {
+        let global_parameters = &mut ctx.accounts.global_parameters;
+        let previous_treasury = global_parameters.treasury;
+        global_parameters.treasury = treasury;
+
+        // Emit the event
+        emit_cpi!(SetTreasuryEvent {
+            previous_treasury,
+            treasury,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(SetTreasuryEvent {
+            previous_treasury,
+            treasury,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use anchor_lang::prelude::*;
+
+// SyncEvent event
+#[event]
+pub struct SyncEvent {
+    pub amm: Pubkey,
+    pub previous_base_reserve: u64,
+    pub previous_quote_reserve: u64,
+    pub base_reserve: u64,
+    pub quote_reserve: u64,
+    pub timestamp: u64,
+}
+
+/// The `sync` module reconciles the reserves recorded on the AMM with the balances actually held
+/// by its vaults, e.g. after tokens were donated to the pool. It is permissionless since it can
+/// only move the recorded reserves to what the vaults hold.
+pub mod sync {
+    use super::*;
+    use crate::SyncReserves;
+
+    /// Sets the AMM's reserves to the balances of its reserve vaults.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the AMM and its reserve vaults.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<SyncReserves>) -> Result<()> {
+        let amm = &mut ctx.accounts.amm;
+        let previous_base_reserve = amm.base_reserve;
+        let previous_quote_reserve = amm.quote_reserve;
+        amm.update_price_accumulators(Clock::get()?.unix_timestamp);
+        amm.base_reserve = ctx.accounts.base_reserve_ata.amount;
+        amm.quote_reserve = ctx.accounts.quote_reserve_ata.amount;
+
+        // Emit the event
+        emit_cpi!(SyncEvent {
+            amm: ctx.accounts.amm.key(),
+            previous_base_reserve,
+            previous_quote_reserve,
+            base_reserve: ctx.accounts.amm.base_reserve,
+            quote_reserve: ctx.accounts.amm.quote_reserve,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(SyncEvent {
+            amm: ctx.accounts.amm.key(),
+            previous_base_reserve,
+            previous_quote_reserve,
+            base_reserve: ctx.accounts.amm.base_reserve,
+            quote_reserve: ctx.accounts.amm.quote_reserve,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::buy_instruction;
+    use crate::fixtures::tests::fetch_reserves;
+    use crate::fixtures::tests::setup_test_environment;
+    use anchor_lang::AccountDeserialize;
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+    use std::str::FromStr;
+    use switchboard_solana::Pubkey;
+
+    fn sync_instruction(
+        program_id: &solana_sdk::pubkey::Pubkey,
+        amm_pubkey: &solana_sdk::pubkey::Pubkey,
+        base_vault: &solana_sdk::pubkey::Pubkey,
+        quote_vault: &solana_sdk::pubkey::Pubkey,
+    ) -> solana_sdk::instruction::Instruction {
+        let data = switchboard_solana::get_ixn_discriminator("sync").to_vec();
+        // Construct the accounts required for the sync_instruction
+        let accounts = vec![
+            solana_sdk::instruction::AccountMeta::new(*amm_pubkey, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(*base_vault, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(*quote_vault, false),
+            solana_sdk::instruction::AccountMeta::new_readonly(
+                Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+                false,
+            ),
+            solana_sdk::instruction::AccountMeta::new_readonly(*program_id, false),
+        ];
+        solana_sdk::instruction::Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_absorbs_donation() {
+        let setup = setup_test_environment(true).await;
+        let donation = 1_000;
+
+        // Donate quote tokens straight to the quote vault
+        let donate_ix = spl_token::instruction::transfer(
+            &spl_token::ID,
+            &setup.user_quote,
+            &setup.quote_vault,
+            &setup.keypair.pubkey(),
+            &[],
+            donation,
+        )
+        .unwrap();
+        let ix = sync_instruction(
+            &setup.program_id,
+            &setup.amm_account,
+            &setup.base_vault,
+            &setup.quote_vault,
+        );
+
+        let mut tx = Transaction::new_with_payer(&[donate_ix, ix], Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.send_and_confirm_transaction(&tx).await;
+        assert!(result.is_ok(), "Sync transaction should succeed");
+
+        let (base_vault_balance, quote_vault_balance) = fetch_reserves(&setup).await;
+        let amm_acc = setup.client.get_account(&setup.amm_account).await.unwrap();
+        let mut data = amm_acc.data.as_slice();
+        let amm = crate::state::Amm::try_deserialize(&mut data).unwrap();
+        assert_eq!(
+            amm.base_reserve, base_vault_balance,
+            "Base reserve should match the base vault after sync"
+        );
+        assert_eq!(
+            amm.quote_reserve, quote_vault_balance,
+            "Quote reserve should include the donation after sync"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unsynced_donation_keeps_swaps_working() {
+        let setup = setup_test_environment(true).await;
+        let (_, quote_vault_before) = fetch_reserves(&setup).await;
+
+        // Donate quote tokens straight to the quote vault, without syncing
+        let donate_ix = spl_token::instruction::transfer(
+            &spl_token::ID,
+            &setup.user_quote,
+            &setup.quote_vault,
+            &setup.keypair.pubkey(),
+            &[],
+            1,
+        )
+        .unwrap();
+        let ix = buy_instruction(
+            &setup.program_id,
+            &setup.global_parameters,
+            &setup.amm_account,
+            &setup.base_mint,
+            &setup.quote_mint,
+            &setup.keypair.pubkey(),
+            1_000,
+            u64::MAX,
+        );
+
+        let mut tx = Transaction::new_with_payer(&[donate_ix, ix], Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        let result = setup.client.send_and_confirm_transaction(&tx).await;
+        assert!(
+            result.is_ok(),
+            "A donation left in the vault should not block swaps"
+        );
+
+        let amm_acc = setup.client.get_account(&setup.amm_account).await.unwrap();
+        let mut data = amm_acc.data.as_slice();
+        let amm = crate::state::Amm::try_deserialize(&mut data).unwrap();
+        let (_, quote_vault_balance) = fetch_reserves(&setup).await;
+        assert!(
+            amm.quote_reserve > quote_vault_before && amm.quote_reserve < quote_vault_balance,
+            "The donation should stay out of the reserves until synced"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use crate::error::ErrorCode;
+use crate::utils::{spl_token_transfer, TokenTransferParams};
+use anchor_lang::prelude::*;
+
+// UnlockLiquidityEvent event
+#[event]
+pub struct UnlockLiquidityEvent {
+    pub amm: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub locked_amount: u64, // shares still locked for the owner
+    pub fee_shares: u64,    // fees claimed with the unlocked shares
+    pub timestamp: u64,
+}
+
+/// Returns LP shares from a timed lock once its unlock timestamp is reached.
+pub mod unlock_liquidity {
+    use super::*;
+    use crate::LockedLiquidity;
+
+    /// Transfers `amount` locked shares back to the owner, with the fees pending on the lock.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the owner's LP token account, the vault and the lock.
+    /// - `amount`: Amount of LP shares to unlock.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<LockedLiquidity>, amount: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let liquidity_lock = &mut ctx.accounts.liquidity_lock;
+        // Permanent locks never unlock
+        let unlock_timestamp = liquidity_lock
+            .unlock_timestamp
+            .ok_or(ErrorCode::LiquidityLocked)?;
+        require_gte!(now, unlock_timestamp, ErrorCode::LiquidityLocked);
+
+        let fee_shares = liquidity_lock.claim_fees(&ctx.accounts.amm, now)?;
+        require_gte!(
+            liquidity_lock.amount,
+            amount,
+            ErrorCode::LiquidityRemovalExceedsShares
+        );
+        liquidity_lock.amount -= amount;
+
+        // Transfer the shares and their fees back to the owner
+        let amm_key = ctx.accounts.amm.key();
+        let signer_seeds = [
+            b"lp_locker".as_ref(),
+            amm_key.as_ref(),
+            &[ctx.bumps.lp_locker],
+        ];
+        spl_token_transfer(TokenTransferParams {
+            source: ctx.accounts.locker_lp_ata.to_account_info(),
+            destination: ctx.accounts.owner_lp_ata.to_account_info(),
+            amount: amount + fee_shares,
+            authority: ctx.accounts.lp_locker.to_account_info(),
+            authority_signer_seeds: &signer_seeds,
+            decimals: ctx.accounts.lp_mint.decimals,
+            mint: ctx.accounts.lp_mint.to_account_info(),
+            token_program: ctx.accounts.lp_token_program.to_account_info(),
+        })?;
+
+        // Emit the event
+        emit_cpi!(UnlockLiquidityEvent {
+            amm: ctx.accounts.amm.key(),
+            owner: ctx.accounts.owner.key(),
+            amount,
+            locked_amount: ctx.accounts.liquidity_lock.amount,
+            fee_shares,
+            timestamp: now as u64,
+        });
+
+        emit!(UnlockLiquidityEvent {
+            amm: ctx.accounts.amm.key(),
+            owner: ctx.accounts.owner.key(),
+            amount,
+            locked_amount: ctx.accounts.liquidity_lock.amount,
+            fee_shares,
+            timestamp: now as u64,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use crate::instructions::pause::{validate_pause_flags, PauseEvent};
+use anchor_lang::prelude::*;
+
+/// Resumes operations halted globally by `pause`. Operations paused on a single pool stay paused
+/// until that pool is unpaused too.
+pub mod unpause {
+    use crate::SetPause;
+
+    use super::*;
+
+    /// Removes `flags` from the globally paused operations
+    ///
+    /// # Parameters:
+    /// - `ctx`: The context of the AMM.
+    /// - `flags`: The `PAUSE_*` flags of the operations to resume.
+    ///
+    /// # Returns:
+    /// - `Result<()>`: A success indicator if the operations are resumed.
+    pub fn handler(ctx: Context<SetPause>, flags: u8) -> Result<()> {
+        validate_pause_flags(flags)?;
+
+        let global_parameters = &mut ctx.accounts.global_parameters;
+        global_parameters.paused &= !flags;
+        let paused = global_parameters.paused;
+
+        // Emit the event
+        emit_cpi!(PauseEvent {
+            amm: None,
+            paused,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(PauseEvent {
+            amm: None,
+            paused,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use crate::instructions::pause::{validate_pause_flags, PauseEvent};
+use anchor_lang::prelude::*;
+
+/// Resumes operations halted on a single AMM by `pause_pool`. Operations paused globally stay
+/// paused until `unpause` is called.
+pub mod unpause_pool {
+    use crate::SetPoolPause;
+
+    use super::*;
+
+    /// Removes `flags` from the paused operations of the pool
+    ///
+    /// # Parameters:
+    /// - `ctx`: The context of the AMM.
+    /// - `flags`: The `PAUSE_*` flags of the operations to resume.
+    ///
+    /// # Returns:
+    /// - `Result<()>`: A success indicator if the operations are resumed.
+    pub fn handler(ctx: Context<SetPoolPause>, flags: u8) -> Result<()> {
+        validate_pause_flags(flags)?;
+
+        let amm = &mut ctx.accounts.amm;
+        amm.paused &= !flags;
+        let paused = amm.paused;
+
+        // Emit the event
+        emit_cpi!(PauseEvent {
+            amm: Some(ctx.accounts.amm.key()),
+            paused,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(PauseEvent {
+            amm: Some(ctx.accounts.amm.key()),
+            paused,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use crate::utils::create_fee_receiver_ata;
+use anchor_lang::prelude::*;
+
+// UpdateFeeReceiverEvent event
+#[event]
+pub struct UpdateFeeReceiverEvent {
+    pub amm: Pubkey,
+    pub previous_fee_receiver_ata: Pubkey,
+    pub fee_receiver_ata: Pubkey,
+    pub timestamp: u64,
+}
+
+/// Points the protocol fees of a pool at the fee authority's token account for its quote mint.
+///
+/// Pools created before the fee authority paid their protocol fees to a token account of the admin
+/// at creation time, which kept receiving them after the admin changed. The new receiver is fixed
+/// by the program, so the instruction is permissionless.
+pub mod update_fee_receiver {
+    use super::*;
+    use crate::UpdateFeeReceiver;
+
+    /// Sets the pool's fee receiver to the fee authority's associated token account.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the AMM and the fee authority's token account.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<UpdateFeeReceiver>) -> Result<()> {
+        create_fee_receiver_ata(
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.fee_receiver_ata.to_account_info(),
+            ctx.accounts.fee_authority.to_account_info(),
+            ctx.accounts.quote_mint.to_account_info(),
+            ctx.accounts.quote_token_program.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.associated_token_program.to_account_info(),
+        )?;
+
+        let amm = &mut ctx.accounts.amm;
+        let previous_fee_receiver_ata = amm.fee_receiver_ata;
+        amm.fee_receiver_ata = ctx.accounts.fee_receiver_ata.key();
+
+        // Emit the event
+        emit_cpi!(UpdateFeeReceiverEvent {
+            amm: ctx.accounts.amm.key(),
+            previous_fee_receiver_ata,
+            fee_receiver_ata: ctx.accounts.fee_receiver_ata.key(),
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(UpdateFeeReceiverEvent {
+            amm: ctx.accounts.amm.key(),
+            previous_fee_receiver_ata,
+            fee_receiver_ata: ctx.accounts.fee_receiver_ata.key(),
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
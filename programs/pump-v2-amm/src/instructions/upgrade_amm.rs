@@ -0,0 +1,108 @@
+use crate::error::ErrorCode;
+use crate::utils::grow_account;
+use crate::Amm;
+use anchor_lang::prelude::*;
+
+// UpgradeAmmEvent event
+#[event]
+pub struct UpgradeAmmEvent {
+    pub amm: Pubkey,
+    pub previous_len: u64,
+    pub len: u64,
+    pub timestamp: u64,
+}
+
+/// Grows a pool created with the original `Amm` layout to the current one.
+///
+/// The fields appended since read as zero once the account is grown: no fee override, a constant
+/// product curve, empty price accumulators, no flash loan and nothing paused. Only the timestamp
+/// the accumulators start from and the fee mode with the liquidity its fees accrue from are set.
+/// The result is the same for anyone calling it, so the instruction is permissionless and the
+/// caller pays the rent.
+///
+/// Upgrade path of a deployment created with the original layouts:
+/// 1. `upgrade_global_parameters`, which every pool instruction loads.
+/// 2. `upgrade_amm` on each pool.
+/// 3. `update_fee_receiver` on each pool, to pay the protocol fees to the fee authority.
+pub mod upgrade_amm {
+    use super::*;
+    use crate::UpgradeAmm;
+
+    /// Reallocates the pool to `Amm::LEN` and initializes the appended fields.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the pool, the global parameters and the payer of the rent.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<UpgradeAmm>) -> Result<()> {
+        let amm_info = ctx.accounts.amm.to_account_info();
+        let previous_len = amm_info.data_len();
+        require_eq!(previous_len, Amm::V1_LEN, ErrorCode::UnknownAccountLayout);
+        grow_account(
+            amm_info.clone(),
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            Amm::LEN,
+        )?;
+
+        // Checks the discriminator, now that the account deserializes
+        let mut amm = Amm::try_deserialize(&mut &amm_info.try_borrow_data()?[..])?;
+        let now = Clock::get()?.unix_timestamp;
+        amm.last_price_update_ts = now;
+        amm.update_liquidity_last(&ctx.accounts.global_parameters, now)?;
+        amm.try_serialize(&mut &mut amm_info.try_borrow_mut_data()?[..])?;
+
+        // Emit the event
+        emit_cpi!(UpgradeAmmEvent {
+            amm: amm_info.key(),
+            previous_len: previous_len as u64,
+            len: Amm::LEN as u64,
+            timestamp: now as u64,
+        });
+
+        emit!(UpgradeAmmEvent {
+            amm: amm_info.key(),
+            previous_len: previous_len as u64,
+            len: Amm::LEN as u64,
+            timestamp: now as u64,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Amm, Curve};
+    use anchor_lang::{AccountDeserialize, Discriminator};
+    use switchboard_solana::Pubkey;
+
+    #[test]
+    fn test_v1_layout_reads_after_growing() {
+        let base_mint = Pubkey::new_unique();
+        let fee_receiver_ata = Pubkey::new_unique();
+        let mut data = Amm::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(base_mint.as_ref());
+        data.extend_from_slice(&[0; 32]); // quote_mint
+        data.extend_from_slice(&1_000u64.to_le_bytes()); // base_reserve
+        data.extend_from_slice(&2_000u64.to_le_bytes()); // quote_reserve
+        data.extend_from_slice(&3_000u64.to_le_bytes()); // total_shares
+        data.extend_from_slice(&[0; 4 * 32]); // lp_mint, creator and reserve accounts
+        data.extend_from_slice(fee_receiver_ata.as_ref());
+        assert_eq!(data.len(), Amm::V1_LEN);
+
+        // Growing zero fills the appended fields
+        data.resize(Amm::LEN, 0);
+        let amm = Amm::try_deserialize(&mut data.as_slice()).unwrap();
+        assert_eq!(amm.base_mint, base_mint);
+        assert_eq!(amm.base_reserve, 1_000);
+        assert_eq!(amm.quote_reserve, 2_000);
+        assert_eq!(amm.total_shares, 3_000);
+        assert_eq!(amm.fee_receiver_ata, fee_receiver_ata);
+        assert_eq!(amm.fee_config, None);
+        assert_eq!(amm.curve, Curve::ConstantProduct);
+        assert!(amm.flash_loan.is_none());
+        assert_eq!(amm.paused, 0);
+    }
+}
@@ -0,0 +1,99 @@
+use crate::error::ErrorCode;
+use crate::utils::grow_account;
+use crate::GlobalParameters;
+use anchor_lang::prelude::*;
+
+// UpgradeGlobalParametersEvent event
+#[event]
+pub struct UpgradeGlobalParametersEvent {
+    pub previous_len: u64,
+    pub len: u64,
+    pub timestamp: u64,
+}
+
+/// Grows global parameters created with the original `GlobalParameters` layout to the current
+/// one, as the first step of the upgrade path described on `upgrade_amm`.
+///
+/// The fields appended since read as zero once the account is grown: no LP or flash loan fee,
+/// nothing paused, no timelock and the `OnTop` fee mode. Only the treasury is set, to the admin as
+/// `initialize` does. The result is the same for anyone calling it, so the instruction is
+/// permissionless and the caller pays the rent.
+pub mod upgrade_global_parameters {
+    use super::*;
+    use crate::UpgradeGlobalParameters;
+
+    /// Reallocates the global parameters to `GlobalParameters::LEN` and initializes the treasury.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the global parameters and the payer of the rent.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<UpgradeGlobalParameters>) -> Result<()> {
+        let global_parameters_info = ctx.accounts.global_parameters.to_account_info();
+        let previous_len = global_parameters_info.data_len();
+        require_eq!(
+            previous_len,
+            GlobalParameters::V1_LEN,
+            ErrorCode::UnknownAccountLayout
+        );
+        grow_account(
+            global_parameters_info.clone(),
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            GlobalParameters::LEN,
+        )?;
+
+        // Checks the discriminator, now that the account deserializes
+        let mut global_parameters =
+            GlobalParameters::try_deserialize(&mut &global_parameters_info.try_borrow_data()?[..])?;
+        global_parameters.treasury = global_parameters.admin;
+        global_parameters
+            .try_serialize(&mut &mut global_parameters_info.try_borrow_mut_data()?[..])?;
+
+        // Emit the event
+        emit_cpi!(UpgradeGlobalParametersEvent {
+            previous_len: previous_len as u64,
+            len: GlobalParameters::LEN as u64,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(UpgradeGlobalParametersEvent {
+            previous_len: previous_len as u64,
+            len: GlobalParameters::LEN as u64,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FeeMode, GlobalParameters};
+    use anchor_lang::{AccountDeserialize, Discriminator};
+    use switchboard_solana::Pubkey;
+
+    #[test]
+    fn test_v1_layout_reads_after_growing() {
+        let admin = Pubkey::new_unique();
+        let mut data = GlobalParameters::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&30u64.to_le_bytes()); // protocol_fee_bps
+        data.extend_from_slice(&10u64.to_le_bytes()); // referrer_fee_bps
+        data.extend_from_slice(&5u64.to_le_bytes()); // referrer_fee_discount_bps
+        data.extend_from_slice(admin.as_ref());
+        data.extend_from_slice(&[0; 32]); // proposed_admin
+        assert_eq!(data.len(), GlobalParameters::V1_LEN);
+
+        // Growing zero fills the appended fields
+        data.resize(GlobalParameters::LEN, 0);
+        let global_parameters = GlobalParameters::try_deserialize(&mut data.as_slice()).unwrap();
+        assert_eq!(global_parameters.protocol_fee_bps, 30);
+        assert_eq!(global_parameters.referrer_fee_bps, 10);
+        assert_eq!(global_parameters.referrer_fee_discount_bps, 5);
+        assert_eq!(global_parameters.admin, admin);
+        assert_eq!(global_parameters.lp_fee_bps, 0);
+        assert_eq!(global_parameters.paused, 0);
+        assert_eq!(global_parameters.fee_mode, FeeMode::OnTop);
+    }
+}
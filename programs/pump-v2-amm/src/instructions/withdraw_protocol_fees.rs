@@ -0,0 +1,152 @@
+use crate::utils::{spl_token_transfer, TokenTransferParams};
+use anchor_lang::prelude::*;
+
+// WithdrawProtocolFeesEvent event
+#[event]
+pub struct WithdrawProtocolFeesEvent {
+    pub mint: Pubkey,
+    pub treasury_ata: Pubkey,
+    pub amount: u64,
+    pub timestamp: u64,
+}
+
+/// Sweeps the protocol fees collected in one mint to a token account of the treasury.
+pub mod withdraw_protocol_fees {
+    use super::*;
+    use crate::WithdrawProtocolFees;
+
+    /// Transfers the whole balance of the fee authority's token account to the treasury.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing the fee authority's and the treasury's token accounts.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(ctx: Context<WithdrawProtocolFees>) -> Result<()> {
+        let amount = ctx.accounts.fee_receiver_ata.amount;
+        let signer_seeds = [b"fee_authority".as_ref(), &[ctx.bumps.fee_authority]];
+
+        spl_token_transfer(TokenTransferParams {
+            source: ctx.accounts.fee_receiver_ata.to_account_info(),
+            destination: ctx.accounts.treasury_ata.to_account_info(),
+            amount,
+            authority: ctx.accounts.fee_authority.to_account_info(),
+            authority_signer_seeds: &signer_seeds,
+            decimals: ctx.accounts.mint.decimals,
+            mint: ctx.accounts.mint.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        })?;
+
+        // Emit the event
+        emit_cpi!(WithdrawProtocolFeesEvent {
+            mint: ctx.accounts.mint.key(),
+            treasury_ata: ctx.accounts.treasury_ata.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        emit!(WithdrawProtocolFeesEvent {
+            mint: ctx.accounts.mint.key(),
+            treasury_ata: ctx.accounts.treasury_ata.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp as u64,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::setup_test_environment;
+    use crate::fixtures::tests::TestEnvironment;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+    use std::str::FromStr;
+    use switchboard_solana::Pubkey;
+
+    async fn token_balance(setup: &TestEnvironment, owner: &Pubkey) -> u64 {
+        setup
+            .client
+            .get_token_account_balance(
+                &spl_associated_token_account::get_associated_token_address_with_program_id(
+                    owner,
+                    &setup.quote_mint,
+                    &spl_token::ID,
+                ),
+            )
+            .await
+            .unwrap()
+            .amount
+            .parse::<u64>()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_withdraw_protocol_fees_to_treasury() {
+        let setup = setup_test_environment(true).await;
+        let fee_authority = Pubkey::find_program_address(&[b"fee_authority"], &setup.program_id).0;
+        // The admin is the treasury until set_treasury is called
+        let treasury = setup.keypair.pubkey();
+
+        let initial_fees = token_balance(&setup, &fee_authority).await;
+        let initial_treasury_balance = token_balance(&setup, &treasury).await;
+
+        let ix = Instruction {
+            program_id: setup.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(setup.global_parameters, false),
+                AccountMeta::new_readonly(setup.keypair.pubkey(), true),
+                AccountMeta::new_readonly(fee_authority, false),
+                AccountMeta::new(
+                    spl_associated_token_account::get_associated_token_address_with_program_id(
+                        &fee_authority,
+                        &setup.quote_mint,
+                        &spl_token::ID,
+                    ),
+                    false,
+                ),
+                AccountMeta::new(
+                    spl_associated_token_account::get_associated_token_address_with_program_id(
+                        &treasury,
+                        &setup.quote_mint,
+                        &spl_token::ID,
+                    ),
+                    false,
+                ),
+                AccountMeta::new_readonly(setup.quote_mint, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                // No role account, the test keypair is the admin
+                AccountMeta::new_readonly(setup.program_id, false),
+                AccountMeta::new_readonly(
+                    Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+                    false,
+                ),
+                AccountMeta::new_readonly(setup.program_id, false),
+            ],
+            data: switchboard_solana::get_ixn_discriminator("withdraw_protocol_fees").to_vec(),
+        };
+
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        setup
+            .client
+            .send_and_confirm_transaction(&tx)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            token_balance(&setup, &fee_authority).await,
+            0,
+            "All protocol fees should be swept"
+        );
+        assert_eq!(
+            token_balance(&setup, &treasury).await,
+            initial_treasury_balance + initial_fees,
+            "The treasury should receive the swept fees"
+        );
+    }
+}
@@ -0,0 +1,433 @@
+use crate::curve::{self, Invariant, Rounding};
+use crate::error::ErrorCode;
+use crate::utils::{
+    amount_after_transfer_fee, check_reserve_balances, spl_token_transfer, TokenTransferParams,
+};
+use crate::{FeeConfig, PoolSide};
+use anchor_lang::prelude::*;
+
+// ZapInEvent event, amounts are the ones credited to the reserves after transfer fees
+#[event]
+pub struct ZapInEvent {
+    pub side: PoolSide,
+    pub amount: u64,
+    pub swap_amount: u64, // part of the amount swapped for the other side
+    pub base_amount: u64, // deposited after the swap
+    pub quote_amount: u64,
+    pub shares: u64,
+    pub lp_fee_amount: u64,
+    pub protocol_fee_amount: u64,
+    pub protocol_fee_shares: u64,
+    pub timestamp: u64,
+    pub user: Pubkey,
+}
+
+/// Swap made inside a zap. Its tokens never leave the reserve vaults, only the protocol fee is
+/// sent from the quote reserve to the fee receiver.
+pub(crate) struct ZapSwap {
+    /// Tokens of the other side given by the swap.
+    pub amount_out: u64,
+    /// Base reserve after the swap.
+    pub base_reserve: u64,
+    /// Quote reserve after the swap, without the protocol fee.
+    pub quote_reserve: u64,
+    /// The part of the swapped quote amount kept in the reserves as the LP fee.
+    pub lp_fee_amount: u64,
+    /// The part of the swapped quote amount paid to the protocol.
+    pub protocol_fee_amount: u64,
+}
+
+/// Quotes a zap swap of `amount_in` tokens of `side_in` against the given reserves.
+///
+/// Fees are charged as on a swap: the protocol and LP fees on the quote tokens paid in when
+/// swapping quote for base, on the quote tokens out of the curve when swapping base for quote.
+pub(crate) fn quote_zap_swap(
+    invariant: Invariant,
+    fees: &FeeConfig,
+    side_in: PoolSide,
+    amount_in: u64,
+    base_reserve: u64,
+    quote_reserve: u64,
+) -> Result<ZapSwap> {
+    match side_in {
+        PoolSide::Quote => {
+            let protocol_fee_amount =
+                curve::apply_bps(amount_in, fees.protocol_fee_bps, Rounding::Down)?;
+            let quote_reserve_amount = amount_in - protocol_fee_amount;
+            let lp_fee_amount = curve::fee_amount(quote_reserve_amount, fees.lp_fee_bps)?;
+            let amount_out = invariant.buy_exact_in_quote(
+                quote_reserve_amount - lp_fee_amount,
+                base_reserve,
+                quote_reserve,
+            )?;
+            Ok(ZapSwap {
+                amount_out,
+                base_reserve: base_reserve - amount_out,
+                quote_reserve: quote_reserve
+                    .checked_add(quote_reserve_amount)
+                    .ok_or(ErrorCode::MathOverflow)?,
+                lp_fee_amount,
+                protocol_fee_amount,
+            })
+        }
+        PoolSide::Base => {
+            let swap_quote_amount = invariant.sell_quote(amount_in, base_reserve, quote_reserve)?;
+            let lp_fee_amount = curve::fee_amount(swap_quote_amount, fees.lp_fee_bps)?;
+            let quote_reserve_amount = swap_quote_amount - lp_fee_amount;
+            let protocol_fee_amount =
+                curve::apply_bps(quote_reserve_amount, fees.protocol_fee_bps, Rounding::Down)?;
+            Ok(ZapSwap {
+                amount_out: quote_reserve_amount - protocol_fee_amount,
+                base_reserve: base_reserve
+                    .checked_add(amount_in)
+                    .ok_or(ErrorCode::MathOverflow)?,
+                quote_reserve: quote_reserve - quote_reserve_amount,
+                lp_fee_amount,
+                protocol_fee_amount,
+            })
+        }
+    }
+}
+
+/// Single sided deposit: swaps the part of one token that balances the rest against the
+/// reserves, then deposits both sides as `add_liquidity` would.
+pub mod zap_in {
+    use super::*;
+    use crate::{Zap, PAUSE_DEPOSITS, PAUSE_SWAPS};
+
+    /// Deposits `amount` tokens of `side`, part of them swapped internally for the other side.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing all the accounts required to execute the operation.
+    /// - `side`: Side of the pool the tokens are deposited in.
+    /// - `amount`: Amount of tokens deposited.
+    /// - `min_lp_shares`: Minimum number of liquidity provider (LP) shares the user expects to receive.
+    ///
+    /// # Returns:
+    /// - Result indicating success or error state.
+    pub fn handler(
+        ctx: Context<Zap>,
+        side: PoolSide,
+        amount: u64,
+        min_lp_shares: u64,
+    ) -> Result<()> {
+        let amm = &ctx.accounts.amm;
+        amm.require_not_paused(&ctx.accounts.global_parameters, PAUSE_DEPOSITS)?;
+        amm.require_not_paused(&ctx.accounts.global_parameters, PAUSE_SWAPS)?;
+        // The first deposit sets the price, it has to bring both sides
+        require_gt!(amm.total_shares, 0, ErrorCode::InsufficientLiquidity);
+        require_gt!(amount, 0, ErrorCode::InvalidTokenAmounts);
+
+        let (mint, user_ata, reserve_ata, token_program) = match side {
+            PoolSide::Base => (
+                &ctx.accounts.base_mint,
+                &ctx.accounts.user_base_ata,
+                &ctx.accounts.base_reserve_ata,
+                &ctx.accounts.base_token_program,
+            ),
+            PoolSide::Quote => (
+                &ctx.accounts.quote_mint,
+                &ctx.accounts.user_quote_ata,
+                &ctx.accounts.quote_reserve_ata,
+                &ctx.accounts.quote_token_program,
+            ),
+        };
+
+        // Only what reaches the vault after Token-2022 transfer fees is credited to the pool
+        let deposit_amount = amount_after_transfer_fee(&mint.to_account_info(), amount)?;
+
+        // Swap the part of the deposit that balances the rest against the reserves after the swap
+        let now = Clock::get()?.unix_timestamp;
+        let fees = amm.fee_config(&ctx.accounts.global_parameters);
+        let invariant = amm.invariant(now);
+        let quote_swap = |swap_amount| {
+            quote_zap_swap(
+                invariant,
+                &fees,
+                side,
+                swap_amount,
+                amm.base_reserve,
+                amm.quote_reserve,
+            )
+        };
+        let swap_amount = curve::zap_swap_amount(deposit_amount, |swap_amount| {
+            let swap = quote_swap(swap_amount)?;
+            Ok(match side {
+                PoolSide::Base => (
+                    deposit_amount - swap_amount,
+                    swap.amount_out,
+                    swap.base_reserve,
+                    swap.quote_reserve,
+                ),
+                PoolSide::Quote => (
+                    deposit_amount - swap_amount,
+                    swap.amount_out,
+                    swap.quote_reserve,
+                    swap.base_reserve,
+                ),
+            })
+        })?;
+        let swap = quote_swap(swap_amount)?;
+        let (base_amount, quote_amount) = match side {
+            PoolSide::Base => (deposit_amount - swap_amount, swap.amount_out),
+            PoolSide::Quote => (swap.amount_out, deposit_amount - swap_amount),
+        };
+
+        // Apply the swap first, so the protocol's share of its LP fee accrues with the rest
+        let amm = &mut ctx.accounts.amm;
+        amm.update_price_accumulators(now);
+        amm.base_reserve = swap.base_reserve;
+        amm.quote_reserve = swap.quote_reserve;
+        let protocol_fee_shares = amm.accrue_protocol_fee()?;
+
+        // Calculate the shares of the balanced deposit
+        let shares = invariant.calculate_shares(
+            base_amount,
+            quote_amount,
+            amm.base_reserve,
+            amm.quote_reserve,
+            amm.total_shares,
+        )?;
+        require_gte!(shares, min_lp_shares, ErrorCode::InsufficientLiquidity);
+        amm.base_reserve = amm
+            .base_reserve
+            .checked_add(base_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        amm.quote_reserve = amm
+            .quote_reserve
+            .checked_add(quote_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        amm.total_shares = amm
+            .total_shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+        amm.update_liquidity_last(&ctx.accounts.global_parameters, now)?;
+
+        // Transfer the deposit to the reserve of its side
+        spl_token_transfer(TokenTransferParams {
+            source: user_ata.to_account_info(),
+            destination: reserve_ata.to_account_info(),
+            amount,
+            authority: ctx.accounts.user.to_account_info(),
+            authority_signer_seeds: &[],
+            decimals: mint.decimals,
+            mint: mint.to_account_info(),
+            token_program: token_program.to_account_info(),
+        })?;
+
+        let signer_seeds = [
+            b"amm",
+            ctx.accounts.amm.creator.as_ref(),
+            ctx.accounts.base_mint.to_account_info().key.as_ref(),
+            ctx.accounts.quote_mint.to_account_info().key.as_ref(),
+            &[ctx.bumps.amm],
+        ];
+
+        // Transfer the protocol fee of the swap from the quote reserve to the fee receiver
+        if swap.protocol_fee_amount > 0 {
+            spl_token_transfer(TokenTransferParams {
+                source: ctx.accounts.quote_reserve_ata.to_account_info(),
+                destination: ctx.accounts.fee_receiver_ata.to_account_info(),
+                amount: swap.protocol_fee_amount,
+                authority: ctx.accounts.amm.to_account_info(),
+                authority_signer_seeds: &signer_seeds,
+                decimals: ctx.accounts.quote_mint.decimals,
+                mint: ctx.accounts.quote_mint.to_account_info(),
+                token_program: ctx.accounts.quote_token_program.to_account_info(),
+            })?;
+        }
+
+        // Mint the protocol's share of the accrued fees to the treasury
+        if protocol_fee_shares > 0 {
+            let treasury_lp_ata = ctx
+                .accounts
+                .treasury_lp_ata
+                .as_ref()
+                .ok_or(ErrorCode::MissingTreasuryLpAccount)?;
+            anchor_spl::token_interface::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.lp_token_program.to_account_info(),
+                    anchor_spl::token_interface::MintTo {
+                        mint: ctx.accounts.lp_mint.to_account_info(),
+                        to: treasury_lp_ata.to_account_info(),
+                        authority: ctx.accounts.amm.to_account_info(),
+                    },
+                    &[&signer_seeds],
+                ),
+                protocol_fee_shares,
+            )?;
+        }
+
+        // Mint the shares to the user
+        anchor_spl::token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.lp_token_program.to_account_info(),
+                anchor_spl::token_interface::MintTo {
+                    mint: ctx.accounts.lp_mint.to_account_info(),
+                    to: ctx.accounts.user_lp_ata.to_account_info(),
+                    authority: ctx.accounts.amm.to_account_info(),
+                },
+                &[&signer_seeds],
+            ),
+            shares,
+        )?;
+
+        // Make sure the vaults still back the recorded reserves
+        check_reserve_balances(
+            &ctx.accounts.amm,
+            &mut ctx.accounts.base_reserve_ata,
+            &mut ctx.accounts.quote_reserve_ata,
+        )?;
+
+        // Emit the event
+        emit_cpi!(ZapInEvent {
+            side,
+            amount: deposit_amount,
+            swap_amount,
+            base_amount,
+            quote_amount,
+            shares,
+            lp_fee_amount: swap.lp_fee_amount,
+            protocol_fee_amount: swap.protocol_fee_amount,
+            protocol_fee_shares,
+            timestamp: now as u64,
+            user: *ctx.accounts.user.key,
+        });
+
+        emit!(ZapInEvent {
+            side,
+            amount: deposit_amount,
+            swap_amount,
+            base_amount,
+            quote_amount,
+            shares,
+            lp_fee_amount: swap.lp_fee_amount,
+            protocol_fee_amount: swap.protocol_fee_amount,
+            protocol_fee_shares,
+            timestamp: now as u64,
+            user: *ctx.accounts.user.key,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::tests::setup_test_environment;
+    use crate::fixtures::tests::TestEnvironment;
+    use crate::PoolSide;
+    use anchor_lang::AnchorSerialize;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+    use std::str::FromStr;
+    use switchboard_solana::Pubkey;
+
+    fn zap_instruction(
+        setup: &TestEnvironment,
+        name: &str,
+        side: PoolSide,
+        amount: u64,
+        min_amount_out: u64,
+    ) -> Instruction {
+        let payer = setup.keypair.pubkey();
+        let fee_authority = Pubkey::find_program_address(&[b"fee_authority"], &setup.program_id).0;
+        let mut data = switchboard_solana::get_ixn_discriminator(name).to_vec();
+        side.serialize(&mut data).unwrap();
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.extend_from_slice(&min_amount_out.to_le_bytes());
+        Instruction {
+            program_id: setup.program_id,
+            accounts: vec![
+                AccountMeta::new(setup.amm_account, false),
+                AccountMeta::new_readonly(setup.global_parameters, false),
+                AccountMeta::new_readonly(payer, true),
+                AccountMeta::new(setup.user_base, false),
+                AccountMeta::new(setup.user_quote, false),
+                AccountMeta::new(setup.base_vault, false),
+                AccountMeta::new(setup.quote_vault, false),
+                AccountMeta::new(
+                    spl_associated_token_account::get_associated_token_address_with_program_id(
+                        &fee_authority,
+                        &setup.quote_mint,
+                        &spl_token::ID,
+                    ),
+                    false,
+                ),
+                AccountMeta::new(setup.user_lp, false),
+                AccountMeta::new(setup.lp_mint, false),
+                AccountMeta::new(setup.base_mint, false),
+                AccountMeta::new(setup.quote_mint, false),
+                AccountMeta::new_readonly(spl_token_2022::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                // No treasury LP account, fees are charged on top
+                AccountMeta::new_readonly(setup.program_id, false),
+                AccountMeta::new_readonly(
+                    Pubkey::from_str("38C9cb9ak6zRdtA3ZxKPp9sYAPEKT9KfZcUcdC5Tda69").unwrap(),
+                    false,
+                ),
+                AccountMeta::new_readonly(setup.program_id, false),
+            ],
+            data,
+        }
+    }
+
+    async fn lp_balance(setup: &TestEnvironment) -> u64 {
+        setup
+            .client
+            .get_token_account_balance(&setup.user_lp)
+            .await
+            .unwrap()
+            .amount
+            .parse::<u64>()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_zap_in_and_out_quote() {
+        let setup = setup_test_environment(true).await;
+        let lp_before = lp_balance(&setup).await;
+
+        let ix = zap_instruction(&setup, "zap_in", PoolSide::Quote, 1_000_000, 1);
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        setup
+            .client
+            .send_and_confirm_transaction(&tx)
+            .await
+            .unwrap();
+        let shares = lp_balance(&setup).await - lp_before;
+        assert!(shares > 0, "The zap should mint shares");
+
+        // Withdrawing the shares into quote only gives back less than deposited, the swap
+        // fees being paid both ways
+        let ix = zap_instruction(&setup, "zap_out", PoolSide::Quote, shares, 1_000_000);
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        assert!(setup
+            .client
+            .send_and_confirm_transaction(&tx)
+            .await
+            .is_err());
+
+        let ix = zap_instruction(&setup, "zap_out", PoolSide::Quote, shares, 1);
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&setup.keypair.pubkey()));
+        tx.sign(
+            &[&setup.keypair],
+            setup.client.get_latest_blockhash().await.unwrap(),
+        );
+        setup
+            .client
+            .send_and_confirm_transaction(&tx)
+            .await
+            .unwrap();
+        assert_eq!(lp_balance(&setup).await, lp_before);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::error::ErrorCode;
+use crate::instructions::zap_in::quote_zap_swap;
+use crate::utils::{
+    amount_after_transfer_fee, check_reserve_balances, spl_token_transfer, TokenTransferParams,
+};
+use crate::PoolSide;
+use anchor_lang::prelude::*;
+
+// ZapOutEvent event
+#[event]
+pub struct ZapOutEvent {
+    pub side: PoolSide,
+    pub shares: u64,
+    pub base_amount: u64, // withdrawn before the swap
+    pub quote_amount: u64,
+    pub swap_amount: u64, // withdrawn tokens of the other side swapped into `side`
+    pub amount: u64,      // received by the user after transfer fees
+    pub lp_fee_amount: u64,
+    pub protocol_fee_amount: u64,
+    pub protocol_fee_shares: u64,
+    pub timestamp: u64,
+    pub user: Pubkey,
+}
+
+/// Single sided withdrawal: removes liquidity as `remove_liquidity` would, then swaps the
+/// withdrawn tokens of the other side into one token.
+pub mod zap_out {
+    use super::*;
+    use crate::{Zap, PAUSE_SWAPS, PAUSE_WITHDRAWALS};
+
+    /// Burns `shares` and pays them out in tokens of `side` only.
+    ///
+    /// # Parameters:
+    /// - `ctx`: Context containing all the accounts required to execute the operation.
+    /// - `side`: Side of the pool the liquidity is withdrawn into.
+    /// - `shares`: The number of liquidity shares the user wants to remove.
+    /// - `min_amount_out`: The minimum amount of tokens of `side` the user expects to receive.
+    ///
+    /// # Returns:
+    /// - Result indicating success or an error.
+    pub fn handler(
+        ctx: Context<Zap>,
+        side: PoolSide,
+        shares: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        let global_parameters = &ctx.accounts.global_parameters;
+        ctx.accounts
+            .amm
+            .require_not_paused(global_parameters, PAUSE_WITHDRAWALS)?;
+        ctx.accounts
+            .amm
+            .require_not_paused(global_parameters, PAUSE_SWAPS)?;
+
+        // Pay the protocol its share of the fees accrued since the last liquidity change
+        let now = Clock::get()?.unix_timestamp;
+        let fees = ctx.accounts.amm.fee_config(global_parameters);
+        let amm = &mut ctx.accounts.amm;
+        let protocol_fee_shares = amm.accrue_protocol_fee()?;
+
+        // Remove the shares' part of both reserves
+        let invariant = amm.invariant(now);
+        let (base_amount, quote_amount) = invariant.remove_quote(
+            shares,
+            amm.base_reserve,
+            amm.quote_reserve,
+            amm.total_shares,
+        )?;
+        amm.update_price_accumulators(now);
+        amm.base_reserve = amm
+            .base_reserve
+            .checked_sub(base_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        amm.quote_reserve = amm
+            .quote_reserve
+            .checked_sub(quote_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        amm.total_shares = amm
+            .total_shares
+            .checked_sub(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+        // Recorded before the swap, so the next accrual counts the swap's LP fee
+        amm.update_liquidity_last(global_parameters, now)?;
+
+        // Swap the withdrawn tokens of the other side into `side`
+        let (side_in, swap_amount, kept_amount) = match side {
+            PoolSide::Base => (PoolSide::Quote, quote_amount, base_amount),
+            PoolSide::Quote => (PoolSide::Base, base_amount, quote_amount),
+        };
+        let swap = quote_zap_swap(
+            invariant,
+            &fees,
+            side_in,
+            swap_amount,
+            amm.base_reserve,
+            amm.quote_reserve,
+        )?;
+        amm.base_reserve = swap.base_reserve;
+        amm.quote_reserve = swap.quote_reserve;
+        let withdrawn_amount = kept_amount
+            .checked_add(swap.amount_out)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let (mint, user_ata, reserve_ata, token_program) = match side {
+            PoolSide::Base => (
+                &ctx.accounts.base_mint,
+                &ctx.accounts.user_base_ata,
+                &ctx.accounts.base_reserve_ata,
+                &ctx.accounts.base_token_program,
+            ),
+            PoolSide::Quote => (
+                &ctx.accounts.quote_mint,
+                &ctx.accounts.user_quote_ata,
+                &ctx.accounts.quote_reserve_ata,
+                &ctx.accounts.quote_token_program,
+            ),
+        };
+
+        // The user receives the withdrawn amount minus any Token-2022 transfer fee
+        let amount = amount_after_transfer_fee(&mint.to_account_info(), withdrawn_amount)?;
+        match side {
+            PoolSide::Base => require!(amount >= min_amount_out, ErrorCode::BaseAmountTooLow),
+            PoolSide::Quote => require!(amount >= min_amount_out, ErrorCode::QuoteAmountTooLow),
+        }
+
+        let signer_seeds = [
+            b"amm",
+            ctx.accounts.amm.creator.as_ref(),
+            ctx.accounts.base_mint.to_account_info().key.as_ref(),
+            ctx.accounts.quote_mint.to_account_info().key.as_ref(),
+            &[ctx.bumps.amm],
+        ];
+
+        // Mint the protocol's share of the accrued fees to the treasury
+        if protocol_fee_shares > 0 {
+            let treasury_lp_ata = ctx
+                .accounts
+                .treasury_lp_ata
+                .as_ref()
+                .ok_or(ErrorCode::MissingTreasuryLpAccount)?;
+            anchor_spl::token_interface::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.lp_token_program.to_account_info(),
+                    anchor_spl::token_interface::MintTo {
+                        mint: ctx.accounts.lp_mint.to_account_info(),
+                        to: treasury_lp_ata.to_account_info(),
+                        authority: ctx.accounts.amm.to_account_info(),
+                    },
+                    &[&signer_seeds],
+                ),
+                protocol_fee_shares,
+            )?;
+        }
+
+        // Burn the LP shares to reflect the removal of liquidity
+        anchor_spl::token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.lp_token_program.to_account_info(),
+                anchor_spl::token_interface::Burn {
+                    mint: ctx.accounts.lp_mint.to_account_info(),
+                    from: ctx.accounts.user_lp_ata.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            shares,
+        )?;
+
+        // Transfer the withdrawn tokens from the reserve of `side` to the user
+        spl_token_transfer(TokenTransferParams {
+            source: reserve_ata.to_account_info(),
+            destination: user_ata.to_account_info(),
+            amount: withdrawn_amount,
+            authority: ctx.accounts.amm.to_account_info(),
+            authority_signer_seeds: &signer_seeds,
+            decimals: mint.decimals,
+            mint: mint.to_account_info(),
+            token_program: token_program.to_account_info(),
+        })?;
+
+        // Transfer the protocol fee of the swap from the quote reserve to the fee receiver
+        if swap.protocol_fee_amount > 0 {
+            spl_token_transfer(TokenTransferParams {
+                source: ctx.accounts.quote_reserve_ata.to_account_info(),
+                destination: ctx.accounts.fee_receiver_ata.to_account_info(),
+                amount: swap.protocol_fee_amount,
+                authority: ctx.accounts.amm.to_account_info(),
+                authority_signer_seeds: &signer_seeds,
+                decimals: ctx.accounts.quote_mint.decimals,
+                mint: ctx.accounts.quote_mint.to_account_info(),
+                token_program: ctx.accounts.quote_token_program.to_account_info(),
+            })?;
+        }
+
+        // Make sure the vaults still back the recorded reserves
+        check_reserve_balances(
+            &ctx.accounts.amm,
+            &mut ctx.accounts.base_reserve_ata,
+            &mut ctx.accounts.quote_reserve_ata,
+        )?;
+
+        // Emit the event
+        emit_cpi!(ZapOutEvent {
+            side,
+            shares,
+            base_amount,
+            quote_amount,
+            swap_amount,
+            amount,
+            lp_fee_amount: swap.lp_fee_amount,
+            protocol_fee_amount: swap.protocol_fee_amount,
+            protocol_fee_shares,
+            timestamp: now as u64,
+            user: *ctx.accounts.user.key,
+        });
+
+        emit!(ZapOutEvent {
+            side,
+            shares,
+            base_amount,
+            quote_amount,
+            swap_amount,
+            amount,
+            lp_fee_amount: swap.lp_fee_amount,
+            protocol_fee_amount: swap.protocol_fee_amount,
+            protocol_fee_shares,
+            timestamp: now as u64,
+            user: *ctx.accounts.user.key,
+        });
+
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+pub mod curve;
 pub mod error;
 pub mod fixtures;
 pub mod instructions;
@@ -12,8 +13,54 @@ declare_id!("GuA9pdtCqntcxCCWr2W8WbD7YdN7RDCni1JrFbzibENg");
 pub mod solana_amm {
     use super::*;
 
-    pub fn create(ctx: Context<Create>) -> Result<()> {
-        instructions::create::create::handler(ctx)
+    pub fn create(ctx: Context<Create>, amp: Option<u64>) -> Result<()> {
+        instructions::create::create::handler(ctx, amp)
+    }
+
+    pub fn migrate(
+        ctx: Context<Migrate>,
+        amp: Option<u64>,
+        base_amount: u64,
+        quote_amount: u64,
+        lp: MigrationLp,
+    ) -> Result<()> {
+        instructions::migrate::migrate::handler(ctx, amp, base_amount, quote_amount, lp)
+    }
+
+    pub fn lock_liquidity(
+        ctx: Context<LockLiquidity>,
+        amount: u64,
+        unlock_timestamp: Option<i64>,
+    ) -> Result<()> {
+        instructions::lock_liquidity::lock_liquidity::handler(ctx, amount, unlock_timestamp)
+    }
+
+    pub fn unlock_liquidity(ctx: Context<LockedLiquidity>, amount: u64) -> Result<()> {
+        instructions::unlock_liquidity::unlock_liquidity::handler(ctx, amount)
+    }
+
+    pub fn claim_locked_fees(ctx: Context<LockedLiquidity>) -> Result<()> {
+        instructions::claim_locked_fees::claim_locked_fees::handler(ctx)
+    }
+
+    pub fn create_vesting(
+        ctx: Context<CreateVesting>,
+        amount: u64,
+        start_timestamp: i64,
+        cliff_timestamp: i64,
+        end_timestamp: i64,
+    ) -> Result<()> {
+        instructions::create_vesting::create_vesting::handler(
+            ctx,
+            amount,
+            start_timestamp,
+            cliff_timestamp,
+            end_timestamp,
+        )
+    }
+
+    pub fn claim_vested(ctx: Context<ClaimVested>) -> Result<()> {
+        instructions::claim_vested::claim_vested::handler(ctx)
     }
 
     pub fn propose_admin(ctx: Context<ProposeAdmin>) -> Result<()> {
@@ -24,18 +71,66 @@ pub mod solana_amm {
         instructions::accept_admin::accept_admin::handler(ctx)
     }
 
+    pub fn cancel_admin_proposal(ctx: Context<CancelAdminProposal>) -> Result<()> {
+        instructions::cancel_admin_proposal::cancel_admin_proposal::handler(ctx)
+    }
+
+    pub fn set_treasury(ctx: Context<SetTreasury>, treasury: Pubkey) -> Result<()> {
+        instructions::set_treasury::set_treasury::handler(ctx, treasury)
+    }
+
+    pub fn update_fee_receiver(ctx: Context<UpdateFeeReceiver>) -> Result<()> {
+        instructions::update_fee_receiver::update_fee_receiver::handler(ctx)
+    }
+
+    pub fn withdraw_protocol_fees(ctx: Context<WithdrawProtocolFees>) -> Result<()> {
+        instructions::withdraw_protocol_fees::withdraw_protocol_fees::handler(ctx)
+    }
+
+    pub fn grant_roles(ctx: Context<GrantRoles>, roles: u8) -> Result<()> {
+        instructions::grant_roles::grant_roles::handler(ctx, roles)
+    }
+
+    pub fn revoke_roles(ctx: Context<RevokeRoles>, roles: u8) -> Result<()> {
+        instructions::revoke_roles::revoke_roles::handler(ctx, roles)
+    }
+
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        instructions::register_referrer::register_referrer::handler(ctx)
+    }
+
+    pub fn set_referrer_fee_tier(
+        ctx: Context<SetReferrerFeeTier>,
+        fee_tier: Option<ReferrerFeeTier>,
+    ) -> Result<()> {
+        instructions::set_referrer_fee_tier::set_referrer_fee_tier::handler(ctx, fee_tier)
+    }
+
     pub fn add_liquidity(
         ctx: Context<AddLiquidity>,
-        base_amount: u64,
-        quote_amount: u64,
+        max_base: u64,
+        max_quote: u64,
         min_lp_shares: u64,
     ) -> Result<()> {
-        instructions::add_liquidity::add_liquidity::handler(
-            ctx,
-            base_amount,
-            quote_amount,
-            min_lp_shares,
-        )
+        instructions::add_liquidity::add_liquidity::handler(ctx, max_base, max_quote, min_lp_shares)
+    }
+
+    pub fn zap_in(
+        ctx: Context<Zap>,
+        side: PoolSide,
+        amount: u64,
+        min_lp_shares: u64,
+    ) -> Result<()> {
+        instructions::zap_in::zap_in::handler(ctx, side, amount, min_lp_shares)
+    }
+
+    pub fn zap_out(
+        ctx: Context<Zap>,
+        side: PoolSide,
+        shares: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        instructions::zap_out::zap_out::handler(ctx, side, shares, min_amount_out)
     }
 
     pub fn remove_liquidity(
@@ -62,6 +157,14 @@ pub mod solana_amm {
         instructions::buy::buy::handler(ctx, base_amount, max_quote_amount)
     }
 
+    pub fn buy_exact_in<'a>(
+        ctx: Context<'_, '_, '_, 'a, Buy<'a>>,
+        quote_amount: u64,
+        min_base_amount: u64,
+    ) -> Result<()> {
+        instructions::buy_exact_in::buy_exact_in::handler(ctx, quote_amount, min_base_amount)
+    }
+
     pub fn sell<'a>(
         ctx: Context<'_, '_, '_, 'a, Sell<'a>>,
         base_amount: u64,
@@ -70,17 +173,187 @@ pub mod solana_amm {
         instructions::sell::sell::handler(ctx, base_amount, max_quote_amount)
     }
 
-    pub fn set_parameters(
-        ctx: Context<SetParameters>,
-        protocol_fee_bps: u64,
-        referrer_fee_bps: u64,
-        referrer_rebate_bps: u64,
+    pub fn sell_exact_out<'a>(
+        ctx: Context<'_, '_, '_, 'a, Sell<'a>>,
+        quote_amount: u64,
+        max_base_amount: u64,
+    ) -> Result<()> {
+        instructions::sell_exact_out::sell_exact_out::handler(ctx, quote_amount, max_base_amount)
+    }
+
+    pub fn route<'a>(
+        ctx: Context<'_, '_, 'a, 'a, Route<'a>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        directions: Vec<SwapDirection>,
+    ) -> Result<()> {
+        instructions::route::route::handler(ctx, amount_in, min_amount_out, directions)
+    }
+
+    pub fn queue_parameters(
+        ctx: Context<QueueParameters>,
+        parameters: QueuedParameters,
+    ) -> Result<()> {
+        instructions::queue_parameters::queue_parameters::handler(ctx, parameters)
+    }
+
+    pub fn execute_parameters(ctx: Context<ExecuteParameters>) -> Result<()> {
+        instructions::execute_parameters::execute_parameters::handler(ctx)
+    }
+
+    pub fn cancel_parameters(ctx: Context<CancelParameters>) -> Result<()> {
+        instructions::cancel_parameters::cancel_parameters::handler(ctx)
+    }
+
+    pub fn queue_pool_parameters(
+        ctx: Context<QueuePoolParameters>,
+        fee_config: Option<FeeConfig>,
     ) -> Result<()> {
-        instructions::set_parameters::set_parameters::handler(
+        instructions::queue_pool_parameters::queue_pool_parameters::handler(ctx, fee_config)
+    }
+
+    pub fn execute_pool_parameters(ctx: Context<ExecutePoolParameters>) -> Result<()> {
+        instructions::execute_pool_parameters::execute_pool_parameters::handler(ctx)
+    }
+
+    pub fn cancel_pool_parameters(ctx: Context<CancelPoolParameters>) -> Result<()> {
+        instructions::cancel_pool_parameters::cancel_pool_parameters::handler(ctx)
+    }
+
+    pub fn observe(ctx: Context<Observe>) -> Result<Observation> {
+        instructions::observe::observe::handler(ctx)
+    }
+
+    pub fn observe_at(ctx: Context<ObserveAt>, timestamp: i64) -> Result<Observation> {
+        instructions::observe_at::observe_at::handler(ctx, timestamp)
+    }
+
+    pub fn initialize_observations(ctx: Context<InitializeObservations>) -> Result<()> {
+        instructions::initialize_observations::initialize_observations::handler(ctx)
+    }
+
+    pub fn grow_observations(ctx: Context<GrowObservations>, capacity: u16) -> Result<()> {
+        instructions::grow_observations::grow_observations::handler(ctx, capacity)
+    }
+
+    pub fn ramp_amp(ctx: Context<RampAmp>, target_amp: u64, ramp_stop_ts: i64) -> Result<()> {
+        instructions::ramp_amp::ramp_amp::handler(ctx, target_amp, ramp_stop_ts)
+    }
+
+    pub fn pause(ctx: Context<SetPause>, flags: u8) -> Result<()> {
+        instructions::pause::pause::handler(ctx, flags)
+    }
+
+    pub fn unpause(ctx: Context<SetPause>, flags: u8) -> Result<()> {
+        instructions::unpause::unpause::handler(ctx, flags)
+    }
+
+    pub fn pause_pool(ctx: Context<SetPoolPause>, flags: u8) -> Result<()> {
+        instructions::pause_pool::pause_pool::handler(ctx, flags)
+    }
+
+    pub fn unpause_pool(ctx: Context<SetPoolPause>, flags: u8) -> Result<()> {
+        instructions::unpause_pool::unpause_pool::handler(ctx, flags)
+    }
+
+    pub fn flash_borrow(
+        ctx: Context<FlashBorrow>,
+        base_amount: u64,
+        quote_amount: u64,
+    ) -> Result<()> {
+        instructions::flash_borrow::flash_borrow::handler(ctx, base_amount, quote_amount)
+    }
+
+    pub fn flash_repay(ctx: Context<FlashRepay>) -> Result<()> {
+        instructions::flash_repay::flash_repay::handler(ctx)
+    }
+
+    pub fn sync(ctx: Context<SyncReserves>) -> Result<()> {
+        instructions::sync::sync::handler(ctx)
+    }
+
+    pub fn create_cl_pool(
+        ctx: Context<CreateClPool>,
+        tick_spacing: u16,
+        fee_bps: u16,
+        sqrt_price_x64: u128,
+    ) -> Result<()> {
+        instructions::create_cl_pool::create_cl_pool::handler(
+            ctx,
+            tick_spacing,
+            fee_bps,
+            sqrt_price_x64,
+        )
+    }
+
+    pub fn initialize_tick_array(
+        ctx: Context<InitializeTickArray>,
+        start_tick_index: i32,
+    ) -> Result<()> {
+        instructions::initialize_tick_array::initialize_tick_array::handler(ctx, start_tick_index)
+    }
+
+    pub fn open_position(
+        ctx: Context<OpenPosition>,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<()> {
+        instructions::open_position::open_position::handler(ctx, tick_lower, tick_upper)
+    }
+
+    pub fn increase_liquidity(
+        ctx: Context<ModifyPosition>,
+        liquidity: u128,
+        max_base_amount: u64,
+        max_quote_amount: u64,
+    ) -> Result<()> {
+        instructions::increase_liquidity::increase_liquidity::handler(
             ctx,
-            protocol_fee_bps,
-            referrer_fee_bps,
-            referrer_rebate_bps,
+            liquidity,
+            max_base_amount,
+            max_quote_amount,
         )
     }
+
+    pub fn decrease_liquidity(
+        ctx: Context<ModifyPosition>,
+        liquidity: u128,
+        min_base_amount: u64,
+        min_quote_amount: u64,
+    ) -> Result<()> {
+        instructions::decrease_liquidity::decrease_liquidity::handler(
+            ctx,
+            liquidity,
+            min_base_amount,
+            min_quote_amount,
+        )
+    }
+
+    pub fn collect_fees(ctx: Context<ModifyPosition>) -> Result<()> {
+        instructions::collect_fees::collect_fees::handler(ctx)
+    }
+
+    pub fn cl_swap<'a>(
+        ctx: Context<'_, '_, 'a, 'a, ClSwap<'a>>,
+        direction: SwapDirection,
+        amount_in: u64,
+        min_amount_out: u64,
+        sqrt_price_limit_x64: u128,
+    ) -> Result<()> {
+        instructions::cl_swap::cl_swap::handler(
+            ctx,
+            direction,
+            amount_in,
+            min_amount_out,
+            sqrt_price_limit_x64,
+        )
+    }
+
+    pub fn upgrade_amm(ctx: Context<UpgradeAmm>) -> Result<()> {
+        instructions::upgrade_amm::upgrade_amm::handler(ctx)
+    }
+
+    pub fn upgrade_global_parameters(ctx: Context<UpgradeGlobalParameters>) -> Result<()> {
+        instructions::upgrade_global_parameters::upgrade_global_parameters::handler(ctx)
+    }
 }
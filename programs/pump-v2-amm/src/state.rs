@@ -1,4 +1,12 @@
+use crate::curve::{
+    locked_fee_shares, mul_div_u256, protocol_fee_shares, vested_amount, Invariant, Rounding,
+};
+use crate::error::ErrorCode;
+use anchor_lang::prelude::borsh;
 use anchor_lang::prelude::*;
+use num_integer::Roots;
+/// Fields are only ever appended, accounts created with an older layout are grown in place by
+/// `upgrade_amm`.
 #[account]
 pub struct Amm {
     pub base_mint: Pubkey,  // 32
@@ -11,8 +19,448 @@ pub struct Amm {
     pub base_reserve_ata: Pubkey,
     pub quote_reserve_ata: Pubkey,
     pub fee_receiver_ata: Pubkey,
+    pub fee_config: Option<FeeConfig>, // overrides the global fees when set
+    pub curve: Curve,
+    pub base_price_cumulative_x64: u128, // sum of quote per base price * seconds, Q64.64, wrapping
+    pub quote_price_cumulative_x64: u128, // sum of base per quote price * seconds, Q64.64, wrapping
+    pub last_price_update_ts: i64,
+    pub liquidity_cumulative: u128, // sum of sqrt(base_reserve * quote_reserve) * seconds, wrapping
+    pub flash_loan: Option<FlashLoan>, // outstanding loan between flash_borrow and flash_repay
+    pub paused: u8, // PAUSE_* operations halted on this pool on top of the global ones
+    pub liquidity_last: u128, // liquidity after the last deposit or withdrawal, 0 unless FeeMode::LpShare
+    pub liquidity_last_amp: u64, // amp liquidity_last was measured at, 0 for a constant product pool
+    pub fee_mode: FeeMode, // global fee mode at the last deposit or withdrawal, charged until the next
 }
 
+impl Amm {
+    /// Size of the pools created before any field was appended to the original layout.
+    pub const V1_LEN: usize = 8 + 7 * 32 + 3 * 8;
+    /// Size of the pools created with the current layout.
+    pub const LEN: usize = 8 + std::mem::size_of::<Amm>();
+
+    /// Fails with `Paused` if any of the `flags` operations is paused on this pool or globally.
+    pub fn require_not_paused(
+        &self,
+        global_parameters: &GlobalParameters,
+        flags: u8,
+    ) -> Result<()> {
+        global_parameters.require_not_paused(flags)?;
+        require!(self.paused & flags == 0, ErrorCode::Paused);
+        Ok(())
+    }
+
+    /// Price accumulators brought forward to `now` with the current reserves.
+    ///
+    /// Each price is added once per second since the last update, so the average price over a
+    /// window is the difference of two observations divided by the time between them.
+    pub fn observe(&self, now: i64) -> Observation {
+        let elapsed = now.saturating_sub(self.last_price_update_ts).max(0) as u128;
+        let (
+            mut base_price_cumulative_x64,
+            mut quote_price_cumulative_x64,
+            mut liquidity_cumulative,
+        ) = (
+            self.base_price_cumulative_x64,
+            self.quote_price_cumulative_x64,
+            self.liquidity_cumulative,
+        );
+        // An empty pool has no price to accumulate
+        if elapsed > 0 && self.base_reserve > 0 && self.quote_reserve > 0 {
+            let base_price_x64 = ((self.quote_reserve as u128) << 64) / self.base_reserve as u128;
+            let quote_price_x64 = ((self.base_reserve as u128) << 64) / self.quote_reserve as u128;
+            base_price_cumulative_x64 =
+                base_price_cumulative_x64.wrapping_add(base_price_x64.wrapping_mul(elapsed));
+            quote_price_cumulative_x64 =
+                quote_price_cumulative_x64.wrapping_add(quote_price_x64.wrapping_mul(elapsed));
+            let liquidity = (self.base_reserve as u128 * self.quote_reserve as u128).sqrt();
+            liquidity_cumulative = liquidity_cumulative.wrapping_add(liquidity * elapsed);
+        }
+        Observation {
+            timestamp: now,
+            base_price_cumulative_x64,
+            quote_price_cumulative_x64,
+            liquidity_cumulative,
+        }
+    }
+
+    /// Accumulates the prices up to `now`. Called before every reserve change, so each price
+    /// is weighted by how long the reserves stayed at it.
+    pub fn update_price_accumulators(&mut self, now: i64) {
+        let observation = self.observe(now);
+        self.base_price_cumulative_x64 = observation.base_price_cumulative_x64;
+        self.quote_price_cumulative_x64 = observation.quote_price_cumulative_x64;
+        self.liquidity_cumulative = observation.liquidity_cumulative;
+        self.last_price_update_ts = now;
+    }
+
+    /// Invariant the pool trades on at `now`.
+    pub fn invariant(&self, now: i64) -> Invariant {
+        self.invariant_at(self.amp(now))
+    }
+
+    /// Amplification coefficient of the pool at `now`, 0 for a constant product pool.
+    pub fn amp(&self, now: i64) -> u64 {
+        match self.curve {
+            Curve::ConstantProduct => 0,
+            Curve::Stable(ramp) => ramp.amp(now),
+        }
+    }
+
+    /// Invariant of the pool's curve with the amplification coefficient fixed at `amp`.
+    ///
+    /// The liquidity of the same reserves changes with the amplification coefficient, so fees are
+    /// only measured by comparing liquidities computed at the same `amp`.
+    pub fn invariant_at(&self, amp: u64) -> Invariant {
+        match self.curve {
+            Curve::ConstantProduct => Invariant::ConstantProduct,
+            Curve::Stable(_) => Invariant::Stable { amp },
+        }
+    }
+
+    /// Fees charged by this pool: its own configuration if set, the global one otherwise.
+    ///
+    /// Under `FeeMode::LpShare` only the LP fee is charged on swaps, the protocol is paid by
+    /// `accrue_protocol_fee` instead and there is no protocol or referrer fee on top. The pool
+    /// keeps the fee mode it had at its last deposit or withdrawal, so that the fees accrued
+    /// since are all charged under one mode.
+    pub fn fee_config(&self, global_parameters: &GlobalParameters) -> FeeConfig {
+        let fees = self
+            .fee_config
+            .unwrap_or_else(|| global_parameters.fee_config());
+        match self.fee_mode {
+            FeeMode::OnTop => fees,
+            FeeMode::LpShare { .. } => FeeConfig {
+                protocol_fee_bps: 0,
+                referrer_fee_bps: 0,
+                referrer_fee_discount_bps: 0,
+                ..fees
+            },
+        }
+    }
+
+    /// Adds to `total_shares` the LP shares owed to the protocol under `FeeMode::LpShare` for
+    /// the fees accrued since the last deposit or withdrawal, and returns them for the caller
+    /// to mint to the treasury. Called before the shares of a deposit or withdrawal are priced.
+    pub fn accrue_protocol_fee(&mut self) -> Result<u64> {
+        let shares = self.pending_protocol_fee_shares()?;
+        self.total_shares = self
+            .total_shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(shares)
+    }
+
+    /// LP shares owed to the protocol but not yet added by `accrue_protocol_fee`.
+    ///
+    /// The liquidity is measured at the amplification coefficient `liquidity_last` was, so an amp
+    /// ramp does not count as fees, and charged under the fee mode the pool had then, so a change
+    /// of the global fee mode only applies from the next deposit or withdrawal on.
+    pub fn pending_protocol_fee_shares(&self) -> Result<u64> {
+        let FeeMode::LpShare { protocol_share_bps } = self.fee_mode else {
+            return Ok(0);
+        };
+        if self.liquidity_last == 0 {
+            return Ok(0);
+        }
+        let liquidity = self
+            .invariant_at(self.liquidity_last_amp)
+            .liquidity(self.base_reserve, self.quote_reserve)?;
+        protocol_fee_shares(
+            liquidity,
+            self.liquidity_last,
+            self.total_shares,
+            protocol_share_bps,
+        )
+    }
+
+    /// Liquidity of the reserves per LP share at the amplification coefficient `amp`, in Q64.64,
+    /// counting the shares owed to the protocol. At a fixed `amp` it only grows through fees, so
+    /// it measures the fees earned by locked shares.
+    pub fn liquidity_per_share_x64(&self, amp: u64) -> Result<u128> {
+        require_gt!(self.total_shares, 0, ErrorCode::InsufficientLiquidity);
+        let liquidity = self
+            .invariant_at(amp)
+            .liquidity(self.base_reserve, self.quote_reserve)?;
+        let total_shares = self.total_shares as u128 + self.pending_protocol_fee_shares()? as u128;
+        mul_div_u256(liquidity, 1 << 64, total_shares, Rounding::Down)
+    }
+
+    /// Records the liquidity left by a deposit or withdrawal, from which the next
+    /// `accrue_protocol_fee` measures the fees, and switches the pool to the global fee mode.
+    /// Kept at zero while fees are charged on top.
+    pub fn update_liquidity_last(
+        &mut self,
+        global_parameters: &GlobalParameters,
+        now: i64,
+    ) -> Result<()> {
+        self.fee_mode = global_parameters.fee_mode;
+        self.liquidity_last_amp = self.amp(now);
+        self.liquidity_last = match self.fee_mode {
+            FeeMode::OnTop => 0,
+            FeeMode::LpShare { .. } => self
+                .invariant_at(self.liquidity_last_amp)
+                .liquidity(self.base_reserve, self.quote_reserve)?,
+        };
+        Ok(())
+    }
+}
+
+/// Cumulative prices and liquidity of an `Amm` at a point in time, as returned by `observe`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Observation {
+    pub timestamp: i64,
+    pub base_price_cumulative_x64: u128,
+    pub quote_price_cumulative_x64: u128,
+    pub liquidity_cumulative: u128,
+}
+
+impl Observation {
+    pub const LEN: usize = 8 + 16 * 3;
+
+    /// Linear interpolation at `timestamp` between this observation and a later one.
+    fn interpolate(&self, later: &Observation, timestamp: i64) -> Result<Observation> {
+        let elapsed = (timestamp - self.timestamp) as u128;
+        let duration = (later.timestamp - self.timestamp) as u128;
+        let at = |before: u128, after: u128| -> Result<u128> {
+            Ok(before.wrapping_add(mul_div_u256(
+                after.wrapping_sub(before),
+                elapsed,
+                duration,
+                Rounding::Down,
+            )?))
+        };
+        Ok(Observation {
+            timestamp,
+            base_price_cumulative_x64: at(
+                self.base_price_cumulative_x64,
+                later.base_price_cumulative_x64,
+            )?,
+            quote_price_cumulative_x64: at(
+                self.quote_price_cumulative_x64,
+                later.quote_price_cumulative_x64,
+            )?,
+            liquidity_cumulative: at(self.liquidity_cumulative, later.liquidity_cumulative)?,
+        })
+    }
+
+    /// Time weighted average prices, quote per base and base per quote in Q64.64, between an
+    /// `earlier` observation and this one.
+    pub fn average_prices_since(&self, earlier: &Observation) -> Result<(u128, u128)> {
+        require_gt!(
+            self.timestamp,
+            earlier.timestamp,
+            ErrorCode::InvalidObservationWindow
+        );
+        let elapsed = (self.timestamp - earlier.timestamp) as u128;
+        Ok((
+            self.base_price_cumulative_x64
+                .wrapping_sub(earlier.base_price_cumulative_x64)
+                / elapsed,
+            self.quote_price_cumulative_x64
+                .wrapping_sub(earlier.quote_price_cumulative_x64)
+                / elapsed,
+        ))
+    }
+}
+
+/// Ring buffer of past `Observation`s of an `Amm`, written on every buy and sell that passes it.
+#[account]
+pub struct Observations {
+    pub amm: Pubkey,
+    pub capacity: u16, // entries the account has room for, raised by grow_observations
+    pub index: u16,    // slot of the most recent entry
+    pub entries: Vec<Observation>,
+}
+
+impl Observations {
+    /// Account size for `capacity` entries.
+    pub fn space(capacity: u16) -> usize {
+        8 + 32 + 2 + 2 + 4 + capacity as usize * Observation::LEN
+    }
+
+    /// Records an observation, overwriting the oldest one once the buffer is full. The first
+    /// observation of a timestamp is kept since nothing accumulates within the same second.
+    pub fn write(&mut self, observation: Observation) {
+        let Some(latest) = self.entries.get(self.index as usize) else {
+            self.entries.push(observation);
+            self.index = 0;
+            return;
+        };
+        if latest.timestamp == observation.timestamp {
+            return;
+        }
+        // Added capacity is only used once the writes reach the end of the entries, which keeps
+        // the entries after `index` the oldest ones
+        let len = self.entries.len();
+        let next = self.index as usize + 1;
+        if next == len && len < self.capacity as usize {
+            self.entries.push(observation);
+            self.index = next as u16;
+        } else {
+            self.entries[next % len] = observation;
+            self.index = (next % len) as u16;
+        }
+    }
+
+    /// Cumulative values of `amm` at `timestamp`, interpolated between the recorded observations
+    /// or brought forward from the pool's latest update.
+    pub fn observe_at(&self, amm: &Amm, timestamp: i64) -> Result<Observation> {
+        // The pool's latest update is the most recent point known
+        let current = Observation {
+            timestamp: amm.last_price_update_ts,
+            base_price_cumulative_x64: amm.base_price_cumulative_x64,
+            quote_price_cumulative_x64: amm.quote_price_cumulative_x64,
+            liquidity_cumulative: amm.liquidity_cumulative,
+        };
+        if timestamp >= current.timestamp {
+            return Ok(amm.observe(timestamp));
+        }
+
+        // Entries from the oldest to the most recent, followed by the current point
+        let len = self.entries.len();
+        let oldest = if len == 0 {
+            0
+        } else {
+            (self.index as usize + 1) % len
+        };
+        let chronological = |i: usize| {
+            if i < len {
+                &self.entries[(oldest + i) % len]
+            } else {
+                &current
+            }
+        };
+        require!(
+            timestamp >= chronological(0).timestamp,
+            ErrorCode::ObservationTooOld
+        );
+
+        // Binary search for the last point at or before the timestamp
+        let (mut low, mut high) = (0, len);
+        while low < high {
+            let middle = (low + high).div_ceil(2);
+            if chronological(middle).timestamp <= timestamp {
+                low = middle;
+            } else {
+                high = middle - 1;
+            }
+        }
+        chronological(low).interpolate(chronological(low + 1), timestamp)
+    }
+}
+
+/// Curve an `Amm` trades on, chosen at creation.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Curve {
+    /// x * y = k
+    ConstantProduct,
+    /// StableSwap invariant for pegged pairs.
+    Stable(AmpRamp),
+}
+
+/// Amplification coefficient of a stable pool, moving linearly from `initial_amp` to
+/// `target_amp` between the two timestamps.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AmpRamp {
+    pub initial_amp: u64,
+    pub target_amp: u64,
+    pub ramp_start_ts: i64,
+    pub ramp_stop_ts: i64,
+}
+
+impl AmpRamp {
+    /// A constant amplification coefficient.
+    pub fn fixed(amp: u64) -> AmpRamp {
+        AmpRamp {
+            initial_amp: amp,
+            target_amp: amp,
+            ramp_start_ts: 0,
+            ramp_stop_ts: 0,
+        }
+    }
+
+    /// Amplification coefficient at `now`.
+    pub fn amp(&self, now: i64) -> u64 {
+        if now >= self.ramp_stop_ts {
+            return self.target_amp;
+        }
+        if now <= self.ramp_start_ts {
+            return self.initial_amp;
+        }
+        let elapsed = (now - self.ramp_start_ts) as u128;
+        let duration = (self.ramp_stop_ts - self.ramp_start_ts) as u128;
+        let change = self.initial_amp.abs_diff(self.target_amp) as u128 * elapsed / duration;
+        if self.target_amp > self.initial_amp {
+            self.initial_amp + change as u64
+        } else {
+            self.initial_amp - change as u64
+        }
+    }
+}
+
+/// What `migrate` does with the LP shares of a pool's initial deposit.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MigrationLp {
+    /// Minted to the migrating user.
+    Keep,
+    /// Never minted, so the initial liquidity can never be withdrawn.
+    Burn,
+    /// Minted to the pool's locker vault and locked for the user until `unlock_timestamp`, or
+    /// forever when `None`. The fees they earn stay claimable, see `LiquidityLock`.
+    Lock { unlock_timestamp: Option<i64> },
+}
+
+/// How the protocol is paid on swaps, chosen for the whole deployment in `GlobalParameters`. Each
+/// pool switches to a new mode on its next deposit or withdrawal.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeMode {
+    /// The protocol and referrer fees are transferred from the user on top of the swap amount.
+    OnTop,
+    /// Only the LP fee is charged, inside the curve math, and the protocol takes
+    /// `protocol_share_bps` of it as LP shares minted to the treasury on the next deposit or
+    /// withdrawal, Uniswap v2 `kLast` style.
+    LpShare { protocol_share_bps: u64 },
+}
+
+impl FeeMode {
+    /// Checks the protocol share is at most the whole LP fee.
+    pub fn validate(&self) -> Result<()> {
+        if let FeeMode::LpShare { protocol_share_bps } = *self {
+            require!(
+                protocol_share_bps <= 10000,
+                ErrorCode::InvalidFeeConfiguration
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeConfig {
+    pub protocol_fee_bps: u64,
+    pub referrer_fee_bps: u64,
+    pub referrer_fee_discount_bps: u64,
+    pub lp_fee_bps: u64,
+}
+
+impl FeeConfig {
+    /// Checks the same fee invariants enforced by `queue_parameters`.
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.referrer_fee_discount_bps > self.referrer_fee_bps,
+            ErrorCode::ReferrerFeeDiscountExceedsFee
+        );
+        require!(
+            self.protocol_fee_bps > self.referrer_fee_discount_bps + self.referrer_fee_bps,
+            ErrorCode::InvalidFeeConfiguration
+        );
+        require!(self.lp_fee_bps < 10000, ErrorCode::InvalidFeeConfiguration);
+        Ok(())
+    }
+}
+
+/// Fields are only ever appended, an account created with an older layout is grown in place by
+/// `upgrade_global_parameters`.
 #[account]
 pub struct GlobalParameters {
     pub protocol_fee_bps: u64,
@@ -20,12 +468,486 @@ pub struct GlobalParameters {
     pub referrer_fee_discount_bps: u64,
     pub admin: Pubkey,
     pub proposed_admin: Pubkey,
+    pub lp_fee_bps: u64,
+    pub flash_loan_fee_bps: u64,
+    pub paused: u8,                 // PAUSE_* operations halted on every pool
+    pub timelock_delay: i64,        // seconds between queue_parameters and execute_parameters
+    pub proposed_admin_expiry: i64, // last timestamp at which proposed_admin can accept
+    pub treasury: Pubkey,           // owner of the token accounts protocol fees are withdrawn to
+    pub fee_mode: FeeMode,
+}
+
+/// Role allowed to queue, execute and cancel global parameter changes, to set the fee tiers of
+/// referrers and to withdraw the protocol fees to the treasury.
+pub const ROLE_FEE_MANAGER: u8 = 1 << 0;
+/// Role allowed to pause and unpause operations, globally and per pool.
+pub const ROLE_PAUSER: u8 = 1 << 1;
+/// Role allowed to change the parameters of a single pool, its fees and amplification.
+pub const ROLE_POOL_MANAGER: u8 = 1 << 2;
+/// Every role.
+pub const ROLE_ALL: u8 = ROLE_FEE_MANAGER | ROLE_PAUSER | ROLE_POOL_MANAGER;
+
+/// Global parameters passed to `queue_parameters`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueuedParameters {
+    pub fee_config: FeeConfig,
+    pub fee_mode: FeeMode,
+    pub flash_loan_fee_bps: u64,
+    pub timelock_delay: i64, // seconds applied to the parameter changes queued next
+}
+
+/// Global parameters queued by `queue_parameters`, applied by `execute_parameters` once
+/// `execute_after` is reached. At most one change is queued at a time, at the
+/// `[b"parameters_proposal"]` PDA, which is closed when the change is executed or cancelled.
+#[account]
+pub struct ParametersProposal {
+    pub fee_config: FeeConfig,
+    pub fee_mode: FeeMode,
+    pub flash_loan_fee_bps: u64,
+    pub timelock_delay: i64,
+    pub execute_after: i64,
+}
+
+/// Fees of a single pool queued by `queue_pool_parameters`, applied by `execute_pool_parameters`
+/// once `execute_after` is reached. At most one change is queued per pool, at the
+/// `[b"pool_parameters_proposal", amm]` PDA, which is closed when the change is executed or
+/// cancelled.
+#[account]
+pub struct PoolParametersProposal {
+    pub fee_config: Option<FeeConfig>, // None clears the pool's fee override
+    pub execute_after: i64,
+}
+
+/// LP shares locked by `owner` in the locker vault of `amm`, at the
+/// `[b"liquidity_lock", amm, owner]` PDA. The vault is the `[b"lp_locker", amm]` PDA's associated
+/// token account for the LP mint.
+#[account]
+pub struct LiquidityLock {
+    pub amm: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,                   // LP shares held in the vault for the owner
+    pub unlock_timestamp: Option<i64>, // None for a permanent lock
+    pub liquidity_per_share_x64: u128, // pool liquidity per share when the fees were last claimed
+    pub amp: u64,                      // amp liquidity_per_share_x64 was measured at
+}
+
+impl LiquidityLock {
+    /// Adds `amount` shares, locked until `unlock_timestamp` or forever when `None`. The lock
+    /// only ever gets longer: a permanent lock stays permanent and the latest timestamp wins.
+    ///
+    /// Pending fees must have been claimed first, the whole lock then earns from the liquidity per
+    /// share of `amm` at `now` on.
+    pub fn add(
+        &mut self,
+        amount: u64,
+        unlock_timestamp: Option<i64>,
+        amm: &Amm,
+        now: i64,
+    ) -> Result<()> {
+        // An empty lock, new or fully unlocked, takes the given timestamp
+        self.unlock_timestamp = match (self.unlock_timestamp, unlock_timestamp) {
+            _ if self.amount == 0 => unlock_timestamp,
+            (Some(current), Some(new)) => Some(current.max(new)),
+            _ => None,
+        };
+        self.amount = self
+            .amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.amp = amm.amp(now);
+        self.liquidity_per_share_x64 = amm.liquidity_per_share_x64(self.amp)?;
+        Ok(())
+    }
+
+    /// Takes the shares earned as fees since the last claim out of the lock, leaving the ones
+    /// still worth the locked liquidity.
+    ///
+    /// The fees are measured at the amplification coefficient of the last claim, as an amp ramp
+    /// changes the liquidity per share without any fee being earned. The lock then measures from
+    /// the amp of `amm` at `now` on.
+    pub fn claim_fees(&mut self, amm: &Amm, now: i64) -> Result<u64> {
+        // An empty lock has nothing to claim, nor a liquidity per share to measure from
+        if self.amount == 0 {
+            return Ok(0);
+        }
+        let liquidity_per_share_x64 = amm.liquidity_per_share_x64(self.amp)?;
+        let shares = locked_fee_shares(
+            self.amount,
+            self.liquidity_per_share_x64,
+            liquidity_per_share_x64,
+        )?;
+        self.amount -= shares;
+        let amp = amm.amp(now);
+        self.liquidity_per_share_x64 = if amp == self.amp {
+            self.liquidity_per_share_x64.max(liquidity_per_share_x64)
+        } else {
+            amm.liquidity_per_share_x64(amp)?
+        };
+        self.amp = amp;
+        Ok(shares)
+    }
+}
+
+/// LP shares vesting to `beneficiary`, at the `[b"vesting", amm, beneficiary]` PDA. The shares
+/// are held in the same locker vault as the `LiquidityLock`s of the pool.
+#[account]
+pub struct VestingSchedule {
+    pub amm: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,         // LP shares released over the whole schedule
+    pub claimed_amount: u64, // LP shares already transferred to the beneficiary
+    pub start_timestamp: i64,
+    pub cliff_timestamp: i64, // nothing is released before, then what vested since the start
+    pub end_timestamp: i64,
+}
+
+impl VestingSchedule {
+    /// Checks the schedule releases a non-zero amount over a non-empty period containing the cliff.
+    pub fn validate(&self) -> Result<()> {
+        require_gt!(self.amount, 0, ErrorCode::InvalidTokenAmounts);
+        require!(
+            self.start_timestamp <= self.cliff_timestamp
+                && self.cliff_timestamp <= self.end_timestamp
+                && self.start_timestamp < self.end_timestamp,
+            ErrorCode::InvalidVestingSchedule
+        );
+        Ok(())
+    }
+
+    /// Vested shares not claimed yet at `now`.
+    pub fn claimable_amount(&self, now: i64) -> Result<u64> {
+        let vested = vested_amount(
+            self.amount,
+            self.start_timestamp,
+            self.cliff_timestamp,
+            self.end_timestamp,
+            now,
+        )?;
+        Ok(vested.saturating_sub(self.claimed_amount))
+    }
+}
+
+/// Referrer registered by `register_referrer`, at the `[b"referrer", owner]` PDA. Referred swaps
+/// pay its fee to a quote token account owned by `owner`.
+#[account]
+pub struct Referrer {
+    pub owner: Pubkey,
+    pub fee_tier: Option<ReferrerFeeTier>, // overrides the pool's referrer fees when set
+    pub volume: u128,                      // quote amount of every referred swap
+    pub fees_earned: u128,                 // quote amount paid to the referrer
+}
+
+impl Referrer {
+    /// Referrer fee and protocol fee discount of a swap referred by this account: its own tier if
+    /// set, the pool's `fees` otherwise. Nothing is charged on top under `FeeMode::LpShare`.
+    pub fn fee_tier(&self, fees: &FeeConfig, fee_mode: FeeMode) -> ReferrerFeeTier {
+        match (fee_mode, self.fee_tier) {
+            (FeeMode::OnTop, Some(fee_tier)) => fee_tier,
+            _ => ReferrerFeeTier {
+                referrer_fee_bps: fees.referrer_fee_bps,
+                referrer_fee_discount_bps: fees.referrer_fee_discount_bps,
+            },
+        }
+    }
+}
+
+/// Custom referrer fees set by `set_referrer_fee_tier`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReferrerFeeTier {
+    pub referrer_fee_bps: u64,
+    pub referrer_fee_discount_bps: u64,
+}
+
+impl ReferrerFeeTier {
+    /// Checks the fee and discount stay below the whole swap amount.
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.referrer_fee_bps + self.referrer_fee_discount_bps < 10000,
+            ErrorCode::InvalidFeeConfiguration
+        );
+        Ok(())
+    }
+}
+
+/// `ROLE_*` roles granted to a key by the admin, at the `[b"role", member]` PDA.
+#[account]
+pub struct Role {
+    pub member: Pubkey,
+    pub roles: u8,
+}
+
+/// Pause flag halting swaps, including routes and flash loans.
+pub const PAUSE_SWAPS: u8 = 1 << 0;
+/// Pause flag halting liquidity deposits.
+pub const PAUSE_DEPOSITS: u8 = 1 << 1;
+/// Pause flag halting liquidity withdrawals.
+pub const PAUSE_WITHDRAWALS: u8 = 1 << 2;
+/// Every pause flag.
+pub const PAUSE_ALL: u8 = PAUSE_SWAPS | PAUSE_DEPOSITS | PAUSE_WITHDRAWALS;
+
+impl GlobalParameters {
+    /// Size of the global parameters created before any field was appended to the original layout.
+    pub const V1_LEN: usize = 8 + 3 * 8 + 2 * 32;
+    /// Size of the global parameters created with the current layout.
+    pub const LEN: usize = 8 + std::mem::size_of::<GlobalParameters>();
+
+    /// Whether `authority` holds every bit of `roles`.
+    ///
+    /// The admin holds every role, other keys need them granted in their `Role` account.
+    pub fn has_role(&self, authority: &Pubkey, role: Option<&Role>, roles: u8) -> bool {
+        *authority == self.admin || role.is_some_and(|role| role.roles & roles == roles)
+    }
+
+    /// Fails with `Paused` if any of the `flags` operations is paused globally.
+    pub fn require_not_paused(&self, flags: u8) -> Result<()> {
+        require!(self.paused & flags == 0, ErrorCode::Paused);
+        Ok(())
+    }
+
+    pub fn fee_config(&self) -> FeeConfig {
+        FeeConfig {
+            protocol_fee_bps: self.protocol_fee_bps,
+            referrer_fee_bps: self.referrer_fee_bps,
+            referrer_fee_discount_bps: self.referrer_fee_discount_bps,
+            lp_fee_bps: self.lp_fee_bps,
+        }
+    }
+}
+
+/// Reserves lent out by `flash_borrow`, owed back by the `flash_repay` of the same transaction.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FlashLoan {
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    pub base_fee_amount: u64,
+    pub quote_fee_amount: u64,
+}
+
+/// Side of a pool a route hop trades on.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapDirection {
+    /// Spend quote tokens for base tokens.
+    Buy,
+    /// Spend base tokens for quote tokens.
+    Sell,
+}
+
+/// Side of a pool a single sided deposit or withdrawal is made in.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolSide {
+    Base,
+    Quote,
+}
+
+/// Number of ticks stored in each `TickArray`.
+pub const TICK_ARRAY_SIZE: usize = 32;
+
+/// Concentrated liquidity pool. Unlike `Amm`, liquidity is provided over price ranges by
+/// individual `Position`s instead of fungible LP shares.
+#[account]
+pub struct ClPool {
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub creator: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub tick_spacing: u16,
+    pub fee_bps: u16, // LP fee charged on the input of every swap
+    pub bump: u8,
+    pub sqrt_price_x64: u128, // square root of the quote per base price, Q64.64
+    pub tick_current: i32,
+    pub liquidity: u128, // liquidity of the positions in range of the current price
+    pub fee_growth_global_base_x64: u128,
+    pub fee_growth_global_quote_x64: u128,
+}
+
+impl ClPool {
+    /// Number of ticks covered by one tick array of this pool.
+    pub fn ticks_per_array(&self) -> i32 {
+        self.tick_spacing as i32 * TICK_ARRAY_SIZE as i32
+    }
+
+    /// Start tick of the tick array holding `tick`.
+    pub fn tick_array_start(&self, tick: i32) -> i32 {
+        tick.div_euclid(self.ticks_per_array()) * self.ticks_per_array()
+    }
+
+    /// Fee growth per unit of liquidity earned between two initialized ticks, in Q64.64.
+    ///
+    /// Values wrap around by design, only differences between them are meaningful.
+    pub fn fee_growth_inside(
+        &self,
+        tick_lower: i32,
+        lower: &Tick,
+        tick_upper: i32,
+        upper: &Tick,
+    ) -> (u128, u128) {
+        let (global_base, global_quote) = (
+            self.fee_growth_global_base_x64,
+            self.fee_growth_global_quote_x64,
+        );
+        let (below_base, below_quote) = if self.tick_current >= tick_lower {
+            (
+                lower.fee_growth_outside_base_x64,
+                lower.fee_growth_outside_quote_x64,
+            )
+        } else {
+            (
+                global_base.wrapping_sub(lower.fee_growth_outside_base_x64),
+                global_quote.wrapping_sub(lower.fee_growth_outside_quote_x64),
+            )
+        };
+        let (above_base, above_quote) = if self.tick_current < tick_upper {
+            (
+                upper.fee_growth_outside_base_x64,
+                upper.fee_growth_outside_quote_x64,
+            )
+        } else {
+            (
+                global_base.wrapping_sub(upper.fee_growth_outside_base_x64),
+                global_quote.wrapping_sub(upper.fee_growth_outside_quote_x64),
+            )
+        };
+        (
+            global_base
+                .wrapping_sub(below_base)
+                .wrapping_sub(above_base),
+            global_quote
+                .wrapping_sub(below_quote)
+                .wrapping_sub(above_quote),
+        )
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tick {
+    pub liquidity_net: i128, // liquidity added when the price crosses the tick upwards
+    pub liquidity_gross: u128, // liquidity of every position using the tick as a bound
+    pub fee_growth_outside_base_x64: u128,
+    pub fee_growth_outside_quote_x64: u128,
+}
+
+impl Tick {
+    pub const LEN: usize = 16 * 4;
+
+    pub fn is_initialized(&self) -> bool {
+        self.liquidity_gross > 0
+    }
+
+    /// Adds the liquidity delta of a position to one of its bounds.
+    pub fn update(
+        &mut self,
+        tick: i32,
+        pool: &ClPool,
+        liquidity_delta: i128,
+        upper: bool,
+    ) -> Result<()> {
+        let liquidity_gross =
+            crate::curve::concentrated::add_liquidity_delta(self.liquidity_gross, liquidity_delta)?;
+        // By convention, all the fees so far were earned below a tick initialized under the price
+        if !self.is_initialized() && tick <= pool.tick_current {
+            self.fee_growth_outside_base_x64 = pool.fee_growth_global_base_x64;
+            self.fee_growth_outside_quote_x64 = pool.fee_growth_global_quote_x64;
+        }
+        self.liquidity_gross = liquidity_gross;
+        self.liquidity_net = if upper {
+            self.liquidity_net.checked_sub(liquidity_delta)
+        } else {
+            self.liquidity_net.checked_add(liquidity_delta)
+        }
+        .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Flips the fee growth outside the tick as the price crosses it and returns its liquidity net.
+    pub fn cross(&mut self, pool: &ClPool) -> i128 {
+        self.fee_growth_outside_base_x64 = pool
+            .fee_growth_global_base_x64
+            .wrapping_sub(self.fee_growth_outside_base_x64);
+        self.fee_growth_outside_quote_x64 = pool
+            .fee_growth_global_quote_x64
+            .wrapping_sub(self.fee_growth_outside_quote_x64);
+        self.liquidity_net
+    }
+}
+
+/// A run of `TICK_ARRAY_SIZE` consecutive ticks of a `ClPool`, spaced by its tick spacing.
+#[account]
+pub struct TickArray {
+    pub pool: Pubkey,
+    pub start_tick_index: i32,
+    pub ticks: Vec<Tick>,
+}
+
+impl TickArray {
+    pub const LEN: usize = 8 + 32 + 4 + 4 + TICK_ARRAY_SIZE * Tick::LEN;
+
+    /// Reads a tick array of `pool` passed as an untyped account.
+    pub fn load(info: &AccountInfo, pool: &Pubkey) -> Result<TickArray> {
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::InvalidTickArray);
+        let tick_array = TickArray::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        require_keys_eq!(tick_array.pool, *pool, ErrorCode::InvalidTickArray);
+        Ok(tick_array)
+    }
+
+    /// Writes back a tick array read with `load`.
+    pub fn save(&self, info: &AccountInfo) -> Result<()> {
+        self.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])
+    }
+
+    /// Whether `tick` falls within this array.
+    pub fn contains(&self, tick: i32, tick_spacing: u16) -> bool {
+        let offset = tick - self.start_tick_index;
+        offset >= 0 && offset < tick_spacing as i32 * TICK_ARRAY_SIZE as i32
+    }
+
+    /// Returns the tick at an initializable index of this array.
+    pub fn tick_mut(&mut self, tick: i32, tick_spacing: u16) -> Result<&mut Tick> {
+        require!(
+            self.contains(tick, tick_spacing) && tick % tick_spacing as i32 == 0,
+            ErrorCode::InvalidTickArray
+        );
+        let offset = (tick - self.start_tick_index) / tick_spacing as i32;
+        Ok(&mut self.ticks[offset as usize])
+    }
+
+    /// Finds the next initialized tick of this array from `tick`: the greatest one at or below it
+    /// when `lte`, the smallest one above it otherwise.
+    pub fn next_initialized_tick(&self, tick: i32, tick_spacing: u16, lte: bool) -> Option<i32> {
+        let spacing = tick_spacing as i32;
+        let index = |offset: usize| self.start_tick_index + offset as i32 * spacing;
+        let mut initialized = self
+            .ticks
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.is_initialized())
+            .map(|(offset, _)| index(offset));
+        if lte {
+            initialized.rfind(|t| *t <= tick)
+        } else {
+            initialized.find(|t| *t > tick)
+        }
+    }
+}
+
+/// Liquidity provided by `owner` to a `ClPool` between two ticks.
+#[account]
+pub struct Position {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+    pub fee_growth_inside_base_last_x64: u128,
+    pub fee_growth_inside_quote_last_x64: u128,
+    pub tokens_owed_base: u64,
+    pub tokens_owed_quote: u64,
 }
 
 #[event_cpi]
 #[derive(Accounts)]
 pub struct Create<'info> {
-    #[account(init, payer = user, space = 8 + std::mem::size_of::<Amm>(), seeds = [b"amm", user.key().as_ref(), base_mint.key().as_ref(), quote_mint.key().as_ref()], bump)]
+    #[account(init, payer = user, space = Amm::LEN, seeds = [b"amm", user.key().as_ref(), base_mint.key().as_ref(), quote_mint.key().as_ref()], bump)]
     pub amm: Account<'info, Amm>,
     #[account(seeds = [b"global_parameters"], bump)]
     pub global_parameters: Account<'info, GlobalParameters>,
@@ -36,6 +958,7 @@ pub struct Create<'info> {
     mint::decimals = 9,
     mint::authority = amm.key(),
     mint::freeze_authority = amm.key(),
+    mint::token_program = lp_token_program,
     )]
     pub lp_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
     /// CHECK:
@@ -44,24 +967,167 @@ pub struct Create<'info> {
     /// CHECK:
     #[account(mut)]
     pub quote_reserve_ata: AccountInfo<'info>, // you'll note teh user is not the admin. there's no restrictions on the user (who signs and pays tx fees)
-    #[account(token::authority = global_parameters.admin)]
-    pub fee_receiver_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    /// CHECK: The fee authority's associated token account for the quote mint, created if needed
+    #[account(mut)]
+    pub fee_receiver_ata: AccountInfo<'info>,
+    /// CHECK: PDA owning the protocol fees of every pool
+    #[account(seeds = [b"fee_authority"], bump)]
+    pub fee_authority: UncheckedAccount<'info>,
     pub base_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
     pub quote_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
     pub base_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
     pub quote_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
-    pub token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub lp_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
     /// CHECK:
     pub rent: AccountInfo<'info>,
     /// CHECK:
     pub associated_token_program: AccountInfo<'info>,
 }
 
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Migrate<'info> {
+    #[account(init, payer = user, space = Amm::LEN, seeds = [b"amm", user.key().as_ref(), base_mint.key().as_ref(), quote_mint.key().as_ref()], bump)]
+    pub amm: Account<'info, Amm>,
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut, token::authority = user, token::mint = base_mint.key(), token::token_program = base_token_program)]
+    pub user_base_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, token::authority = user, token::mint = quote_mint.key(), token::token_program = quote_token_program)]
+    pub user_quote_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(init, payer = user,
+    mint::decimals = 9,
+    mint::authority = amm.key(),
+    mint::freeze_authority = amm.key(),
+    mint::token_program = lp_token_program,
+    )]
+    pub lp_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    #[account(init, payer = user, associated_token::mint = lp_mint, associated_token::authority = user, associated_token::token_program = lp_token_program)]
+    pub user_lp_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    // The vaults may have been created, and funded, before the migration
+    #[account(init_if_needed, payer = user, associated_token::mint = base_mint, associated_token::authority = amm, associated_token::token_program = base_token_program)]
+    pub base_reserve_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(init_if_needed, payer = user, associated_token::mint = quote_mint, associated_token::authority = amm, associated_token::token_program = quote_token_program)]
+    pub quote_reserve_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    /// CHECK: The fee authority's associated token account for the quote mint, created if needed
+    #[account(mut)]
+    pub fee_receiver_ata: AccountInfo<'info>,
+    /// CHECK: PDA owning the protocol fees of every pool
+    #[account(seeds = [b"fee_authority"], bump)]
+    pub fee_authority: UncheckedAccount<'info>,
+    pub base_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    pub quote_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    pub base_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub quote_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub lp_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: PDA owning the locked LP shares of the pool, only with `MigrationLp::Lock`
+    #[account(seeds = [b"lp_locker", amm.key().as_ref()], bump)]
+    pub lp_locker: Option<UncheckedAccount<'info>>,
+    #[account(init, payer = user, associated_token::mint = lp_mint, associated_token::authority = lp_locker, associated_token::token_program = lp_token_program)]
+    pub locker_lp_ata: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    #[account(init, payer = user, space = 8 + std::mem::size_of::<LiquidityLock>(), seeds = [b"liquidity_lock", amm.key().as_ref(), user.key().as_ref()], bump)]
+    pub liquidity_lock: Option<Account<'info, LiquidityLock>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct LockLiquidity<'info> {
+    pub amm: Account<'info, Amm>,
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut, token::authority = owner, token::mint = lp_mint.key(), token::token_program = lp_token_program)]
+    pub owner_lp_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(address = amm.lp_mint, mint::token_program = lp_token_program)]
+    pub lp_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    /// CHECK: PDA owning the locked LP shares of the pool
+    #[account(seeds = [b"lp_locker", amm.key().as_ref()], bump)]
+    pub lp_locker: UncheckedAccount<'info>,
+    #[account(init_if_needed, payer = owner, associated_token::mint = lp_mint, associated_token::authority = lp_locker, associated_token::token_program = lp_token_program)]
+    pub locker_lp_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(init_if_needed, payer = owner, space = 8 + std::mem::size_of::<LiquidityLock>(), seeds = [b"liquidity_lock", amm.key().as_ref(), owner.key().as_ref()], bump)]
+    pub liquidity_lock: Account<'info, LiquidityLock>,
+    pub lp_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct LockedLiquidity<'info> {
+    pub amm: Account<'info, Amm>,
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    pub owner: Signer<'info>,
+    #[account(mut, token::authority = owner, token::mint = lp_mint.key(), token::token_program = lp_token_program)]
+    pub owner_lp_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(address = amm.lp_mint, mint::token_program = lp_token_program)]
+    pub lp_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    /// CHECK: PDA owning the locked LP shares of the pool
+    #[account(seeds = [b"lp_locker", amm.key().as_ref()], bump)]
+    pub lp_locker: UncheckedAccount<'info>,
+    #[account(mut, associated_token::mint = lp_mint, associated_token::authority = lp_locker, associated_token::token_program = lp_token_program)]
+    pub locker_lp_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, seeds = [b"liquidity_lock", amm.key().as_ref(), owner.key().as_ref()], bump)]
+    pub liquidity_lock: Account<'info, LiquidityLock>,
+    pub lp_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateVesting<'info> {
+    pub amm: Account<'info, Amm>,
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    #[account(mut, token::authority = funder, token::mint = lp_mint.key(), token::token_program = lp_token_program)]
+    pub funder_lp_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    /// CHECK: Only receives the vested shares, may be any account
+    pub beneficiary: UncheckedAccount<'info>,
+    #[account(address = amm.lp_mint, mint::token_program = lp_token_program)]
+    pub lp_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    /// CHECK: PDA owning the locked LP shares of the pool
+    #[account(seeds = [b"lp_locker", amm.key().as_ref()], bump)]
+    pub lp_locker: UncheckedAccount<'info>,
+    #[account(init_if_needed, payer = funder, associated_token::mint = lp_mint, associated_token::authority = lp_locker, associated_token::token_program = lp_token_program)]
+    pub locker_lp_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(init, payer = funder, space = 8 + std::mem::size_of::<VestingSchedule>(), seeds = [b"vesting", amm.key().as_ref(), beneficiary.key().as_ref()], bump)]
+    pub vesting_schedule: Account<'info, VestingSchedule>,
+    pub lp_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    pub amm: Account<'info, Amm>,
+    pub beneficiary: Signer<'info>,
+    #[account(mut, token::authority = beneficiary, token::mint = lp_mint.key(), token::token_program = lp_token_program)]
+    pub beneficiary_lp_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(address = amm.lp_mint, mint::token_program = lp_token_program)]
+    pub lp_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    /// CHECK: PDA owning the locked LP shares of the pool
+    #[account(seeds = [b"lp_locker", amm.key().as_ref()], bump)]
+    pub lp_locker: UncheckedAccount<'info>,
+    #[account(mut, associated_token::mint = lp_mint, associated_token::authority = lp_locker, associated_token::token_program = lp_token_program)]
+    pub locker_lp_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, seeds = [b"vesting", amm.key().as_ref(), beneficiary.key().as_ref()], bump)]
+    pub vesting_schedule: Account<'info, VestingSchedule>,
+    pub lp_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+}
+
 #[event_cpi]
 #[derive(Accounts)]
 pub struct AddLiquidity<'info> {
-    #[account(mut, seeds = [b"amm", amm.creator.as_ref(), base_mint.key().as_ref(), quote_mint.key().as_ref()], bump)]
+    #[account(mut, seeds = [b"amm", amm.creator.as_ref(), base_mint.key().as_ref(), quote_mint.key().as_ref()], bump, constraint = amm.flash_loan.is_none() @ ErrorCode::FlashLoanInProgress)]
     pub amm: Account<'info, Amm>,
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
     #[account(mut)]
     pub user: Signer<'info>,
     #[account(mut, token::authority = user, token::mint = base_mint.key(), token::token_program = base_token_program)]
@@ -72,9 +1138,9 @@ pub struct AddLiquidity<'info> {
     pub base_reserve_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
     #[account(mut, constraint = quote_reserve_ata.key() == amm.quote_reserve_ata)]
     pub quote_reserve_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
-    #[account(mut, token::authority = user, token::mint = lp_mint.key())]
+    #[account(mut, token::authority = user, token::mint = lp_mint.key(), token::token_program = lp_token_program)]
     pub user_lp_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
-    #[account(mut)]
+    #[account(mut, address = amm.lp_mint, mint::token_program = lp_token_program)]
     pub lp_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
     #[account(mut)]
     pub base_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
@@ -82,14 +1148,19 @@ pub struct AddLiquidity<'info> {
     pub quote_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
     pub base_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
     pub quote_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub lp_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
     pub system_program: Program<'info, System>,
+    #[account(mut, token::authority = global_parameters.treasury, token::mint = lp_mint.key(), token::token_program = lp_token_program)]
+    pub treasury_lp_ata: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
 }
 
 #[event_cpi]
 #[derive(Accounts)]
 pub struct RemoveLiquidity<'info> {
-    #[account(mut, seeds = [b"amm",  amm.creator.as_ref(), base_mint.key().as_ref(), quote_mint.key().as_ref()], bump)]
+    #[account(mut, seeds = [b"amm",  amm.creator.as_ref(), base_mint.key().as_ref(), quote_mint.key().as_ref()], bump, constraint = amm.flash_loan.is_none() @ ErrorCode::FlashLoanInProgress)]
     pub amm: Account<'info, Amm>,
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
     #[account(mut)]
     pub user: Signer<'info>,
     #[account(mut, token::authority = user, token::mint = base_mint.key(), token::token_program = base_token_program)]
@@ -100,9 +1171,9 @@ pub struct RemoveLiquidity<'info> {
     pub base_reserve_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
     #[account(mut, constraint = quote_reserve_ata.key() == amm.quote_reserve_ata)]
     pub quote_reserve_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
-    #[account(mut, token::authority = user.key(), token::mint = lp_mint.key())]
+    #[account(mut, token::authority = user.key(), token::mint = lp_mint.key(), token::token_program = lp_token_program)]
     pub user_lp_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
-    #[account(mut)]
+    #[account(mut, address = amm.lp_mint, mint::token_program = lp_token_program)]
     pub lp_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
     #[account(mut)]
     pub base_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
@@ -110,14 +1181,50 @@ pub struct RemoveLiquidity<'info> {
     pub quote_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
     pub base_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
     pub quote_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub lp_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
 
     pub system_program: Program<'info, System>,
+    #[account(mut, token::authority = global_parameters.treasury, token::mint = lp_mint.key(), token::token_program = lp_token_program)]
+    pub treasury_lp_ata: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Zap<'info> {
+    #[account(mut, seeds = [b"amm", amm.creator.as_ref(), base_mint.key().as_ref(), quote_mint.key().as_ref()], bump, constraint = amm.flash_loan.is_none() @ ErrorCode::FlashLoanInProgress)]
+    pub amm: Account<'info, Amm>,
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    pub user: Signer<'info>,
+    #[account(mut, token::authority = user, token::mint = base_mint.key(), token::token_program = base_token_program)]
+    pub user_base_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, token::authority = user, token::mint = quote_mint.key(), token::token_program = quote_token_program)]
+    pub user_quote_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, constraint = base_reserve_ata.key() == amm.base_reserve_ata)]
+    pub base_reserve_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, constraint = quote_reserve_ata.key() == amm.quote_reserve_ata)]
+    pub quote_reserve_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, constraint = fee_receiver_ata.key() == amm.fee_receiver_ata)]
+    pub fee_receiver_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, token::authority = user, token::mint = lp_mint.key(), token::token_program = lp_token_program)]
+    pub user_lp_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, address = amm.lp_mint, mint::token_program = lp_token_program)]
+    pub lp_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    #[account(mut)]
+    pub base_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    #[account(mut)]
+    pub quote_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    pub base_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub quote_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub lp_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    #[account(mut, token::authority = global_parameters.treasury, token::mint = lp_mint.key(), token::token_program = lp_token_program)]
+    pub treasury_lp_ata: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
 }
 
 #[event_cpi]
 #[derive(Accounts)]
 pub struct Buy<'info> {
-    #[account(mut, seeds = [b"amm", amm.creator.as_ref(), base_mint.key().as_ref(), quote_mint.key().as_ref()], bump)]
+    #[account(mut, seeds = [b"amm", amm.creator.as_ref(), base_mint.key().as_ref(), quote_mint.key().as_ref()], bump, constraint = amm.flash_loan.is_none() @ ErrorCode::FlashLoanInProgress)]
     pub amm: Account<'info, Amm>,
     pub global_parameters: Account<'info, GlobalParameters>,
     pub user: Signer<'info>,
@@ -137,12 +1244,14 @@ pub struct Buy<'info> {
     pub quote_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
     pub base_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
     pub quote_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    #[account(mut, seeds = [b"observations", amm.key().as_ref()], bump)]
+    pub observations: Option<Account<'info, Observations>>,
 }
 
 #[event_cpi]
 #[derive(Accounts)]
 pub struct Sell<'info> {
-    #[account(mut, seeds = [b"amm", amm.creator.as_ref(), base_mint.key().as_ref(), quote_mint.key().as_ref()], bump)]
+    #[account(mut, seeds = [b"amm", amm.creator.as_ref(), base_mint.key().as_ref(), quote_mint.key().as_ref()], bump, constraint = amm.flash_loan.is_none() @ ErrorCode::FlashLoanInProgress)]
     pub amm: Account<'info, Amm>,
     pub global_parameters: Account<'info, GlobalParameters>,
     pub user: Signer<'info>,
@@ -162,24 +1271,331 @@ pub struct Sell<'info> {
     pub quote_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
     pub base_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
     pub quote_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    #[account(mut, seeds = [b"observations", amm.key().as_ref()], bump)]
+    pub observations: Option<Account<'info, Observations>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Route<'info> {
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    pub user: Signer<'info>,
+    // The accounts of every hop follow as remaining accounts, see `instructions::route`
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SyncReserves<'info> {
+    #[account(mut, seeds = [b"amm", amm.creator.as_ref(), amm.base_mint.as_ref(), amm.quote_mint.as_ref()], bump, constraint = amm.flash_loan.is_none() @ ErrorCode::FlashLoanInProgress)]
+    pub amm: Account<'info, Amm>,
+    #[account(constraint = base_reserve_ata.key() == amm.base_reserve_ata)]
+    pub base_reserve_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(constraint = quote_reserve_ata.key() == amm.quote_reserve_ata)]
+    pub quote_reserve_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct FlashBorrow<'info> {
+    #[account(mut, seeds = [b"amm", amm.creator.as_ref(), base_mint.key().as_ref(), quote_mint.key().as_ref()], bump)]
+    pub amm: Account<'info, Amm>,
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    pub user: Signer<'info>,
+    #[account(mut, token::mint = base_mint.key(), token::token_program = base_token_program)]
+    pub user_base_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, token::mint = quote_mint.key(), token::token_program = quote_token_program)]
+    pub user_quote_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, constraint = base_reserve_ata.key() == amm.base_reserve_ata)]
+    pub base_reserve_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, constraint = quote_reserve_ata.key() == amm.quote_reserve_ata)]
+    pub quote_reserve_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    pub base_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    pub quote_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    pub base_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub quote_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    /// CHECK: The instructions sysvar, used to find the matching `flash_repay`
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FlashRepay<'info> {
+    #[account(mut, seeds = [b"amm", amm.creator.as_ref(), base_mint.key().as_ref(), quote_mint.key().as_ref()], bump)]
+    pub amm: Account<'info, Amm>,
+    pub user: Signer<'info>,
+    #[account(mut, token::authority = user, token::mint = base_mint.key(), token::token_program = base_token_program)]
+    pub user_base_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, token::authority = user, token::mint = quote_mint.key(), token::token_program = quote_token_program)]
+    pub user_quote_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, constraint = base_reserve_ata.key() == amm.base_reserve_ata)]
+    pub base_reserve_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, constraint = quote_reserve_ata.key() == amm.quote_reserve_ata)]
+    pub quote_reserve_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    pub base_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    pub quote_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    pub base_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub quote_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
 }
 
 #[derive(Accounts)]
 pub struct Initialize<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
-    #[account(init, space =  8 + std::mem::size_of::<GlobalParameters>(), payer = admin, seeds = [b"global_parameters"], bump)]
+    #[account(init, space = GlobalParameters::LEN, payer = admin, seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct QueueParameters<'info> {
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(mut, constraint = global_parameters.has_role(&authority.key(), role.as_deref(), ROLE_FEE_MANAGER) @ ErrorCode::MissingRole)]
+    pub authority: Signer<'info>,
+    #[account(init, payer = authority, space = 8 + std::mem::size_of::<ParametersProposal>(), seeds = [b"parameters_proposal"], bump)]
+    pub proposal: Account<'info, ParametersProposal>,
+    #[account(seeds = [b"role", authority.key().as_ref()], bump)]
+    pub role: Option<Account<'info, Role>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExecuteParameters<'info> {
+    #[account(mut, seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(mut, constraint = global_parameters.has_role(&authority.key(), role.as_deref(), ROLE_FEE_MANAGER) @ ErrorCode::MissingRole)]
+    pub authority: Signer<'info>,
+    #[account(mut, close = authority, seeds = [b"parameters_proposal"], bump)]
+    pub proposal: Account<'info, ParametersProposal>,
+    #[account(seeds = [b"role", authority.key().as_ref()], bump)]
+    pub role: Option<Account<'info, Role>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelParameters<'info> {
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(mut, constraint = global_parameters.has_role(&authority.key(), role.as_deref(), ROLE_FEE_MANAGER) @ ErrorCode::MissingRole)]
+    pub authority: Signer<'info>,
+    #[account(mut, close = authority, seeds = [b"parameters_proposal"], bump)]
+    pub proposal: Account<'info, ParametersProposal>,
+    #[account(seeds = [b"role", authority.key().as_ref()], bump)]
+    pub role: Option<Account<'info, Role>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct QueuePoolParameters<'info> {
+    #[account(seeds = [b"global_parameters"], bump)]
     pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(mut, constraint = global_parameters.has_role(&authority.key(), role.as_deref(), ROLE_POOL_MANAGER) @ ErrorCode::MissingRole)]
+    pub authority: Signer<'info>,
+    pub amm: Account<'info, Amm>,
+    #[account(init, payer = authority, space = 8 + std::mem::size_of::<PoolParametersProposal>(), seeds = [b"pool_parameters_proposal", amm.key().as_ref()], bump)]
+    pub proposal: Account<'info, PoolParametersProposal>,
+    #[account(seeds = [b"role", authority.key().as_ref()], bump)]
+    pub role: Option<Account<'info, Role>>,
     pub system_program: Program<'info, System>,
 }
 
 #[event_cpi]
 #[derive(Accounts)]
-pub struct SetParameters<'info> {
+pub struct ExecutePoolParameters<'info> {
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(mut, constraint = global_parameters.has_role(&authority.key(), role.as_deref(), ROLE_POOL_MANAGER) @ ErrorCode::MissingRole)]
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub amm: Account<'info, Amm>,
+    #[account(mut, close = authority, seeds = [b"pool_parameters_proposal", amm.key().as_ref()], bump)]
+    pub proposal: Account<'info, PoolParametersProposal>,
+    #[account(seeds = [b"role", authority.key().as_ref()], bump)]
+    pub role: Option<Account<'info, Role>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelPoolParameters<'info> {
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(mut, constraint = global_parameters.has_role(&authority.key(), role.as_deref(), ROLE_POOL_MANAGER) @ ErrorCode::MissingRole)]
+    pub authority: Signer<'info>,
+    pub amm: Account<'info, Amm>,
+    #[account(mut, close = authority, seeds = [b"pool_parameters_proposal", amm.key().as_ref()], bump)]
+    pub proposal: Account<'info, PoolParametersProposal>,
+    #[account(seeds = [b"role", authority.key().as_ref()], bump)]
+    pub role: Option<Account<'info, Role>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetPause<'info> {
     #[account(mut, seeds = [b"global_parameters"], bump)]
     pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(constraint = global_parameters.has_role(&authority.key(), role.as_deref(), ROLE_PAUSER) @ ErrorCode::MissingRole)]
+    pub authority: Signer<'info>,
+    #[account(seeds = [b"role", authority.key().as_ref()], bump)]
+    pub role: Option<Account<'info, Role>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetPoolPause<'info> {
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(constraint = global_parameters.has_role(&authority.key(), role.as_deref(), ROLE_PAUSER) @ ErrorCode::MissingRole)]
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub amm: Account<'info, Amm>,
+    #[account(seeds = [b"role", authority.key().as_ref()], bump)]
+    pub role: Option<Account<'info, Role>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct GrantRoles<'info> {
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
     #[account(mut, constraint = admin.key() == global_parameters.admin)]
     pub admin: Signer<'info>,
+    /// CHECK: Any key can be granted roles
+    pub member: UncheckedAccount<'info>,
+    #[account(init_if_needed, payer = admin, space = 8 + std::mem::size_of::<Role>(), seeds = [b"role", member.key().as_ref()], bump)]
+    pub role: Account<'info, Role>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevokeRoles<'info> {
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(constraint = admin.key() == global_parameters.admin)]
+    pub admin: Signer<'info>,
+    #[account(mut, seeds = [b"role", role.member.as_ref()], bump)]
+    pub role: Account<'info, Role>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    #[account(init, payer = owner, space = 8 + std::mem::size_of::<Referrer>(), seeds = [b"referrer", owner.key().as_ref()], bump)]
+    pub referrer: Account<'info, Referrer>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetReferrerFeeTier<'info> {
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(constraint = global_parameters.has_role(&authority.key(), role.as_deref(), ROLE_FEE_MANAGER) @ ErrorCode::MissingRole)]
+    pub authority: Signer<'info>,
+    #[account(mut, seeds = [b"referrer", referrer.owner.as_ref()], bump)]
+    pub referrer: Account<'info, Referrer>,
+    #[account(seeds = [b"role", authority.key().as_ref()], bump)]
+    pub role: Option<Account<'info, Role>>,
+}
+
+#[derive(Accounts)]
+pub struct Observe<'info> {
+    pub amm: Account<'info, Amm>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeObservations<'info> {
+    pub amm: Account<'info, Amm>,
+    #[account(init, payer = payer, space = Observations::space(1), seeds = [b"observations", amm.key().as_ref()], bump)]
+    pub observations: Account<'info, Observations>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(capacity: u16)]
+pub struct GrowObservations<'info> {
+    #[account(mut, seeds = [b"observations", observations.amm.as_ref()], bump, realloc = Observations::space(capacity), realloc::payer = payer, realloc::zero = false)]
+    pub observations: Account<'info, Observations>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ObserveAt<'info> {
+    pub amm: Account<'info, Amm>,
+    #[account(has_one = amm)]
+    pub observations: Account<'info, Observations>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RampAmp<'info> {
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(constraint = global_parameters.has_role(&authority.key(), role.as_deref(), ROLE_POOL_MANAGER) @ ErrorCode::MissingRole)]
+    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub amm: Account<'info, Amm>,
+    #[account(seeds = [b"role", authority.key().as_ref()], bump)]
+    pub role: Option<Account<'info, Role>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetTreasury<'info> {
+    #[account(mut, seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(constraint = admin.key() == global_parameters.admin)]
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateFeeReceiver<'info> {
+    #[account(mut, constraint = amm.quote_mint == quote_mint.key())]
+    pub amm: Account<'info, Amm>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: The fee authority's associated token account for the quote mint, created if needed
+    #[account(mut)]
+    pub fee_receiver_ata: AccountInfo<'info>,
+    /// CHECK: PDA owning the protocol fees of every pool
+    #[account(seeds = [b"fee_authority"], bump)]
+    pub fee_authority: UncheckedAccount<'info>,
+    pub quote_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    pub quote_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub system_program: Program<'info, System>,
+    /// CHECK:
+    pub associated_token_program: AccountInfo<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawProtocolFees<'info> {
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(constraint = global_parameters.has_role(&authority.key(), role.as_deref(), ROLE_FEE_MANAGER) @ ErrorCode::MissingRole)]
+    pub authority: Signer<'info>,
+    /// CHECK: PDA owning the protocol fees of every pool
+    #[account(seeds = [b"fee_authority"], bump)]
+    pub fee_authority: UncheckedAccount<'info>,
+    #[account(mut, token::authority = fee_authority, token::mint = mint, token::token_program = token_program)]
+    pub fee_receiver_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, token::authority = global_parameters.treasury, token::mint = mint, token::token_program = token_program)]
+    pub treasury_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    pub mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    pub token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    #[account(seeds = [b"role", authority.key().as_ref()], bump)]
+    pub role: Option<Account<'info, Role>>,
 }
 
 #[event_cpi]
@@ -193,6 +1609,15 @@ pub struct ProposeAdmin<'info> {
     pub proposed_admin: AccountInfo<'info>,
 }
 
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelAdminProposal<'info> {
+    #[account(mut, seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(constraint = admin.key() == global_parameters.admin)]
+    pub admin: Signer<'info>,
+}
+
 #[event_cpi]
 #[derive(Accounts)]
 pub struct AcceptAdmin<'info> {
@@ -201,3 +1626,126 @@ pub struct AcceptAdmin<'info> {
     #[account(mut, constraint = admin.key() == global_parameters.proposed_admin)]
     pub admin: Signer<'info>,
 }
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateClPool<'info> {
+    #[account(init, payer = user, space = 8 + std::mem::size_of::<ClPool>(), seeds = [b"cl_pool", user.key().as_ref(), base_mint.key().as_ref(), quote_mint.key().as_ref()], bump)]
+    pub pool: Account<'info, ClPool>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(init, payer = user, associated_token::mint = base_mint, associated_token::authority = pool, associated_token::token_program = base_token_program)]
+    pub base_vault: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(init, payer = user, associated_token::mint = quote_mint, associated_token::authority = pool, associated_token::token_program = quote_token_program)]
+    pub quote_vault: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    pub base_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    pub quote_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    pub base_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub quote_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(start_tick_index: i32)]
+pub struct InitializeTickArray<'info> {
+    pub pool: Account<'info, ClPool>,
+    #[account(init, payer = payer, space = TickArray::LEN, seeds = [b"tick_array", pool.key().as_ref(), start_tick_index.to_le_bytes().as_ref()], bump)]
+    pub tick_array: Account<'info, TickArray>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(tick_lower: i32, tick_upper: i32)]
+pub struct OpenPosition<'info> {
+    pub pool: Account<'info, ClPool>,
+    #[account(init, payer = owner, space = 8 + std::mem::size_of::<Position>(), seeds = [b"position", pool.key().as_ref(), owner.key().as_ref(), tick_lower.to_le_bytes().as_ref(), tick_upper.to_le_bytes().as_ref()], bump)]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ModifyPosition<'info> {
+    #[account(mut, seeds = [b"cl_pool", pool.creator.as_ref(), pool.base_mint.as_ref(), pool.quote_mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, ClPool>,
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(mut, has_one = pool, has_one = owner)]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+    /// CHECK: tick array holding the lower tick, checked when loaded. May be the upper one too.
+    #[account(mut)]
+    pub tick_array_lower: UncheckedAccount<'info>,
+    /// CHECK: tick array holding the upper tick, checked when loaded.
+    #[account(mut)]
+    pub tick_array_upper: UncheckedAccount<'info>,
+    #[account(mut, constraint = base_vault.key() == pool.base_vault)]
+    pub base_vault: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, constraint = quote_vault.key() == pool.quote_vault)]
+    pub quote_vault: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, token::authority = owner, token::mint = base_mint.key(), token::token_program = base_token_program)]
+    pub owner_base_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, token::authority = owner, token::mint = quote_mint.key(), token::token_program = quote_token_program)]
+    pub owner_quote_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(address = pool.base_mint)]
+    pub base_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    #[account(address = pool.quote_mint)]
+    pub quote_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    pub base_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub quote_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClSwap<'info> {
+    #[account(mut, seeds = [b"cl_pool", pool.creator.as_ref(), pool.base_mint.as_ref(), pool.quote_mint.as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, ClPool>,
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    pub user: Signer<'info>,
+    #[account(mut, token::authority = user, token::mint = base_mint.key(), token::token_program = base_token_program)]
+    pub user_base_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, token::authority = user, token::mint = quote_mint.key(), token::token_program = quote_token_program)]
+    pub user_quote_ata: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, constraint = base_vault.key() == pool.base_vault)]
+    pub base_vault: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(mut, constraint = quote_vault.key() == pool.quote_vault)]
+    pub quote_vault: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    #[account(address = pool.base_mint)]
+    pub base_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    #[account(address = pool.quote_mint)]
+    pub quote_mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    pub base_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub quote_token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    // The tick arrays crossed by the swap follow as remaining accounts, in swap order
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpgradeAmm<'info> {
+    /// CHECK: laid out as `Amm::V1_LEN`, which `Account` cannot load, checked by the handler
+    #[account(mut, owner = crate::ID)]
+    pub amm: AccountInfo<'info>,
+    #[account(seeds = [b"global_parameters"], bump)]
+    pub global_parameters: Account<'info, GlobalParameters>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpgradeGlobalParameters<'info> {
+    /// CHECK: laid out as `GlobalParameters::V1_LEN`, which `Account` cannot load, checked by the handler
+    #[account(mut, seeds = [b"global_parameters"], bump, owner = crate::ID)]
+    pub global_parameters: AccountInfo<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
@@ -1,4 +1,5 @@
 use crate::error::ErrorCode;
+use crate::{Amm, Referrer};
 use anchor_lang::{
     prelude::*,
     solana_program::{
@@ -7,6 +8,9 @@ use anchor_lang::{
         program::{invoke, invoke_signed},
     },
 };
+use spl_token_2022::extension::{
+    transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions,
+};
 pub struct TokenTransferParams<'a: 'b, 'b> {
     pub source: AccountInfo<'a>,
     pub destination: AccountInfo<'a>,
@@ -77,3 +81,211 @@ pub fn spl_token_transfer(params: TokenTransferParams<'_, '_>) -> Result<()> {
     }
     Ok(result.map_err(|_| ErrorCode::TokenTransferFailed)?)
 }
+
+/// Creates the fee authority's associated token account for `mint`, unless it already exists.
+///
+/// Every pool quoted in `mint` pays its protocol fees to this account, the associated token account
+/// program checks that `fee_receiver_ata` is at the expected address.
+pub fn create_fee_receiver_ata<'a>(
+    payer: AccountInfo<'a>,
+    fee_receiver_ata: AccountInfo<'a>,
+    fee_authority: AccountInfo<'a>,
+    mint: AccountInfo<'a>,
+    token_program: AccountInfo<'a>,
+    system_program: AccountInfo<'a>,
+    associated_token_program: AccountInfo<'a>,
+) -> Result<()> {
+    invoke(
+        &spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            payer.key,
+            fee_authority.key,
+            mint.key,
+            token_program.key,
+        ),
+        &[
+            associated_token_program,
+            payer,
+            fee_receiver_ata,
+            fee_authority,
+            mint,
+            system_program,
+            token_program,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Reloads the reserve vaults after the transfers of an instruction and checks that they still
+/// hold the reserves recorded on the AMM.
+///
+/// Vault balances above the recorded reserves (e.g. donations) are tolerated, they are folded into
+/// the reserves by `sync`. A vault holding less than its recorded reserve means the accounting
+/// drifted and the instruction fails with `ReserveBalanceMismatch`.
+pub fn check_reserve_balances(
+    amm: &Amm,
+    base_reserve_ata: &mut InterfaceAccount<'_, anchor_spl::token_interface::TokenAccount>,
+    quote_reserve_ata: &mut InterfaceAccount<'_, anchor_spl::token_interface::TokenAccount>,
+) -> Result<()> {
+    base_reserve_ata.reload()?;
+    quote_reserve_ata.reload()?;
+    require_gte!(
+        base_reserve_ata.amount,
+        amm.base_reserve,
+        ErrorCode::ReserveBalanceMismatch
+    );
+    require_gte!(
+        quote_reserve_ata.amount,
+        amm.quote_reserve,
+        ErrorCode::ReserveBalanceMismatch
+    );
+    Ok(())
+}
+
+/// Referrer of a swap, passed in `remaining_accounts` as the quote token account receiving its
+/// fee followed by its `Referrer` account.
+pub struct SwapReferrer<'a> {
+    pub token_account: AccountInfo<'a>,
+    pub account: AccountInfo<'a>,
+    pub referrer: Referrer,
+}
+
+impl SwapReferrer<'_> {
+    /// Adds a referred swap to the lifetime counters of the referrer and writes them back.
+    pub fn record(&mut self, quote_amount: u64, fee_amount: u64) -> Result<()> {
+        self.referrer.volume = self
+            .referrer
+            .volume
+            .checked_add(quote_amount as u128)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.referrer.fees_earned = self
+            .referrer
+            .fees_earned
+            .checked_add(fee_amount as u128)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.referrer
+            .try_serialize(&mut &mut self.account.try_borrow_mut_data()?[..])
+    }
+}
+
+/// Loads the referrer of a swap from `remaining_accounts`, `None` when no referrer is passed.
+///
+/// The `Referrer` account must be registered with `register_referrer` and writable, and the token
+/// account must belong to the referrer's owner and hold `quote_mint`.
+pub fn load_referrer<'a>(
+    remaining_accounts: &[AccountInfo<'a>],
+    quote_mint: &Pubkey,
+) -> Result<Option<SwapReferrer<'a>>> {
+    let (token_account, account) = match remaining_accounts {
+        [] => return Ok(None),
+        [token_account, account, ..] => (token_account, account),
+        _ => return err!(ErrorCode::InvalidReferrerAccount),
+    };
+    require_keys_eq!(*account.owner, crate::ID, ErrorCode::InvalidReferrerAccount);
+    require!(account.is_writable, ErrorCode::InvalidReferrerAccount);
+    let referrer = Referrer::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+
+    require!(
+        *token_account.owner == spl_token::ID || *token_account.owner == spl_token_2022::ID,
+        ErrorCode::InvalidReferrerAccount
+    );
+    let token = anchor_spl::token_interface::TokenAccount::try_deserialize(
+        &mut &token_account.try_borrow_data()?[..],
+    )?;
+    require_keys_eq!(token.mint, *quote_mint, ErrorCode::InvalidReferrerMint);
+    require_keys_eq!(
+        token.owner,
+        referrer.owner,
+        ErrorCode::InvalidReferrerAccount
+    );
+
+    Ok(Some(SwapReferrer {
+        token_account: token_account.clone(),
+        account: account.clone(),
+        referrer,
+    }))
+}
+
+/// Amounts moved by a swap, once transfer fees are taken into account.
+///
+/// The user side amounts are what the user actually pays or receives, the reserve side amounts
+/// are what actually enters or leaves the reserve vaults. They only differ when a mint charges a
+/// Token-2022 transfer fee.
+pub struct SwapAmounts {
+    /// Base tokens received by the user on a buy, paid by the user on a sell.
+    pub base_amount: u64,
+    /// Quote tokens paid by the user on a buy, received by the user on a sell.
+    pub quote_amount: u64,
+    /// Base tokens leaving the base reserve on a buy, entering it on a sell.
+    pub base_reserve_amount: u64,
+    /// Quote tokens entering the quote reserve on a buy, leaving it on a sell.
+    pub quote_reserve_amount: u64,
+    /// The part of the swapped quote amount kept in the reserves as the LP fee.
+    pub lp_fee_amount: u64,
+}
+
+/// Reads the `TransferFeeConfig` extension of a mint, if it has one.
+///
+/// Legacy spl_token mints and Token-2022 mints without the extension return `None`.
+fn transfer_fee_config(mint: &AccountInfo) -> Result<Option<TransferFeeConfig>> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(None);
+    }
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    Ok(state.get_extension::<TransferFeeConfig>().ok().copied())
+}
+
+/// Returns the transfer fee withheld by `mint` when `amount` is transferred, for the current epoch.
+pub fn transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    match transfer_fee_config(mint)? {
+        Some(config) => Ok(config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(ErrorCode::MathOverflow)?),
+        None => Ok(0),
+    }
+}
+
+/// Returns the amount received once the transfer fee of `mint` has been withheld from `amount`.
+pub fn amount_after_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    Ok(amount
+        .checked_sub(transfer_fee(mint, amount)?)
+        .ok_or(ErrorCode::MathOverflow)?)
+}
+
+/// Returns the amount to transfer so that `amount` is received after the transfer fee of `mint`.
+pub fn amount_with_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    let fee = match transfer_fee_config(mint)? {
+        Some(config) => config
+            .calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(ErrorCode::MathOverflow)?,
+        None => 0,
+    };
+    Ok(amount.checked_add(fee).ok_or(ErrorCode::MathOverflow)?)
+}
+
+/// Grows a program owned `account` to `len` bytes, zero filling the new space. The `payer` tops
+/// it up to the rent exempt minimum of the new size.
+pub fn grow_account<'a>(
+    account: AccountInfo<'a>,
+    payer: AccountInfo<'a>,
+    system_program: AccountInfo<'a>,
+    len: usize,
+) -> Result<()> {
+    let lamports = Rent::get()?
+        .minimum_balance(len)
+        .saturating_sub(account.lamports());
+    if lamports > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program,
+                anchor_lang::system_program::Transfer {
+                    from: payer,
+                    to: account.clone(),
+                },
+            ),
+            lamports,
+        )?;
+    }
+    account.realloc(len, true)?;
+    Ok(())
+}